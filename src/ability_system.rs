@@ -0,0 +1,264 @@
+use specs::prelude::*;
+use rltk::{Point, BaseMap, Algorithm2D};
+use super::{
+    Abilities, Ability, AbilityEffect, AreaShape, WantsToUseAbility, WantsToSummon, WantsToMove,
+    Poisoned, Regenerating, Rooted, Name, Position, Map, Pools, SufferDamage, RunState, gamelog::GameLog,
+    particle_system::ParticleBuilder, noise_system::{NoiseBuilder, EXPLOSION_NOISE},
+    terrain_system::TerrainBuilder,
+};
+use crate::raws::{RAWS, SpawnType, spawn_named_mob, free_tiles_near};
+
+/// Carries out the special abilities `BehaviourAI` chose this turn. Damage goes through
+/// `SufferDamage` and shows up as particles, just like an item's. Lunges hand their leap on to
+/// `MovementSystem` as a `WantsToMove`, and summons are left as a `WantsToSummon` for
+/// `summon_creatures`, since spawning needs the whole world.
+pub struct AbilitySystem {}
+
+impl<'a> System<'a> for AbilitySystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToUseAbility>,
+        WriteStorage<'a, Abilities>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, Pools>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, Poisoned>,
+        WriteStorage<'a, Regenerating>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, WantsToSummon>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteExpect<'a, NoiseBuilder>,
+        WriteExpect<'a, TerrainBuilder>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities, mut wants_ability, mut abilities, names, positions, map, mut pools,
+            mut suffer_damage, mut poisoned, mut regenerating, mut wants_move, mut wants_summon,
+            mut gamelog, mut particle_builder, mut noise_builder, mut terrain_builder, mut rng
+        ) = data;
+
+        for (entity, use_ability, abilities, name, pos) in (&entities, &wants_ability, &mut abilities, &names, &positions).join() {
+            let ability = if let Some(ability) = abilities.abilities.get_mut(use_ability.ability) { ability } else { continue; };
+            let target = use_ability.target;
+            let target_pos = if let Some(p) = positions.get(target) { Point::new(p.x, p.y) } else { continue; };
+            let target_name = names.get(target).map_or("it".to_string(), |n| n.name.clone());
+            let my_pos = Point::new(pos.x, pos.y);
+
+            // Anything the player sees in use is worth remembering
+            if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                ability.known = true;
+            }
+
+            match &ability.effect {
+                AbilityEffect::Breath{ damage, shape, ignites } => {
+                    gamelog.entries.push(format!("{} uses {}!", name.name, ability.name));
+                    noise_builder.request(target_pos.x, target_pos.y, EXPLOSION_NOISE, entity);
+                    for tile in area_tiles(&map, my_pos, target_pos, *shape, ability.range) {
+                        particle_builder.request(tile.x, tile.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('░'), 200.0);
+                        if *ignites {
+                            terrain_builder.ignite(tile.x, tile.y);
+                        }
+                        for victim in map.tile_content[map.xy_idx(tile.x, tile.y)].iter() {
+                            if *victim == entity || pools.get(*victim).is_none() { continue; }
+                            let amount = rng.roll_dice(damage.0, damage.1) + damage.2;
+                            SufferDamage::new_damage(&mut suffer_damage, *victim, amount, false);
+                            if let Some(victim_name) = names.get(*victim) {
+                                gamelog.entries.push(format!("{} is caught in the blast, taking {} damage.", victim_name.name, amount));
+                            }
+                        }
+                    }
+                }
+                AbilityEffect::Lunge{ damage } => {
+                    // Leap to the space just short of the target, as long as nothing's in the way
+                    let path = rltk::line2d(rltk::LineAlg::Bresenham, my_pos, target_pos);
+                    let landing = path.iter().rev().nth(1).copied().filter(|p| *p != my_pos);
+                    let clear = path.iter().skip(1).take(path.len().saturating_sub(2)).all(|p| !map.blocked[map.xy_idx(p.x, p.y)]);
+                    match landing {
+                        Some(landing) if clear => {
+                            wants_move.insert(entity, WantsToMove{ destination: map.xy_idx(landing.x, landing.y) }).expect("Unable to insert movement");
+                            let amount = rng.roll_dice(damage.0, damage.1) + damage.2;
+                            SufferDamage::new_damage(&mut suffer_damage, target, amount, false);
+                            gamelog.entries.push(format!("{} lunges at {} for {} damage!", name.name, target_name, amount));
+                            particle_builder.request(target_pos.x, target_pos.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 200.0);
+                        }
+                        _ => gamelog.entries.push(format!("{} lunges at {}, but can't reach.", name.name, target_name)),
+                    }
+                }
+                AbilityEffect::Poison{ damage, per_turn, turns } => {
+                    let amount = rng.roll_dice(damage.0, damage.1) + damage.2;
+                    SufferDamage::new_damage(&mut suffer_damage, target, amount, false);
+                    let turns = poisoned.get(target).map_or(*turns, |p| i32::max(p.turns, *turns));
+                    poisoned.insert(target, Poisoned{ damage: *per_turn, turns }).expect("Unable to insert status");
+                    gamelog.entries.push(format!("{} uses {} on {} for {} damage. {} is poisoned!", name.name, ability.name, target_name, amount, target_name));
+                    particle_builder.request(target_pos.x, target_pos.y, rltk::RGB::named(rltk::GREEN), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 200.0);
+                }
+                AbilityEffect::Heal{ amount } => {
+                    if let Some(stats) = pools.get_mut(entity) {
+                        stats.hit_points.current = i32::min(stats.hit_points.max, stats.hit_points.current + amount);
+                    }
+                    gamelog.entries.push(format!("{} uses {}, healing {} hp.", name.name, ability.name, amount));
+                    particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::GREEN), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('♥'), 200.0);
+                }
+                AbilityEffect::Regen{ amount, turns } => {
+                    regenerating.insert(entity, Regenerating{ amount: *amount, turns: *turns }).expect("Unable to insert status");
+                    gamelog.entries.push(format!("{} uses {}. Its wounds begin to close.", name.name, ability.name));
+                    particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::GREEN), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('♥'), 200.0);
+                }
+                AbilityEffect::Summon{ mob, count } => {
+                    wants_summon.insert(entity, WantsToSummon{ mob: mob.clone(), count: *count }).expect("Unable to insert summon");
+                    gamelog.entries.push(format!("{} uses {}!", name.name, ability.name));
+                    particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::MAGENTA), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('☼'), 200.0);
+                }
+            }
+        }
+
+        wants_ability.clear();
+    }
+}
+
+/// The tiles a breath attack from `from`, aimed at `target`, covers.
+fn area_tiles(map: &Map, from: Point, target: Point, shape: AreaShape, range: f32) -> Vec<Point> {
+    let mut tiles = match shape {
+        AreaShape::Ball{ radius } => rltk::field_of_view(target, radius, map),
+        AreaShape::Line => {
+            // Carry on past the target, out to the ability's range, until something solid gets in the way
+            let (dx, dy) = ((target.x - from.x) as f32, (target.y - from.y) as f32);
+            let scale = range / f32::max(1.0, f32::sqrt(dx * dx + dy * dy));
+            let end = Point::new(from.x + (dx * scale).round() as i32, from.y + (dy * scale).round() as i32);
+            rltk::line2d(rltk::LineAlg::Bresenham, from, end).into_iter()
+                .skip(1)
+                .take_while(|p| map.in_bounds(*p) && !map.is_opaque(map.xy_idx(p.x, p.y)))
+                .collect()
+        }
+        AreaShape::Cone => {
+            // Everything in sight which is in range, and within 45 degrees of the target
+            let aim = ((target.x - from.x) as f32, (target.y - from.y) as f32);
+            let aim_length = f32::max(1.0, f32::sqrt(aim.0 * aim.0 + aim.1 * aim.1));
+            rltk::field_of_view(from, range as i32, map).into_iter()
+                .filter(|p| {
+                    let offset = ((p.x - from.x) as f32, (p.y - from.y) as f32);
+                    let length = f32::sqrt(offset.0 * offset.0 + offset.1 * offset.1);
+                    length > 0.0 && length <= range
+                        && (offset.0 * aim.0 + offset.1 * aim.1) / (length * aim_length) >= std::f32::consts::FRAC_1_SQRT_2
+                })
+                .collect()
+        }
+    };
+    tiles.retain(|p| p.x > 0 && p.x < map.width-1 && p.y > 0 && p.y < map.height-1);
+    tiles
+}
+
+/// Brings in whatever was summoned this turn, in the free spaces around the summoner.
+pub fn summon_creatures(ecs: &mut World) {
+    let summons: Vec<(String, i32, Position)> = {
+        let mut wants_summon = ecs.write_storage::<WantsToSummon>();
+        let positions = ecs.read_storage::<Position>();
+        let summons = (&wants_summon, &positions).join().map(|(s, p)| (s.mob.clone(), s.count, p.clone())).collect();
+        wants_summon.clear();
+        summons
+    };
+    let raws = &RAWS.lock().unwrap();
+    for (mob, count, pos) in summons {
+        for (x, y) in free_tiles_near(ecs, pos.x, pos.y, 2).into_iter().take(count as usize) {
+            spawn_named_mob(raws, ecs, &mob, SpawnType::AtPosition{ x, y });
+        }
+    }
+}
+
+/// Applies ongoing poison and regeneration at the start of each of the creature's turns, and
+/// wears down anything holding it in place.
+pub struct StatusEffectSystem {}
+
+impl<'a> System<'a> for StatusEffectSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteStorage<'a, Poisoned>,
+        WriteStorage<'a, Regenerating>,
+        WriteStorage<'a, Rooted>,
+        WriteStorage<'a, Pools>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities, player_entity, runstate, mut poisoned, mut regenerating, mut rooted, mut pools,
+            mut suffer_damage, positions, mut gamelog, mut particle_builder
+        ) = data;
+
+        let my_turn = |entity: Entity| match *runstate {
+            RunState::PlayerTurn => entity == *player_entity,
+            RunState::MonsterTurn => entity != *player_entity,
+            _ => false
+        };
+
+        let mut cured: Vec<Entity> = Vec::new();
+        for (entity, poison) in (&entities, &mut poisoned).join().filter(|(e, _)| my_turn(*e)) {
+            SufferDamage::new_damage(&mut suffer_damage, entity, poison.damage, false);
+            if let Some(pos) = positions.get(entity) {
+                particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::GREEN), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('♣'), 200.0);
+            }
+            poison.turns -= 1;
+            if poison.turns < 1 {
+                cured.push(entity);
+                if entity == *player_entity {
+                    gamelog.entries.push("The poison wears off.".to_string());
+                }
+            } else if entity == *player_entity {
+                gamelog.entries.push(format!("The poison burns, for {} damage.", poison.damage));
+            }
+        }
+        for e in cured { poisoned.remove(e); }
+
+        let mut healed: Vec<Entity> = Vec::new();
+        for (entity, regen, stats) in (&entities, &mut regenerating, &mut pools).join().filter(|(e, _, _)| my_turn(*e)) {
+            stats.hit_points.current = i32::min(stats.hit_points.max, stats.hit_points.current + regen.amount);
+            regen.turns -= 1;
+            if regen.turns < 1 { healed.push(entity); }
+        }
+        for e in healed { regenerating.remove(e); }
+
+        let mut freed: Vec<Entity> = Vec::new();
+        for (entity, root) in (&entities, &mut rooted).join().filter(|(e, _)| my_turn(*e)) {
+            root.turns -= 1;
+            if root.turns < 1 {
+                freed.push(entity);
+                if entity == *player_entity {
+                    gamelog.entries.push("You struggle free of the net.".to_string());
+                }
+            }
+        }
+        for e in freed { rooted.remove(e); }
+    }
+}
+
+/// Describes an ability for tooltips.
+pub fn describe_ability(ability: &Ability) -> String {
+    let dice = |d: &(i32, i32, i32)| if d.2 == 0 { format!("{}d{}", d.0, d.1) } else { format!("{}d{}+{}", d.0, d.1, d.2) };
+    match &ability.effect {
+        AbilityEffect::Breath{ damage, shape, .. } => {
+            let area = match shape {
+                AreaShape::Ball{ .. } => "burst",
+                AreaShape::Line => "line",
+                AreaShape::Cone => "cone",
+            };
+            format!("{}: {} in a {}", ability.name, dice(damage), area)
+        }
+        AbilityEffect::Summon{ mob, .. } => format!("{}: summons {}", ability.name, mob),
+        AbilityEffect::Heal{ amount } => format!("{}: heals {}", ability.name, amount),
+        AbilityEffect::Poison{ damage, .. } => format!("{}: {}, poisons", ability.name, dice(damage)),
+        AbilityEffect::Lunge{ damage } => format!("{}: leaps for {}", ability.name, dice(damage)),
+        AbilityEffect::Regen{ .. } => format!("{}: regenerates", ability.name),
+    }
+}
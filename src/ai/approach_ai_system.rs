@@ -0,0 +1,42 @@
+use specs::prelude::*;
+use crate::{OpensDoors, DoorsShut, MyTurn, WantsToApproach, WantsToMove, Position, Map};
+use super::FlowMaps;
+
+/// Moves creatures one step along the shortest path towards what they want to approach.
+pub struct ApproachAI {}
+
+impl<'a> System<'a> for ApproachAI {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, WantsToApproach>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, WantsToMove>,
+        WriteExpect<'a, FlowMaps>,
+        ReadStorage<'a, OpensDoors>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut turns, mut wants_approach, positions, map, mut wants_move, mut flow_maps, opens_doors) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        for (entity, _turn, approach, pos) in (&entities, &turns, &wants_approach, &positions).join() {
+            turn_done.push(entity);
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let can_open = opens_doors.contains(entity);
+            let doors_shut = DoorsShut(&map);
+            let exits: &dyn rltk::BaseMap = if can_open { &*map } else { &doors_shut };
+            // The target is usually standing on a blocked tile, so path outwards from it instead of towards it
+            let chase_map = flow_maps.towards(&map, &[approach.idx], can_open);
+            if let Some(chase_target) = rltk::DijkstraMap::find_lowest_exit(chase_map, my_idx, exits) {
+                // Don't pace back and forth if there's no way to get any closer
+                if chase_map.map[chase_target] >= chase_map.map[my_idx] { continue; }
+                wants_move.insert(entity, WantsToMove{ destination: chase_target }).expect("Unable to insert move");
+            }
+        }
+
+        for done in turn_done { turns.remove(done); }
+        wants_approach.clear();
+    }
+}
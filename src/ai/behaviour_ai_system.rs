@@ -0,0 +1,419 @@
+use specs::prelude::*;
+use crate::{
+    MyTurn, Faction, Viewshed, Position, Map, Pools, WantsToMelee, WantsToApproach, WantsToFlee,
+    WantsToPickupItem, RemembersPlayer, Behaviour, AiAction, AiScale, AiConditions, Item, Name,
+    gamelog::GameLog, tile_walkable, Sneaking, Attributes, Skills, Skill, skill_check,
+    sneak_difficulty, GroupMember, Routed, Formation, Companion, CompanionOrder, Abilities,
+    AbilityEffect, WantsToUseAbility, NoticedPlayer, clock_system::WorldClock,
+};
+use crate::raws::{RAWS, Reaction, faction_reaction};
+use rltk::Point;
+
+/// How far a call for help carries.
+const CALL_FOR_HELP_RANGE: f32 = 15.0;
+
+/// How far from where it lost the player a creature will search.
+const SEARCH_RADIUS: i32 = 4;
+
+/// The spaces around a creature, in the order a group fills them, so the first few followers end
+/// up on opposite sides.
+const RING: [(i32, i32); 8] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, -1), (1, -1), (-1, 1)];
+
+/// Everything a creature knows about its surroundings when it decides what to do.
+struct Situation {
+    my_pos: Point,
+    nearest_enemy: Option<(Entity, usize, f32)>,
+    afraid_of: Vec<usize>,
+    enemies: Vec<usize>,
+    nearest_item: Option<(Entity, usize, f32)>,
+    ally_visible: bool,
+    health: f32,
+    home_distance: f32,
+    target: Option<Point>,
+    formation_spot: Option<Point>,
+    routed_from: Option<usize>,
+    order: Option<CompanionOrder>,
+}
+
+/// Looks at everything a creature can see, and picks the best option from its raw-defined
+/// `Behaviour`. Options are scored, and the highest scoring one whose conditions are met wins.
+/// Special `Abilities` compete with the options on score, and are passed on as a
+/// `WantsToUseAbility` if they win. Attacks, pickups and calls for help happen immediately; anything involving movement is passed
+/// on to the later stages as a `WantsToFlee` or `WantsToApproach`. Wandering is left to
+/// `DefaultMoveAI`.
+pub struct BehaviourAI {}
+
+impl<'a> System<'a> for BehaviourAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, Pools>,
+        ReadExpect<'a, Entity>,
+        WriteStorage<'a, WantsToMelee>,
+        WriteStorage<'a, WantsToApproach>,
+        WriteStorage<'a, WantsToFlee>,
+        WriteStorage<'a, WantsToPickupItem>,
+        WriteStorage<'a, RemembersPlayer>,
+        WriteStorage<'a, Behaviour>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+        WriteStorage<'a, Sneaking>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
+        ReadStorage<'a, GroupMember>,
+        ReadStorage<'a, Routed>,
+        ReadStorage<'a, Companion>,
+        (WriteStorage<'a, Abilities>, WriteStorage<'a, WantsToUseAbility>),
+        (WriteStorage<'a, NoticedPlayer>, ReadExpect<'a, WorldClock>),
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities, mut turns, factions, viewsheds, positions, map, pools, player_entity,
+            mut wants_melee, mut wants_approach, mut wants_flee, mut wants_pickup,
+            mut remembers_player, mut behaviours, items, names, mut gamelog, mut rng,
+            mut sneaking, attributes, skills, group_members, routed, companions,
+            (mut abilities, mut wants_ability), (mut noticed, clock)
+        ) = data;
+
+        // Anyone who can see the player notices them, unless the player is sneaking; then anyone
+        // who isn't already looking for them has to spot them first.
+        let mut unnoticed: Vec<Entity> = Vec::new();
+        if let Some(player_pos) = positions.get(*player_entity) {
+            let player_pos = Point::new(player_pos.x, player_pos.y);
+            let difficulty = match (sneaking.get(*player_entity), attributes.get(*player_entity), skills.get(*player_entity)) {
+                (Some(_), Some(player_attr), Some(player_skills)) => Some(sneak_difficulty(player_attr, player_skills)),
+                _ => None
+            };
+            for (entity, _turn, viewshed) in (&entities, &turns, &viewsheds).join() {
+                if entity == *player_entity || !viewshed.visible_tiles.contains(&player_pos) { continue; }
+                let searching = remembers_player.get(entity).is_some_and(|r| r.memory > 0);
+                let spotted = match (difficulty, attributes.get(entity), skills.get(entity)) {
+                    (Some(difficulty), Some(attr), Some(sk)) if !searching => skill_check(&mut rng, Skill::Perception, attr, sk, difficulty),
+                    _ => true
+                };
+                if spotted {
+                    noticed.insert(entity, NoticedPlayer{ turn: clock.turn }).expect("Unable to insert noticed");
+                } else {
+                    unnoticed.push(entity);
+                }
+            }
+            // The player only learns from it if they move or attack while still unnoticed
+            if let (false, Some(sneak)) = (unnoticed.is_empty(), sneaking.get_mut(*player_entity)) {
+                sneak.went_unnoticed = true;
+            }
+        }
+
+        let raws = &RAWS.lock().unwrap();
+        let player_faction = factions.get(*player_entity).map_or("Player".to_string(), |f| f.name.clone());
+        let mut turn_done: Vec<Entity> = Vec::new();
+        let mut calls_for_help: Vec<(String, Point, Point)> = Vec::new();
+
+        for (entity, _turn, faction, viewshed, pos, behaviour) in (&entities, &turns, &factions, &viewsheds, &positions, &mut behaviours).join() {
+            // Work out how we feel about everything we can see
+            let my_pos = Point::new(pos.x, pos.y);
+            let mut situation = Situation {
+                my_pos,
+                nearest_enemy: None,
+                afraid_of: Vec::new(),
+                enemies: Vec::new(),
+                nearest_item: None,
+                ally_visible: false,
+                health: pools.get(entity).map_or(1.0, |p| p.hit_points.current as f32 / p.hit_points.max as f32),
+                home_distance: behaviour.home.map_or(0.0, |h| rltk::DistanceAlg::Pythagoras.distance2d(my_pos, Point::new(h.0, h.1))),
+                target: None,
+                formation_spot: None,
+                routed_from: routed.get(entity).map(|r| map.xy_idx(r.from.0, r.from.1)),
+                order: companions.get(entity).map(|c| c.order),
+            };
+            let group_member = group_members.get(entity);
+            if let Some((member, leader_pos)) = group_member.and_then(|m| positions.get(m.leader).map(|p| (m, p))) {
+                let offset = formation_offset(member.formation, member.slot);
+                let (x, y) = (leader_pos.x + offset.0, leader_pos.y + offset.1);
+                situation.formation_spot = Some(if walkable(&map, x, y) { Point::new(x, y) } else { Point::new(leader_pos.x, leader_pos.y) });
+            }
+            let mut player_seen_at: Option<Point> = None;
+            for other_tile in viewshed.visible_tiles.iter() {
+                let view_idx = map.xy_idx(other_tile.x, other_tile.y);
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(my_pos, *other_tile);
+                for other_entity in map.tile_content[view_idx].iter() {
+                    if *other_entity == entity { continue; }
+                    if *other_entity == *player_entity && unnoticed.contains(&entity) { continue; }
+                    if items.get(*other_entity).is_some() && situation.nearest_item.is_none_or(|i| distance < i.2) {
+                        situation.nearest_item = Some((*other_entity, view_idx, distance));
+                    }
+                    let alive = pools.get(*other_entity).is_some_and(|p| p.hit_points.current > 0);
+                    if let (true, Some(other_faction)) = (alive, factions.get(*other_entity)) {
+                        match faction_reaction(&faction.name, &other_faction.name, raws) {
+                            Reaction::Attack => {
+                                if *other_entity == *player_entity { player_seen_at = Some(*other_tile); }
+                                situation.enemies.push(view_idx);
+                                if situation.nearest_enemy.is_none_or(|e| distance < e.2) {
+                                    situation.nearest_enemy = Some((*other_entity, view_idx, distance));
+                                }
+                            }
+                            Reaction::Flee => situation.afraid_of.push(view_idx),
+                            Reaction::Ignore => {
+                                if other_faction.name == faction.name { situation.ally_visible = true; }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Work out where we'd go looking for trouble, if we can't see any
+            if let Some(remembers) = remembers_player.get_mut(entity) {
+                if let Some(seen) = player_seen_at {
+                    // Reset the creature's memory of the player, and note which way they're going.
+                    remembers.memory = i32::max(remembers.max_memory, remembers.memory);
+                    if let Some(last_seen) = remembers.last_seen {
+                        let heading = ((seen.x - last_seen.0).signum(), (seen.y - last_seen.1).signum());
+                        if heading != (0, 0) { remembers.heading = heading; }
+                    }
+                    remembers.last_seen = Some((seen.x, seen.y));
+                } else if remembers.memory < 1 {
+                    remembers.last_seen = None;
+                } else if faction_reaction(&faction.name, &player_faction, raws) == Reaction::Attack {
+                    situation.target = remembers.last_seen.map(|l| Point::new(l.0, l.1));
+                }
+            }
+            if situation.target.is_none() {
+                situation.target = behaviour.alerted.map(|a| Point::new(a.0, a.1));
+            }
+
+            // Score the options
+            let mut best: Option<(usize, f32)> = None;
+            for (i, option) in behaviour.options.iter_mut().enumerate() {
+                if option.cooldown_remaining > 0 {
+                    option.cooldown_remaining -= 1;
+                    continue;
+                }
+                if !conditions_met(&option.conditions, &situation) { continue; }
+                let score = match option.scale {
+                    AiScale::Constant => option.score,
+                    AiScale::MissingHealth => option.score * (1.0 - situation.health),
+                    AiScale::EnemyCloseness => option.score / f32::max(1.0, situation.nearest_enemy.map_or(1.0, |e| e.2)),
+                };
+                if best.is_none_or(|b| score > b.1) {
+                    best = Some((i, score));
+                }
+            }
+            if situation.nearest_enemy.is_some() {
+                // We've found what we were looking for
+                behaviour.alerted = None;
+            }
+
+            // Special abilities are only worth considering if there's something to use them on
+            let mut best_ability: Option<(usize, f32, Entity)> = None;
+            if let Some(abilities) = abilities.get_mut(entity) {
+                for (i, ability) in abilities.abilities.iter_mut().enumerate() {
+                    if ability.cooldown_remaining > 0 {
+                        ability.cooldown_remaining -= 1;
+                        continue;
+                    }
+                    if !conditions_met(&ability.conditions, &situation) { continue; }
+                    let target = if ability.effect.hostile() {
+                        // Lunging at someone who's already next to us is just a bite
+                        let min_range = if matches!(ability.effect, AbilityEffect::Lunge{..}) { 1.5 } else { 0.0 };
+                        situation.nearest_enemy.filter(|e| e.2 <= ability.range && e.2 >= min_range).map(|e| e.0)
+                    } else {
+                        Some(entity)
+                    };
+                    if let Some(target) = target {
+                        if best_ability.is_none_or(|b| ability.score > b.1) {
+                            best_ability = Some((i, ability.score, target));
+                        }
+                    }
+                }
+            }
+            if let Some((i, _, target)) = best_ability.filter(|a| best.is_none_or(|b| a.1 > b.1)) {
+                if let Some(ability) = abilities.get_mut(entity).map(|a| &mut a.abilities[i]) {
+                    ability.cooldown_remaining = ability.cooldown;
+                    behaviour.decision = ability.name.clone();
+                }
+                wants_ability.insert(entity, WantsToUseAbility{ ability: i, target }).expect("Unable to insert ability");
+                turn_done.push(entity);
+                continue;
+            }
+
+            let action = match best {
+                None => AiAction::Wander,
+                Some((i, _)) => {
+                    let option = &mut behaviour.options[i];
+                    option.cooldown_remaining = option.cooldown;
+                    option.action
+                }
+            };
+
+            // Act on the decision
+            behaviour.decision = format!("{:?}", action);
+            match action {
+                AiAction::Attack => {
+                    if let Some(enemy) = situation.nearest_enemy {
+                        wants_melee.insert(entity, WantsToMelee{ target: enemy.0 }).expect("Unable to insert attack");
+                        if let Some(name) = names.get(enemy.0) { behaviour.decision = format!("Attack {}", name.name); }
+                    }
+                    turn_done.push(entity);
+                }
+                AiAction::Chase => {
+                    if let Some(enemy) = situation.nearest_enemy {
+                        wants_approach.insert(entity, WantsToApproach{ idx: enemy.1 }).expect("Unable to insert approach");
+                        if let Some(name) = names.get(enemy.0) { behaviour.decision = format!("Chase {}", name.name); }
+                    }
+                }
+                AiAction::Surround => {
+                    if let Some(enemy) = situation.nearest_enemy {
+                        // Followers make for a different side of the enemy each, while the leader
+                        // goes straight for them.
+                        let mut idx = enemy.1;
+                        if let Some(member) = group_member {
+                            let (dx, dy) = RING[member.slot as usize % RING.len()];
+                            let (x, y) = (enemy.1 as i32 % map.width + dx, enemy.1 as i32 / map.width + dy);
+                            if walkable(&map, x, y) { idx = map.xy_idx(x, y); }
+                        }
+                        wants_approach.insert(entity, WantsToApproach{ idx }).expect("Unable to insert approach");
+                        if let Some(name) = names.get(enemy.0) { behaviour.decision = format!("Surround {}", name.name); }
+                    }
+                }
+                AiAction::Flee => {
+                    let mut indices = situation.afraid_of.clone();
+                    indices.extend(situation.enemies.iter());
+                    indices.extend(situation.routed_from.iter());
+                    wants_flee.insert(entity, WantsToFlee{ indices }).expect("Unable to insert flee");
+                }
+                AiAction::PickUpItem => {
+                    if let Some(item) = situation.nearest_item {
+                        if item.1 == map.xy_idx(pos.x, pos.y) {
+                            wants_pickup.insert(entity, WantsToPickupItem{ collected_by: entity, item: item.0 }).expect("Unable to insert pickup");
+                            turn_done.push(entity);
+                        } else {
+                            wants_approach.insert(entity, WantsToApproach{ idx: item.1 }).expect("Unable to insert approach");
+                        }
+                    }
+                }
+                AiAction::CallForHelp => {
+                    if let Some(enemy) = situation.nearest_enemy {
+                        let enemy_pos = Point::new(enemy.1 as i32 % map.width, enemy.1 as i32 / map.width);
+                        calls_for_help.push((faction.name.clone(), my_pos, enemy_pos));
+                        if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                            if let Some(name) = names.get(entity) {
+                                gamelog.entries.push(format!("{} calls for help!", name.name));
+                            }
+                        }
+                    }
+                    turn_done.push(entity);
+                }
+                AiAction::Guard => {
+                    if let Some(home) = behaviour.home {
+                        wants_approach.insert(entity, WantsToApproach{ idx: map.xy_idx(home.0, home.1) }).expect("Unable to insert approach");
+                    }
+                }
+                AiAction::Hunt => {
+                    if let Some(mut target) = situation.target {
+                        let arrived = rltk::DistanceAlg::Pythagoras.distance2d(my_pos, target) < 1.5;
+                        let remembers = remembers_player.get_mut(entity)
+                            .filter(|r| r.last_seen == Some((target.x, target.y)));
+                        if let Some(remembers) = remembers {
+                            // Follow the player's trail, and search around where they went
+                            remembers.memory -= 1;
+                            if arrived {
+                                target = search_point(&map, &mut rng, target, remembers.heading);
+                                remembers.heading = (0, 0);
+                                remembers.last_seen = Some((target.x, target.y));
+                                behaviour.decision = "Search".to_string();
+                            }
+                        } else {
+                            behaviour.decision = "Investigate".to_string();
+                            if arrived {
+                                // We've arrived, and there's nothing here
+                                behaviour.alerted = None;
+                            }
+                        }
+                        wants_approach.insert(entity, WantsToApproach{ idx: map.xy_idx(target.x, target.y) }).expect("Unable to insert approach");
+                    }
+                }
+                AiAction::Follow => {
+                    if let Some(spot) = situation.formation_spot {
+                        wants_approach.insert(entity, WantsToApproach{ idx: map.xy_idx(spot.x, spot.y) }).expect("Unable to insert approach");
+                    }
+                }
+                AiAction::Wander => {}
+                AiAction::Idle => turn_done.push(entity),
+            }
+        }
+
+        // Anyone nearby on the same side comes to have a look
+        for (faction_name, caller_pos, enemy_pos) in calls_for_help {
+            for (faction, pos, behaviour) in (&factions, &positions, &mut behaviours).join() {
+                if faction.name == faction_name
+                    && rltk::DistanceAlg::Pythagoras.distance2d(caller_pos, Point::new(pos.x, pos.y)) <= CALL_FOR_HELP_RANGE
+                {
+                    behaviour.alerted = Some((enemy_pos.x, enemy_pos.y));
+                }
+            }
+        }
+
+        for done in turn_done { turns.remove(done); }
+    }
+}
+
+fn conditions_met(conditions: &AiConditions, situation: &Situation) -> bool {
+    let enemy_adjacent = situation.nearest_enemy.is_some_and(|e| e.2 < 1.5);
+    let fear_visible = !situation.afraid_of.is_empty();
+    conditions.enemy_adjacent.is_none_or(|c| c == enemy_adjacent)
+        && conditions.enemy_visible.is_none_or(|c| c == situation.nearest_enemy.is_some())
+        && conditions.fear_visible.is_none_or(|c| c == fear_visible)
+        && conditions.ally_visible.is_none_or(|c| c == situation.ally_visible)
+        && conditions.item_visible.is_none_or(|c| c == situation.nearest_item.is_some())
+        && conditions.remembers_target.is_none_or(|c| c == situation.target.is_some())
+        && conditions.health_below.is_none_or(|h| situation.health < h)
+        && conditions.away_from_home.is_none_or(|d| situation.home_distance > d as f32)
+        && conditions.out_of_formation.is_none_or(|d| situation.formation_spot.is_some_and(|s| {
+            rltk::DistanceAlg::Pythagoras.distance2d(situation.my_pos, s) > d as f32
+        }))
+        && conditions.routed.is_none_or(|c| c == situation.routed_from.is_some())
+        && conditions.order.is_none_or(|o| situation.order == Some(o))
+}
+
+/// Where the follower in `slot` belongs, relative to its leader.
+fn formation_offset(formation: Formation, slot: i32) -> (i32, i32) {
+    match formation {
+        Formation::Line => {
+            let side = if slot % 2 == 0 { 1 } else { -1 };
+            (side * (slot / 2 + 1), 0)
+        }
+        Formation::Pack => {
+            let (dx, dy) = RING[slot as usize % RING.len()];
+            let distance = slot / RING.len() as i32 + 1;
+            (dx * distance, dy * distance)
+        }
+    }
+}
+
+fn walkable(map: &Map, x: i32, y: i32) -> bool {
+    x > 0 && x < map.width-1 && y > 0 && y < map.height-1 && tile_walkable(map.tiles[map.xy_idx(x, y)])
+}
+
+/// Picks somewhere to look next for a target which was lost at `from`. If we know which way they
+/// were heading we look further along that way, otherwise we try somewhere nearby.
+fn search_point(map: &Map, rng: &mut rltk::RandomNumberGenerator, from: Point, heading: (i32, i32)) -> Point {
+    if heading != (0, 0) {
+        for distance in (1 ..= SEARCH_RADIUS).rev() {
+            let (x, y) = (from.x + heading.0 * distance, from.y + heading.1 * distance);
+            if walkable(map, x, y) { return Point::new(x, y); }
+        }
+    }
+    for _ in 0..10 {
+        let x = from.x + rng.range(-SEARCH_RADIUS, SEARCH_RADIUS + 1);
+        let y = from.y + rng.range(-SEARCH_RADIUS, SEARCH_RADIUS + 1);
+        if walkable(map, x, y) { return Point::new(x, y); }
+    }
+    from
+}
@@ -0,0 +1,49 @@
+use specs::prelude::*;
+use crate::{MyTurn, MoveMode, Movement, WantsToMove, Position, Map};
+use rltk::Point;
+
+/// Moves creatures which have nothing better to do, according to their `MoveMode`.
+pub struct DefaultMoveAI {}
+
+impl<'a> System<'a> for DefaultMoveAI {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, MoveMode>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        WriteStorage<'a, WantsToMove>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut turns, move_modes, positions, map, player_pos, mut wants_move, mut rng) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        for (entity, _turn, move_mode, pos) in (&entities, &turns, &move_modes, &positions).join() {
+            turn_done.push(entity);
+            match move_mode.mode {
+                Movement::Static => {}
+                Movement::Random => {
+                    let mut x = pos.x;
+                    let mut y = pos.y;
+                    match rng.roll_dice(1, 5) {
+                        1 => x -= 1,
+                        2 => x += 1,
+                        3 => y -= 1,
+                        4 => y += 1,
+                        _ => {}
+                    }
+
+                    // Don't wander out of bounds, or into the player
+                    if x > 0 && x < map.width-1 && y > 0 && y < map.height-1 && !(x == player_pos.x && y == player_pos.y) {
+                        wants_move.insert(entity, WantsToMove{ destination: map.xy_idx(x, y) }).expect("Unable to insert move");
+                    }
+                }
+            }
+        }
+
+        for done in turn_done { turns.remove(done); }
+    }
+}
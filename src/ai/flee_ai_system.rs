@@ -0,0 +1,39 @@
+use specs::prelude::*;
+use crate::{OpensDoors, DoorsShut, MyTurn, WantsToFlee, WantsToMove, Position, Map};
+use super::FlowMaps;
+
+/// Moves creatures which want to flee away from whatever they're afraid of.
+pub struct FleeAI {}
+
+impl<'a> System<'a> for FleeAI {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, WantsToFlee>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, WantsToMove>,
+        WriteExpect<'a, FlowMaps>,
+        ReadStorage<'a, OpensDoors>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut turns, mut wants_flee, positions, map, mut wants_move, mut flow_maps, opens_doors) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        for (entity, _turn, flee, pos) in (&entities, &turns, &wants_flee, &positions).join() {
+            turn_done.push(entity);
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let can_open = opens_doors.contains(entity);
+            let doors_shut = DoorsShut(&map);
+            let exits: &dyn rltk::BaseMap = if can_open { &*map } else { &doors_shut };
+            let flee_map = flow_maps.towards(&map, &flee.indices, can_open);
+            if let Some(flee_target) = rltk::DijkstraMap::find_highest_exit(flee_map, my_idx, exits) {
+                wants_move.insert(entity, WantsToMove{ destination: flee_target }).expect("Unable to insert move");
+            }
+        }
+
+        for done in turn_done { turns.remove(done); }
+        wants_flee.clear();
+    }
+}
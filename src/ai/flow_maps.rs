@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use crate::{Map, DoorsShut};
+
+/// How far from their targets flow maps are calculated.
+const FLOW_MAP_DEPTH: f32 = 100.0;
+
+/// Dijkstra flow maps built during the current turn, keyed by the tiles they flow towards.
+/// Building a flow map is by far the most expensive thing the AI does, so creatures heading
+/// towards (or running from) the same tiles share a single map rather than each building
+/// their own. `InitiativeSystem` empties the cache at the start of every turn, since the map
+/// changes as everything moves around.
+pub struct FlowMaps {
+    maps: HashMap<(Vec<usize>, bool), rltk::DijkstraMap>,
+}
+
+impl FlowMaps {
+    pub fn new() -> FlowMaps {
+        FlowMaps{ maps: HashMap::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.maps.clear();
+    }
+
+    /// Returns a flow map leading towards the nearest of `targets`. Following it downhill
+    /// approaches them, and following it uphill runs away from them. Creatures which can't open
+    /// doors get a map which leads around closed doors, rather than through them.
+    pub fn towards(&mut self, map: &Map, targets: &[usize], opens_doors: bool) -> &rltk::DijkstraMap {
+        let mut key = targets.to_vec();
+        key.sort_unstable();
+        key.dedup();
+        self.maps.entry((key, opens_doors)).or_insert_with_key(|(key, opens_doors)| {
+            if *opens_doors {
+                rltk::DijkstraMap::new(map.width as usize, map.height as usize, key, map, FLOW_MAP_DEPTH)
+            } else {
+                rltk::DijkstraMap::new(map.width as usize, map.height as usize, key, &DoorsShut(map), FLOW_MAP_DEPTH)
+            }
+        })
+    }
+}
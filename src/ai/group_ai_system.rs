@@ -0,0 +1,57 @@
+use specs::prelude::*;
+use crate::{GroupMember, Routed, MyTurn, Pools, Position, Name, Map, gamelog::GameLog};
+
+/// How long a routed creature keeps running.
+const ROUT_TURNS: i32 = 10;
+
+/// Looks after the morale of groups. When a group's leader dies, each follower either holds its
+/// nerve and carries on alone, or is routed and runs away from where the leader fell for a while.
+pub struct GroupAI {}
+
+impl<'a> System<'a> for GroupAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, GroupMember>,
+        WriteStorage<'a, Routed>,
+        ReadStorage<'a, MyTurn>,
+        ReadStorage<'a, Pools>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut members, mut routed, turns, pools, positions, names, map, mut gamelog, mut rng) = data;
+
+        let mut leaderless: Vec<(Entity, Entity, i32)> = Vec::new();
+        for (entity, member) in (&entities, &members).join() {
+            let leader_alive = entities.is_alive(member.leader)
+                && pools.get(member.leader).is_some_and(|p| p.hit_points.current > 0);
+            if !leader_alive { leaderless.push((entity, member.leader, member.morale)); }
+        }
+
+        for (entity, leader, morale) in leaderless {
+            members.remove(entity);
+            if rng.roll_dice(1, 100) <= morale { continue; }
+            let pos = if let Some(pos) = positions.get(entity) { pos } else { continue; };
+            let from = positions.get(leader).map_or((pos.x, pos.y), |l| (l.x, l.y));
+            routed.insert(entity, Routed{ turns: ROUT_TURNS, from }).expect("Unable to insert routed");
+            if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                if let Some(name) = names.get(entity) {
+                    gamelog.entries.push(format!("{} panics and flees!", name.name));
+                }
+            }
+        }
+
+        // Routed creatures calm down after a while
+        let mut calmed: Vec<Entity> = Vec::new();
+        for (entity, rout, _turn) in (&entities, &mut routed, &turns).join() {
+            rout.turns -= 1;
+            if rout.turns < 1 { calmed.push(entity); }
+        }
+        for entity in calmed { routed.remove(entity); }
+    }
+}
@@ -0,0 +1,30 @@
+use specs::prelude::*;
+use crate::{MyTurn, Faction, Player, Asleep, RunState};
+use super::FlowMaps;
+
+/// Gives every AI-controlled creature which is awake its turn on the monsters' turn.
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Asleep>,
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, FlowMaps>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut turns, factions, players, asleep, runstate, mut flow_maps) = data;
+
+        turns.clear();
+        flow_maps.clear();
+        if *runstate != RunState::MonsterTurn { return; }
+
+        for (entity, _faction, _player, _asleep) in (&entities, &factions, !&players, !&asleep).join() {
+            turns.insert(entity, MyTurn{}).expect("Unable to insert turn");
+        }
+    }
+}
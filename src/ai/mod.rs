@@ -0,0 +1,37 @@
+//! The AI runs as a pipeline of small systems, each handling one stage of a creature's turn:
+//! * `InitiativeSystem` decides who gets to act, and gives them `MyTurn`.
+//! * `TurnStatusSystem` takes the turn away from anyone who can't act, e.g. because they're stunned.
+//! * `ScheduleSystem` sends townsfolk wherever their daily routine says they should be.
+//! * `GroupAI` keeps up the morale of groups, routing followers whose leader has died.
+//! * `BehaviourAI` looks at what each creature can see, and scores the options in its raw-defined
+//!   `Behaviour`. It acts on the best one, or records what it wants to do about it.
+//!   Special abilities compete with those options, and are carried out by `AbilitySystem`.
+//! * The decision systems (`FleeAI`, `ApproachAI`, `DefaultMoveAI`) turn those wants into a `WantsToMove`,
+//!   sharing the turn's `FlowMaps` so each flow map is only built once.
+//! * `MovementSystem` applies every `WantsToMove`, keeping the map's blocked tiles up to date.
+//!
+//! A system which acts on a creature's turn removes its `MyTurn`, so later stages leave it alone.
+//! New behaviours should be added as a new decision component and system.
+
+mod flow_maps;
+pub use flow_maps::FlowMaps;
+mod initiative_system;
+pub use initiative_system::InitiativeSystem;
+mod turn_status_system;
+pub use turn_status_system::TurnStatusSystem;
+mod quip_system;
+pub use quip_system::QuipSystem;
+mod schedule_system;
+pub use schedule_system::ScheduleSystem;
+mod group_ai_system;
+pub use group_ai_system::GroupAI;
+mod behaviour_ai_system;
+pub use behaviour_ai_system::BehaviourAI;
+mod flee_ai_system;
+pub use flee_ai_system::FleeAI;
+mod approach_ai_system;
+pub use approach_ai_system::ApproachAI;
+mod default_move_system;
+pub use default_move_system::DefaultMoveAI;
+mod movement_system;
+pub use movement_system::MovementSystem;
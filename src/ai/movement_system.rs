@@ -0,0 +1,74 @@
+use specs::prelude::*;
+use crate::{
+    WantsToMove, Position, Map, Viewshed, EntityMoved, Door, Locked, OpensDoors, BlocksTile,
+    BlocksVisibility, Renderable, Rooted, noise_system::{NoiseBuilder, DOOR_NOISE},
+};
+
+/// Applies every creature's `WantsToMove`, as long as nothing is standing in the way, and keeps
+/// the map's record of blocked tiles up to date. Creatures who can open doors spend their move
+/// opening any closed door in their way; those who can't, stay put. So do creatures who are
+/// rooted to the spot.
+pub struct MovementSystem {}
+
+impl<'a> System<'a> for MovementSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, Position>,
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, EntityMoved>,
+        WriteStorage<'a, Door>,
+        ReadStorage<'a, Locked>,
+        ReadStorage<'a, OpensDoors>,
+        WriteStorage<'a, BlocksTile>,
+        WriteStorage<'a, BlocksVisibility>,
+        WriteStorage<'a, Renderable>,
+        ReadStorage<'a, Rooted>,
+        WriteExpect<'a, NoiseBuilder>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities, mut wants_move, mut positions, mut map, mut viewsheds, mut entity_moved, mut doors,
+            locked, opens_doors, mut blocks_tile, mut blocks_visibility, mut renderables, rooted, mut noise_builder
+        ) = data;
+
+        let mut opened_door = false;
+        for (entity, movement, pos, _) in (&entities, &wants_move, &mut positions, !&rooted).join() {
+            let closed_door = map.tile_content[movement.destination].iter()
+                .find(|e| doors.get(**e).is_some_and(|d| !d.open))
+                .copied();
+            if let Some(door) = closed_door {
+                if opens_doors.contains(entity) && !locked.contains(door) {
+                    crate::doors::open_door(door, &mut doors, &mut blocks_tile, &mut blocks_visibility, &mut renderables);
+                    map.blocked[movement.destination] = false;
+                    noise_builder.request(movement.destination as i32 % map.width, movement.destination as i32 / map.width, DOOR_NOISE, entity);
+                    opened_door = true;
+                }
+                continue;
+            }
+            if map.blocked[movement.destination] { continue; }
+
+            let start_idx = map.xy_idx(pos.x, pos.y);
+            map.blocked[start_idx] = false;
+            pos.x = movement.destination as i32 % map.width;
+            pos.y = movement.destination as i32 / map.width;
+            map.blocked[movement.destination] = true;
+            if let Some(viewshed) = viewsheds.get_mut(entity) {
+                viewshed.dirty = true;
+            }
+            entity_moved.insert(entity, EntityMoved{}).expect("Unable to insert marker");
+        }
+
+        // Everyone might be able to see through the door now
+        if opened_door {
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+
+        wants_move.clear();
+    }
+}
@@ -0,0 +1,30 @@
+use specs::prelude::*;
+use crate::{MyTurn, Quips, Name, Viewshed, gamelog::GameLog};
+use rltk::Point;
+
+/// Lets creatures which can see the player say something to them now and again.
+pub struct QuipSystem {}
+
+impl<'a> System<'a> for QuipSystem {
+    type SystemData = (
+        ReadStorage<'a, MyTurn>,
+        WriteStorage<'a, Quips>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Viewshed>,
+        ReadExpect<'a, Point>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (turns, mut quips, names, viewsheds, player_pos, mut gamelog, mut rng) = data;
+
+        for (quip, name, viewshed, _turn) in (&mut quips, &names, &viewsheds, &turns).join() {
+            if !quip.available.is_empty() && viewshed.visible_tiles.contains(&player_pos) && rng.roll_dice(1,6)==1 {
+                let quip_index = if quip.available.len() == 1 { 0 } else { (rng.roll_dice(1, quip.available.len() as i32)-1) as usize };
+                gamelog.entries.push(format!("{} says \"{}\"", name.name, quip.available[quip_index]));
+                quip.available.remove(quip_index);
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use specs::prelude::*;
+use crate::{Schedule, Behaviour, Map, Rect, tile_walkable, clock_system::WorldClock};
+
+/// Keeps townsfolk to their daily routine. Whenever the hour comes for someone to be somewhere
+/// else, they pick a spot in that building and make it their `Behaviour`'s home, so that
+/// `BehaviourAI` takes them there. Outside their scheduled hours they're free to wander.
+pub struct ScheduleSystem {}
+
+impl<'a> System<'a> for ScheduleSystem {
+    type SystemData = (
+        WriteStorage<'a, Schedule>,
+        WriteStorage<'a, Behaviour>,
+        ReadExpect<'a, WorldClock>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut schedules, mut behaviours, clock, map, mut rng) = data;
+
+        let hour = clock.hour();
+        for (schedule, behaviour) in (&mut schedules, &mut behaviours).join() {
+            let place = schedule.slots.iter()
+                .find(|s| if s.from <= s.to { hour >= s.from && hour < s.to } else { hour >= s.from || hour < s.to })
+                .map(|s| s.place.as_str());
+            let building = match place {
+                None => None,
+                Some("Home") => schedule.home,
+                Some(tag) => map.buildings.iter().position(|b| b.0 == tag),
+            };
+            if building == schedule.current { continue; }
+
+            schedule.current = building;
+            behaviour.home = building.map(|b| spot_in(&map, &map.buildings[b].1, &mut rng));
+        }
+    }
+}
+
+/// Finds somewhere to stand inside a building.
+fn spot_in(map: &Map, building: &Rect, rng: &mut rltk::RandomNumberGenerator) -> (i32, i32) {
+    for _ in 0..20 {
+        let x = rng.range(building.x1 + 1, building.x2 - 1);
+        let y = rng.range(building.y1 + 1, building.y2 - 1);
+        if tile_walkable(map.tiles[map.xy_idx(x, y)]) { return (x, y); }
+    }
+    building.center()
+}
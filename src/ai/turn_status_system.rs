@@ -0,0 +1,40 @@
+use specs::prelude::*;
+use crate::{MyTurn, Stunned, Position, particle_system::ParticleBuilder};
+
+/// Takes away the turn of any creature which isn't able to act.
+pub struct TurnStatusSystem {}
+
+impl<'a> System<'a> for TurnStatusSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, Stunned>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, ParticleBuilder>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut turns, mut stunned, positions, mut particle_builder) = data;
+
+        let mut not_my_turn: Vec<Entity> = Vec::new();
+        let mut no_longer_stunned: Vec<Entity> = Vec::new();
+        for (entity, _turn, i_am_stunned, pos) in (&entities, &turns, &mut stunned, &positions).join() {
+            not_my_turn.push(entity);
+            i_am_stunned.turns -= 1;
+            if i_am_stunned.turns < 1 {
+                no_longer_stunned.push(entity);
+            }
+            particle_builder.request(
+                pos.x,
+                pos.y,
+                rltk::RGB::named(rltk::MAGENTA),
+                rltk::RGB::named(rltk::BLACK),
+                rltk::to_cp437('?'),
+                200.0
+            );
+        }
+
+        for e in not_my_turn { turns.remove(e); }
+        for e in no_longer_stunned { stunned.remove(e); }
+    }
+}
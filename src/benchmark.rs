@@ -0,0 +1,76 @@
+//! A headless benchmark of how long a turn takes on a large map crowded with creatures, to keep
+//! an eye on the cost of the AI. Run it with `cargo run --release -- --benchmark`.
+use specs::prelude::*;
+use std::time::{Duration, Instant};
+use super::{State, Map, Position, Pools, Asleep, RunState, map_builders, damage_system, tile_walkable};
+use crate::raws::{RAWS, SpawnType, spawn_named_entity};
+use rltk::Point;
+
+const MAP_WIDTH: i32 = 200;
+const MAP_HEIGHT: i32 = 200;
+const MOB_COUNT: usize = 250;
+const TURNS: usize = 100;
+const MOBS: [&str; 8] = ["Goblin", "Orc", "Kobold", "Mangy Wolf", "Fox", "Deer", "Rat", "Bandit"];
+
+pub fn run(gs: &mut State) {
+    gs.ecs.insert(rltk::RandomNumberGenerator::seeded(1));
+
+    // Build a big open cave, and put the player in the middle of it
+    let mut builder = map_builders::benchmark_builder(MAP_WIDTH, MAP_HEIGHT);
+    {
+        let mut rng = gs.ecs.write_resource::<rltk::RandomNumberGenerator>();
+        builder.build_map(&mut rng);
+    }
+    let start = builder.build_data.starting_position.clone().unwrap();
+    *gs.ecs.write_resource::<Map>() = builder.build_data.map.clone();
+    *gs.ecs.write_resource::<Point>() = Point::new(start.x, start.y);
+    {
+        let player_entity = *gs.ecs.fetch::<Entity>();
+        let mut positions = gs.ecs.write_storage::<Position>();
+        positions.insert(player_entity, Position{ x: start.x, y: start.y }).expect("Unable to place player");
+    }
+
+    // Fill it with creatures, all of them awake
+    let mut spawn_points: Vec<(i32, i32)> = Vec::new();
+    {
+        let map = gs.ecs.fetch::<Map>();
+        let mut rng = gs.ecs.write_resource::<rltk::RandomNumberGenerator>();
+        while spawn_points.len() < MOB_COUNT {
+            let x = rng.range(1, map.width - 1);
+            let y = rng.range(1, map.height - 1);
+            if tile_walkable(map.tiles[map.xy_idx(x, y)]) && (x, y) != (start.x, start.y) && !spawn_points.contains(&(x, y)) {
+                spawn_points.push((x, y));
+            }
+        }
+    }
+    for (i, (x, y)) in spawn_points.iter().enumerate() {
+        spawn_named_entity(&RAWS.lock().unwrap(), &mut gs.ecs, MOBS[i % MOBS.len()], SpawnType::AtPosition{ x: *x, y: *y });
+    }
+    gs.ecs.write_storage::<Asleep>().clear();
+
+    // Run the game, timing each of the monsters' turns
+    let mut timings: Vec<Duration> = Vec::new();
+    for turn in 0 .. TURNS * 2 {
+        let monster_turn = turn % 2 == 1;
+        *gs.ecs.write_resource::<RunState>() = if monster_turn { RunState::MonsterTurn } else { RunState::PlayerTurn };
+        let started = Instant::now();
+        gs.run_systems();
+        damage_system::delete_the_dead(&mut gs.ecs);
+        if monster_turn { timings.push(started.elapsed()); }
+
+        // Keep the player alive, so the creatures have something to chase
+        let player_entity = *gs.ecs.fetch::<Entity>();
+        if let Some(pools) = gs.ecs.write_storage::<Pools>().get_mut(player_entity) {
+            pools.hit_points.current = pools.hit_points.max;
+        }
+    }
+
+    let total: Duration = timings.iter().sum();
+    timings.sort();
+    let creatures = gs.ecs.read_storage::<Pools>().count();
+    println!("{} monster turns on a {}x{} map, starting with {} creatures and finishing with {}:",
+        timings.len(), MAP_WIDTH, MAP_HEIGHT, MOB_COUNT, creatures);
+    println!("  mean   {:?}", total / timings.len() as u32);
+    println!("  median {:?}", timings[timings.len() / 2]);
+    println!("  worst  {:?}", timings[timings.len() - 1]);
+}
@@ -0,0 +1,55 @@
+use specs::prelude::*;
+use super::{BossPhases, Pools, Behaviour, Abilities, Position, gamelog::GameLog, particle_system::ParticleBuilder};
+
+/// Moves bosses on to their next phase once they've taken enough damage. A phase can swap the
+/// boss's behaviour and give it new abilities. A big enough hit can run through several phases at
+/// once.
+pub struct BossPhaseSystem {}
+
+impl<'a> System<'a> for BossPhaseSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, BossPhases>,
+        ReadStorage<'a, Pools>,
+        WriteStorage<'a, Behaviour>,
+        WriteStorage<'a, Abilities>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut boss_phases, pools, mut behaviours, mut abilities, positions, mut gamelog, mut particle_builder) = data;
+
+        for (entity, phases, stats) in (&entities, &mut boss_phases, &pools).join() {
+            if stats.hit_points.current < 1 { continue; }
+            let health = stats.hit_points.current as f32 / stats.hit_points.max as f32;
+            while phases.next < phases.phases.len() && health < phases.phases[phases.next].health_below {
+                let phase = phases.phases[phases.next].clone();
+                phases.next += 1;
+
+                if let Some(message) = phase.message {
+                    gamelog.entries.push(message);
+                }
+                if let (Some(new_behaviour), Some(behaviour)) = (phase.behaviour, behaviours.get_mut(entity)) {
+                    // It still remembers where it lives, and what it was looking for
+                    let (home, alerted) = (behaviour.home, behaviour.alerted);
+                    *behaviour = new_behaviour;
+                    behaviour.home = home;
+                    behaviour.alerted = alerted;
+                }
+                if !phase.abilities.is_empty() {
+                    if let Some(known) = abilities.get_mut(entity) {
+                        known.abilities.extend(phase.abilities);
+                    } else {
+                        abilities.insert(entity, Abilities{ abilities: phase.abilities }).expect("Unable to insert abilities");
+                    }
+                }
+                if let Some(pos) = positions.get(entity) {
+                    particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::RED), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('!'), 400.0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+/// Every attribute starts at this value before any points are spent.
+pub const BASE_ATTRIBUTE: i32 = 8;
+/// The highest an attribute can be raised to during character creation.
+pub const MAX_ATTRIBUTE: i32 = 16;
+/// Points available to spend on attributes.
+pub const ATTRIBUTE_POINTS: i32 = 12;
+/// Raising an attribute above this value costs double.
+const EXPENSIVE_ATTRIBUTE: i32 = 14;
+
+pub const ATTRIBUTE_NAMES: [&str; 4] = ["Might", "Fitness", "Quickness", "Intelligence"];
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CreationStage { Attributes, Background, Name }
+
+/// The choices the player has made on the character creation screen.
+pub struct NewCharacter {
+    pub stage: CreationStage,
+    /// Might, fitness, quickness and intelligence, in that order
+    pub attributes: [i32; 4],
+    pub selected_attribute: usize,
+    /// Index into the raw-defined backgrounds
+    pub background: usize,
+    pub name: String,
+}
+
+impl NewCharacter {
+    /// Creates an average character, with all points spent evenly.
+    pub fn new() -> NewCharacter {
+        let even = BASE_ATTRIBUTE + ATTRIBUTE_POINTS / 4;
+        NewCharacter {
+            stage: CreationStage::Attributes,
+            attributes: [even; 4],
+            selected_attribute: 0,
+            background: 0,
+            name: String::new(),
+        }
+    }
+
+    pub fn might(&self) -> i32 { self.attributes[0] }
+    pub fn fitness(&self) -> i32 { self.attributes[1] }
+    pub fn quickness(&self) -> i32 { self.attributes[2] }
+    pub fn intelligence(&self) -> i32 { self.attributes[3] }
+
+    /// The name the character will be spawned with.
+    pub fn display_name(&self) -> String {
+        if self.name.is_empty() { "Player".to_string() } else { self.name.clone() }
+    }
+
+    /// How many points it costs to raise an attribute from the base value to `value`.
+    pub fn point_cost(value: i32) -> i32 {
+        let cheap = i32::min(value, EXPENSIVE_ATTRIBUTE) - BASE_ATTRIBUTE;
+        let expensive = i32::max(0, value - EXPENSIVE_ATTRIBUTE) * 2;
+        cheap + expensive
+    }
+
+    pub fn points_remaining(&self) -> i32 {
+        ATTRIBUTE_POINTS - self.attributes.iter().map(|a| NewCharacter::point_cost(*a)).sum::<i32>()
+    }
+
+    /// Raises the given attribute by one, if there are enough points left.
+    pub fn raise(&mut self, attribute: usize) -> bool {
+        let current = self.attributes[attribute];
+        if current >= MAX_ATTRIBUTE { return false; }
+        let cost = NewCharacter::point_cost(current + 1) - NewCharacter::point_cost(current);
+        if cost > self.points_remaining() { return false; }
+        self.attributes[attribute] += 1;
+        true
+    }
+
+    /// Lowers the given attribute by one, refunding its points.
+    pub fn lower(&mut self, attribute: usize) -> bool {
+        if self.attributes[attribute] <= BASE_ATTRIBUTE { return false; }
+        self.attributes[attribute] -= 1;
+        true
+    }
+}
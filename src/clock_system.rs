@@ -0,0 +1,55 @@
+use specs::prelude::*;
+use serde::{Serialize, Deserialize};
+use super::RunState;
+
+/// How many turns make up an hour of game time, i.e. each turn is five minutes.
+pub const TURNS_PER_HOUR: i32 = 12;
+
+/// The hour of the day a new game starts at.
+const START_HOUR: i32 = 8;
+
+/// The time of day in the game world, counted in player turns.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct WorldClock {
+    pub turn: i32,
+}
+
+impl WorldClock {
+    pub fn new() -> WorldClock {
+        WorldClock{ turn: START_HOUR * TURNS_PER_HOUR }
+    }
+
+    /// The hour of the day, from 0 to 23.
+    pub fn hour(&self) -> i32 {
+        (self.turn / TURNS_PER_HOUR) % 24
+    }
+
+    /// Which day it is, starting at day 1.
+    pub fn day(&self) -> i32 {
+        self.turn / (TURNS_PER_HOUR * 24) + 1
+    }
+
+    /// The time, formatted as e.g. "Day 1, 08:05".
+    pub fn time_of_day(&self) -> String {
+        let minutes = (self.turn % TURNS_PER_HOUR) * 60 / TURNS_PER_HOUR;
+        format!("Day {}, {:02}:{:02}", self.day(), self.hour(), minutes)
+    }
+}
+
+/// Moves the clock on every time the player takes a turn.
+pub struct ClockSystem {}
+
+impl<'a> System<'a> for ClockSystem {
+    type SystemData = (
+        WriteExpect<'a, WorldClock>,
+        ReadExpect<'a, RunState>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut clock, runstate) = data;
+
+        if *runstate == RunState::PlayerTurn {
+            clock.turn += 1;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use specs::prelude::*;
+use rltk::Point;
+use super::{Companion, CompanionOrder, GroupMember, Behaviour, Position, Viewshed, Name, gamelog::GameLog};
+use crate::raws::free_tiles_near;
+
+/// Everyone travelling with the leader: companions, and anyone being escorted.
+pub fn followers(ecs: &World, leader: Entity) -> Vec<Entity> {
+    let entities = ecs.entities();
+    let members = ecs.read_storage::<GroupMember>();
+    (&entities, &members).join().filter(|(_, m)| m.leader == leader).map(|(e, _)| e).collect()
+}
+
+/// The name and current order of each of the player's companions.
+pub fn companion_list(ecs: &World) -> Vec<(String, CompanionOrder)> {
+    let companions = ecs.read_storage::<Companion>();
+    let names = ecs.read_storage::<Name>();
+    (&companions, &names).join().map(|(c, n)| (n.name.clone(), c.order)).collect()
+}
+
+/// Gives every companion a new order. Companions told to stay guard the spot they're standing on.
+pub fn give_order(ecs: &mut World, order: CompanionOrder) {
+    let mut companions = ecs.write_storage::<Companion>();
+    let mut behaviours = ecs.write_storage::<Behaviour>();
+    let positions = ecs.read_storage::<Position>();
+    for (companion, behaviour, pos) in (&mut companions, &mut behaviours, &positions).join() {
+        companion.order = order;
+        behaviour.home = if order == CompanionOrder::Stay { Some((pos.x, pos.y)) } else { None };
+    }
+    let message = match order {
+        CompanionOrder::Follow => "You tell your companions to follow you.",
+        CompanionOrder::Stay => "You tell your companions to stay here.",
+        CompanionOrder::Attack => "You tell your companions to attack at will.",
+    };
+    ecs.fetch_mut::<GameLog>().entries.push(message.to_string());
+}
+
+/// Moves everyone travelling with the player to their side, after arriving on a new level.
+/// Anyone who was told to stay behind catches up, and waits on the new level instead.
+pub fn bring_followers(ecs: &mut World) {
+    let player = *ecs.fetch::<Entity>();
+    let ppos = *ecs.fetch::<Point>();
+    let followers = followers(ecs, player);
+    let spots = free_tiles_near(ecs, ppos.x, ppos.y, 3);
+    let mut positions = ecs.write_storage::<Position>();
+    let mut viewsheds = ecs.write_storage::<Viewshed>();
+    let mut behaviours = ecs.write_storage::<Behaviour>();
+    let companions = ecs.read_storage::<Companion>();
+    for (entity, (x, y)) in followers.iter().zip(spots.iter()) {
+        positions.insert(*entity, Position{ x: *x, y: *y }).expect("Unable to insert position");
+        if let Some(vs) = viewsheds.get_mut(*entity) {
+            vs.dirty = true;
+        }
+        if let (Some(companion), Some(behaviour)) = (companions.get(*entity), behaviours.get_mut(*entity)) {
+            if companion.order == CompanionOrder::Stay {
+                behaviour.home = Some((*x, *y));
+            }
+        }
+        if let Some(behaviour) = behaviours.get_mut(*entity) {
+            behaviour.alerted = None;
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use specs::prelude::*;
 use specs_derive::*;
 use rltk::{RGB};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Serialize, Deserialize};
 use specs::saveload::{Marker, ConvertSaveload};
 use specs::error::NoError;
@@ -54,12 +54,29 @@ pub struct Attributes {
     pub intelligence: Attribute
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
-pub enum Skill { Melee, Defense, Magic }
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Skill { Melee, Defense, Magic, Stealth, Perception, Lockpicking, Ranged, Alchemy }
+
+/// The attribute which governs a skill.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Eq, PartialEq)]
+pub enum SkillAttribute { Might, Fitness, Quickness, Intelligence }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Skills {
-    pub skills: HashMap<Skill, i32>
+    pub skills: HashMap<Skill, i32>,
+    /// How good the entity is at each skill before training in it, copied from the raws when
+    /// it's spawned.
+    #[serde(default)]
+    pub untrained: HashMap<Skill, i32>,
+    /// The attribute governing each skill, copied from the raws when the entity is spawned.
+    #[serde(default)]
+    pub attributes: HashMap<Skill, SkillAttribute>,
+}
+
+/// How many times an entity has successfully used each skill since it last improved.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SkillPractice {
+    pub uses: HashMap<Skill, i32>
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -74,6 +91,14 @@ pub struct Pools {
     pub mana: Pool,
     pub xp: i32,
     pub level: i32,
+    pub gold: i32,
+}
+
+/// Points the player has earned by levelling up, but not yet spent.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct PendingLevelUp {
+    pub attribute_points: i32,
+    pub skill_points: i32,
 }
 
 // Actors
@@ -89,6 +114,19 @@ pub struct HungerClock {
     pub duration: i32,
 }
 
+/// The player is moving carefully, making less noise and being harder to notice.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Sneaking {
+    /// Set when a creature which could see the player fails to notice them. Their next move or
+    /// attack practises Stealth, and clears it.
+    #[serde(default)]
+    pub went_unnoticed: bool,
+}
+
+/// Sleeping creatures don't act until a noise or an attack wakes them.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Asleep {}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct BlocksTile {}
 
@@ -106,26 +144,238 @@ pub struct LootTable {
 }
 
 // AIs
+/// Which raw-defined faction an entity belongs to. Factions decide who attacks, flees from or
+/// ignores whom.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Faction {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum Movement { Static, Random }
+
+/// How an entity moves when it has nothing to react to.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MoveMode {
+    pub mode: Movement,
+}
+
+/// Something a creature can choose to do on its turn.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum AiAction { Attack, Chase, Surround, Flee, PickUpItem, CallForHelp, Guard, Hunt, Follow, Wander, Idle }
+
+/// How an option's score changes with the situation.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum AiScale { Constant, MissingHealth, EnemyCloseness }
+
+/// What must be true for a creature to consider an option. Conditions which are `None` are ignored.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AiConditions {
+    pub enemy_adjacent: Option<bool>,
+    pub enemy_visible: Option<bool>,
+    pub fear_visible: Option<bool>,
+    pub ally_visible: Option<bool>,
+    pub item_visible: Option<bool>,
+    pub remembers_target: Option<bool>,
+    pub health_below: Option<f32>,
+    pub away_from_home: Option<i32>,
+    pub out_of_formation: Option<i32>,
+    pub routed: Option<bool>,
+    pub order: Option<CompanionOrder>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AiOption {
+    pub action: AiAction,
+    pub score: f32,
+    pub scale: AiScale,
+    pub conditions: AiConditions,
+    pub cooldown: i32,
+    pub cooldown_remaining: i32,
+}
+
+/// A creature's raw-defined behaviour. Each turn it takes the highest scoring option whose
+/// conditions are met.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Behaviour {
+    pub options: Vec<AiOption>,
+    pub home: Option<(i32, i32)>,
+    pub alerted: Option<(i32, i32)>,
+    pub decision: String,
+}
+
+/// The area a breath attack covers: a ball around the target, a line out to the ability's range,
+/// or a cone spreading out towards the target.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum AreaShape { Ball{ radius: i32 }, Line, Cone }
+
+/// What a special ability does. Damage is stored as (n_dice, die_type, bonus).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum AbilityEffect {
+    Breath{ damage: (i32, i32, i32), shape: AreaShape, #[serde(default)] ignites: bool },
+    Summon{ mob: String, count: i32 },
+    Heal{ amount: i32 },
+    Poison{ damage: (i32, i32, i32), per_turn: i32, turns: i32 },
+    Lunge{ damage: (i32, i32, i32) },
+    Regen{ amount: i32, turns: i32 },
+}
+
+impl AbilityEffect {
+    /// Whether the ability is aimed at an enemy, rather than used on the creature itself.
+    pub fn hostile(&self) -> bool {
+        matches!(self, AbilityEffect::Breath{..} | AbilityEffect::Poison{..} | AbilityEffect::Lunge{..})
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ability {
+    pub name: String,
+    pub effect: AbilityEffect,
+    pub score: f32,
+    pub range: f32,
+    pub conditions: AiConditions,
+    pub cooldown: i32,
+    pub cooldown_remaining: i32,
+    pub known: bool,
+}
+
+/// A creature's raw-defined special abilities. They compete with its `Behaviour` options, and
+/// become `known` to the player once they've been seen in use.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Abilities {
+    pub abilities: Vec<Ability>,
+}
+
+/// A one-of-a-kind monster, which only turns up once per run.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Unique {}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BossPhase {
+    pub health_below: f32,
+    pub message: Option<String>,
+    pub behaviour: Option<Behaviour>,
+    pub abilities: Vec<Ability>,
+}
+
+/// The phases a boss goes through as it's worn down. `next` is the first phase which hasn't
+/// happened yet.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct BossPhases {
+    pub phases: Vec<BossPhase>,
+    pub next: usize,
+}
+
+/// How the members of a group arrange themselves around their leader.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum Formation { Line, Pack }
+
+/// A creature which travels with a group. It keeps its place in the formation around the leader,
+/// and when the leader dies it holds its nerve with a `morale`% chance.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct GroupMember {
+    pub leader: Entity,
+    pub formation: Formation,
+    pub slot: i32,
+    pub morale: i32,
+}
+
+/// A creature whose nerve has broken, running away from where it happened.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Routed {
+    pub turns: i32,
+    pub from: (i32, i32),
+}
+
+/// Part of a creature's day: from `from` o'clock until `to` o'clock it goes to `place`, which is
+/// either a kind of building or "Home".
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScheduleSlot {
+    pub from: i32,
+    pub to: i32,
+    pub place: String,
+}
+
+/// A creature's daily routine. `home` and `current` are indices into the map's buildings.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Schedule {
+    pub slots: Vec<ScheduleSlot>,
+    pub home: Option<usize>,
+    pub current: Option<usize>,
+}
+
+/// Someone the player can talk to, using the named dialogue tree from the raws.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Dialogue {
+    pub tree: String,
+}
+
+/// Things which have happened in the story so far, which dialogue can check and set.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct StoryFlags {
+    pub flags: HashSet<String>,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum QuestState { Active, Complete, Failed }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QuestProgress {
+    pub name: String,
+    pub progress: Vec<i32>,
+    pub state: QuestState,
+}
+
+/// The quests the player has taken on, with how far along each objective is.
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Monster {}
+pub struct QuestLog {
+    pub quests: Vec<QuestProgress>,
+}
 
+/// The notable things that have happened this run: which unique monsters have turned up, so
+/// they don't turn up again, and the (name, depth) of those which have been killed.
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Bystander {}
+pub struct RunRecord {
+    pub uniques_met: HashSet<String>,
+    pub uniques_killed: Vec<(String, i32)>,
+}
+
+/// What the player has told their companions to do.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum CompanionOrder { Follow, Stay, Attack }
 
+/// A creature fighting on the player's side. It follows the player between levels.
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Vendor {}
+pub struct Companion {
+    pub order: CompanionOrder,
+}
 
+/// Someone the player has to keep alive and bring along for a quest.
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Carnivore {}
+pub struct Escorted {
+    pub quest: String,
+}
 
+/// Marks a creature as being allowed to act this turn. Each stage of the AI pipeline removes it
+/// once the creature has decided what to do.
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Herbivore {}
+pub struct MyTurn {}
+
+/// When (as a `WorldClock` turn) a creature last noticed the player, whether or not it cares
+/// about them. Creatures which haven't noticed the player lately can be caught off guard.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct NoticedPlayer {
+    pub turn: i32,
+}
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct RemembersPlayer {
     // How long the enemy will continue to pursue the player after losing sight.
     pub max_memory: i32,
     pub memory: i32,
+    // Where the player was last seen or heard, and which way they were heading.
+    pub last_seen: Option<(i32, i32)>,
+    pub heading: (i32, i32),
 }
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
@@ -144,6 +394,22 @@ impl SufferDamage {
     }
 }
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SkillUsed {
+    pub skills: Vec<Skill>
+}
+
+impl SkillUsed {
+    pub fn new_use(store: &mut WriteStorage<SkillUsed>, user: Entity, skill: Skill) {
+        if let Some(used) = store.get_mut(user) {
+            used.skills.push(skill);
+        } else {
+            let used = SkillUsed { skills: vec![skill] };
+            store.insert(user, used).expect("Unable to insert skill use!");
+        }
+    }
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct EntityMoved {}
 
@@ -188,12 +454,71 @@ pub struct Stunned {
     pub turns: i32,
 }
 
+/// Takes `damage` hit points at the start of each of the creature's next `turns` turns.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Poisoned {
+    pub damage: i32,
+    pub turns: i32,
+}
+
+/// Held in place, by a net or the like, for the creature's next `turns` turns.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Rooted {
+    pub turns: i32,
+}
+
+/// On fire for its next `turns` turns, unless it gets into water first.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Burning {
+    pub turns: i32,
+}
+
+/// In deep water, and has been for `turns` turns. Once that runs past the creature's breath, it
+/// starts to drown.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Swimming {
+    pub turns: i32,
+}
+
+/// Recovers `amount` hit points at the start of each of the creature's next `turns` turns.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Regenerating {
+    pub amount: i32,
+    pub turns: i32,
+}
+
+/// Sets fire to anything flammable it hits.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Ignites {}
+
+/// Gives off light of the given colour, reaching `range` tiles. Items which need `Fuel` are only
+/// lit while they're equipped.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct LightSource {
+    pub colour: RGB,
+    pub range: i32,
+}
+
+/// How many more turns a light source burns for, while it's equipped, out of `max`.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Fuel {
+    pub turns: i32,
+    pub max: i32,
+}
+
+/// Too heavy to swim with; it sinks and is lost on entering deep water.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Heavy {}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct MagicMapper {}
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesRemoveCurse {}
+
 // Equipment
-#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
-pub enum EquipmentSlot { Melee, Shield, Head, Torso, Legs, Feet, Hands }
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum EquipmentSlot { Melee, Shield, Head, Torso, Legs, Feet, Hands, Light }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Equippable {
@@ -206,12 +531,25 @@ pub struct Equipped {
     pub slot: EquipmentSlot,
 }
 
+/// Equipment marked as cursed can't be taken off until the curse is lifted.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Cursed {}
+
+/// Modifies the attributes of whoever has this item equipped.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AttributeBonus {
+    pub might: Option<i32>,
+    pub fitness: Option<i32>,
+    pub quickness: Option<i32>,
+    pub intelligence: Option<i32>,
+}
+
 #[derive(Component, ConvertSaveload, Clone)]
 pub struct MeleePowerBonus {
     pub power: i32,
 }
 
-#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum WeaponAttribute { Might, Quickness }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -249,7 +587,9 @@ pub struct SerializeMe;
 
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct SerializationHelper {
-    pub map: super::map::Map
+    pub map: super::map::Map,
+    pub clock: super::clock_system::WorldClock,
+    pub director: super::spawn_director::SpawnDirector,
 }
 
 // Intents
@@ -281,6 +621,33 @@ pub struct WantsToRemoveItem {
     pub item: Entity,
 }
 
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToUseAbility {
+    pub ability: usize,
+    pub target: Entity,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToSummon {
+    pub mob: String,
+    pub count: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToApproach {
+    pub idx: usize,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToFlee {
+    pub indices: Vec<usize>,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToMove {
+    pub destination: usize,
+}
+
 // Terrain
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -292,7 +659,93 @@ pub struct EntryTrigger{}
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct SingleActivation {}
 
+/// A trap which sends whoever sets it off to somewhere else on the level.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct TeleportTrap {}
+
+/// A trap which raises a din that carries `volume`, waking anything nearby.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Alarm {
+    pub volume: i32,
+}
+
+/// A trap door, which drops the player to the next level (and anything else out of sight).
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Pit {}
+
+/// A trap which poisons everyone within `radius` of it.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct PoisonGas {
+    pub radius: i32,
+    pub damage: i32,
+    pub turns: i32,
+}
+
+/// A trap which throws a net over its victim, rooting them for `turns` turns.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Ensnares {
+    pub turns: i32,
+}
+
+/// A trap which can be disarmed with a Lockpicking check, perhaps leaving `parts` to salvage.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Disarmable {
+    pub difficulty: i32,
+    pub parts: Option<String>,
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Door {
     pub open: bool
 }
+
+/// A prop which holds items, rolled from `loot_table` the first time it's opened.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Container {
+    pub loot_table: String,
+    pub rolls: i32,
+    pub opened: bool,
+}
+
+/// A lever, which can be pulled back and forth.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Lever {
+    pub pulled: bool,
+}
+
+/// The door a lever opens and closes, linked up when the level is built.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct LinkedDoor {
+    pub door: Entity,
+}
+
+/// A fountain which does something random, picked from `effects` by weight, to whoever drinks
+/// from it. Runs dry after `uses` drinks.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Fountain {
+    pub uses: i32,
+    pub effects: Vec<(String, i32)>,
+}
+
+/// An altar which, when prayed at, lifts the curses from everything the player is carrying.
+/// Only answers one prayer.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Altar {
+    pub uncurse: bool,
+    pub used: bool,
+}
+
+/// A door which won't open until it's unlocked with a key, has its lock picked, or is bashed in.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Locked {
+    pub lock_difficulty: i32,
+    pub bash_difficulty: i32,
+}
+
+/// Unlocks any one locked door, and is used up in doing so.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Key {}
+
+/// Creatures with the hands (and the wits) to open doors.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct OpensDoors {}
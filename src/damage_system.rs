@@ -1,14 +1,16 @@
 use specs::prelude::*;
 use super::{
     Pools, SufferDamage, Player, gamelog::GameLog, Name, RunState,
-    Position, Map, Equipped, InBackpack, LootTable, Attributes,
-    particle_system::ParticleBuilder
+    Position, Map, Equipped, InBackpack, LootTable, PendingLevelUp,
+    particle_system::ParticleBuilder, Asleep, Unique, RunRecord,
 };
-use crate::gamesystem::{player_hp_at_level, mana_at_level};
+use crate::raws::{RawMaster, progression, xp_for_level, xp_for_kill};
+use crate::quest_system::{QuestEvents, QuestEvent};
 
 pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteStorage<'a, Pools>,
         WriteStorage<'a, SufferDamage>,
@@ -16,18 +18,26 @@ impl<'a> System<'a> for DamageSystem {
         WriteExpect<'a, Map>,
         Entities<'a>,
         ReadExpect<'a, Entity>,
-        ReadStorage<'a, Attributes>,
+        WriteStorage<'a, PendingLevelUp>,
         WriteExpect<'a, GameLog>,
         WriteExpect<'a, ParticleBuilder>,
         ReadExpect<'a, rltk::Point>,
+        WriteStorage<'a, Asleep>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, QuestEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut stats, mut damage, positions, mut map, entities, player, attributes, mut log, mut particles, player_pos) = data;
+        let (mut stats, mut damage, positions, mut map, entities, player, mut pending_level_ups, mut log, mut particles, player_pos, mut asleep, names, mut quest_events) = data;
         let mut xp_gain = 0;
+        let raws = &crate::raws::RAWS.lock().unwrap();
+        let player_level = stats.get(*player).map_or(1, |s| s.level);
 
         for (entity, mut stats, damage) in (&entities, &mut stats, &damage).join() {
+            // Getting hurt wakes you up
+            asleep.remove(entity);
             for dmg in damage.amount.iter() {
+                let was_alive = stats.hit_points.current > 0;
                 stats.hit_points.current -= dmg.0;
                 let pos = positions.get(entity);
                 if let Some(pos) = pos {
@@ -36,43 +46,20 @@ impl<'a> System<'a> for DamageSystem {
                 }
 
                 if stats.hit_points.current < 1 && dmg.1 {
-                    xp_gain += stats.level * 1000;// TODO: change this back to 100 //
+                    xp_gain += xp_for_kill(raws, stats.level, player_level);
+                    if was_alive {
+                        if let Some(name) = names.get(entity) {
+                            quest_events.request(QuestEvent::Killed(name.name.clone()));
+                        }
+                    }
                 }
             }
         }
 
         if xp_gain != 0 {
-            let mut player_stats = stats.get_mut(*player).unwrap();
-            let player_attributes = attributes.get(*player).unwrap();
-            player_stats.xp += xp_gain;
-            if player_stats.xp >= player_stats.level * 1000 {
-                // We've gone up a level!
-                player_stats.level += 1;
-                player_stats.hit_points.max = player_hp_at_level(
-                    player_attributes.fitness.base + player_attributes.fitness.modifiers,
-                    player_stats.level);
-                player_stats.hit_points.current = player_stats.hit_points.max; // Full heal
-                player_stats.mana.max = mana_at_level(
-                    player_attributes.intelligence.base + player_attributes.intelligence.modifiers,
-                    player_stats.level
-                );
-                player_stats.mana.current = player_stats.mana.max;
-
-                for i in 0..10 {
-                    let mut particle_pos_y = player_pos.y-i;
-                    if particle_pos_y <=0 {
-                        particle_pos_y = 0;
-                    }
-
-                    particles.request(
-                      player_pos.x,
-                      particle_pos_y,
-                      rltk::RGB::named(rltk::GOLD),
-                      rltk::RGB::named(rltk::BLACK),
-                      rltk::to_cp437('░'),
-                      400.0)
-                }
-                log.entries.push(format!("Congratulations, you are now level {}!", player_stats.level));
+            let levels_gained = award_xp(raws, xp_gain, *player, &mut stats, &mut pending_level_ups, &mut log);
+            if levels_gained > 0 {
+                level_up_particles(&mut particles, &player_pos);
             }
         }
 
@@ -80,6 +67,54 @@ impl<'a> System<'a> for DamageSystem {
     }
 }
 
+/// Gives the player experience, along with the points to spend for any levels it earns them.
+/// Returns how many levels were gained.
+pub fn award_xp(raws: &RawMaster, xp: i32, player: Entity, stats: &mut WriteStorage<Pools>,
+    pending_level_ups: &mut WriteStorage<PendingLevelUp>, log: &mut GameLog) -> i32
+{
+    let player_stats = if let Some(stats) = stats.get_mut(player) { stats } else { return 0; };
+    player_stats.xp += xp;
+    let mut levels_gained = 0;
+    while player_stats.xp >= xp_for_level(raws, player_stats.level + 1) {
+        player_stats.level += 1;
+        levels_gained += 1;
+        log.entries.push(format!("Congratulations, you are now level {}!", player_stats.level));
+    }
+
+    if levels_gained > 0 {
+        // We've gone up a level! The points are spent on the level up screen.
+        let progression = progression(raws);
+        let attribute_points = progression.attribute_points_per_level * levels_gained;
+        let skill_points = progression.skill_points_per_level * levels_gained;
+        if let Some(pending) = pending_level_ups.get_mut(player) {
+            pending.attribute_points += attribute_points;
+            pending.skill_points += skill_points;
+        } else {
+            pending_level_ups.insert(player, PendingLevelUp{ attribute_points, skill_points })
+                .expect("Unable to insert level up");
+        }
+    }
+    levels_gained
+}
+
+/// A shower of gold above the player, to celebrate a new level.
+pub fn level_up_particles(particles: &mut ParticleBuilder, player_pos: &rltk::Point) {
+    for i in 0..10 {
+        let mut particle_pos_y = player_pos.y-i;
+        if particle_pos_y <=0 {
+            particle_pos_y = 0;
+        }
+
+        particles.request(
+          player_pos.x,
+          particle_pos_y,
+          rltk::RGB::named(rltk::GOLD),
+          rltk::RGB::named(rltk::BLACK),
+          rltk::to_cp437('░'),
+          400.0)
+    }
+}
+
 pub fn delete_the_dead(ecs: &mut World) {
     let mut dead: Vec<Entity> = Vec::new();
 
@@ -110,6 +145,22 @@ pub fn delete_the_dead(ecs: &mut World) {
         }
     }
 
+    // Keep a record of any uniques that fell
+    {
+        let player = *ecs.fetch::<Entity>();
+        let depth = ecs.fetch::<Map>().depth;
+        let uniques = ecs.read_storage::<Unique>();
+        let names = ecs.read_storage::<Name>();
+        let mut records = ecs.write_storage::<RunRecord>();
+        if let Some(record) = records.get_mut(player) {
+            for victim in dead.iter().filter(|v| uniques.contains(**v)) {
+                if let Some(name) = names.get(*victim) {
+                    record.uniques_killed.push((name.name.clone(), depth));
+                }
+            }
+        }
+    }
+
     // Drop everything held by dead people
     let mut to_spawn: Vec<(String, Position)> = Vec::new();
     {
@@ -139,12 +190,10 @@ pub fn delete_the_dead(ecs: &mut World) {
             }
 
             if let Some(table) = loot_tables.get(*victim) {
-                let drop_finder = crate::raws::get_item_drop(
-                    &crate::raws::RAWS.lock().unwrap(),
-                    &mut rng,
-                    &table.table);
-                if let Some(tag) = drop_finder {
-                    if let Some(pos) = pos {
+                let raws = &crate::raws::RAWS.lock().unwrap();
+                let drop_finder = crate::raws::get_item_drop(raws, &mut rng, &table.table);
+                if let Some(pos) = pos {
+                    for tag in crate::raws::get_guaranteed_drops(raws, &table.table).into_iter().chain(drop_finder) {
                         to_spawn.push((tag, pos.clone()));
                     }
                 }
@@ -158,12 +207,14 @@ pub fn delete_the_dead(ecs: &mut World) {
     }
 
     {
+        let depth = ecs.fetch::<Map>().depth;
         for drop in to_spawn.iter() {
-            crate::raws::spawn_named_item(
+            crate::raws::spawn_generated_item(
                 &crate::raws::RAWS.lock().unwrap(),
                 ecs,
                 &drop.0,
-                crate::raws::SpawnType::AtPosition{ x: drop.1.x, y: drop.1.y }
+                crate::raws::SpawnType::AtPosition{ x: drop.1.x, y: drop.1.y },
+                depth
             );
         }
     }
@@ -0,0 +1,181 @@
+use specs::prelude::*;
+use super::{Dialogue, StoryFlags, Pools, Name, InBackpack, Faction, CompanionOrder, gamelog::GameLog,
+    quest_system::start_quest};
+use crate::raws::{RAWS, RawMaster, SpawnType, DialogueConditions, DialogueTree, get_dialogue,
+    get_shop, shop_index, spawn_named_item, string_to_dialogue_action, make_companion};
+
+/// Something a dialogue response does when the player picks it.
+pub enum DialogueAction {
+    GiveItem(String),
+    TakeItem(String),
+    GiveGold(i32),
+    TakeGold(i32),
+    SetFlag(String),
+    ChangeFaction(String),
+    StartQuest(String),
+    OpenShop(String),
+    Recruit(CompanionOrder),
+}
+
+/// Where the conversation goes after the player picks a response.
+pub enum DialogueOutcome { Continue(usize), End, OpenShop(usize) }
+
+fn tree_for<'a>(raws: &'a RawMaster, ecs: &World, npc: Entity) -> Option<&'a DialogueTree> {
+    let dialogue = ecs.read_storage::<Dialogue>();
+    dialogue.get(npc).and_then(|d| get_dialogue(raws, &d.tree))
+}
+
+fn node_index(tree: &DialogueTree, id: &str) -> Option<usize> {
+    tree.nodes.iter().position(|n| n.id == id)
+}
+
+fn backpack_item(ecs: &World, owner: Entity, name: &str) -> Option<Entity> {
+    let entities = ecs.entities();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let names = ecs.read_storage::<Name>();
+    (&entities, &backpack, &names).join()
+        .find(|(_, pack, n)| pack.owner == owner && n.name == name)
+        .map(|(entity, _, _)| entity)
+}
+
+fn conditions_met(ecs: &World, conditions: &DialogueConditions) -> bool {
+    let player = *ecs.fetch::<Entity>();
+    let pools = ecs.read_storage::<Pools>();
+    let flags = ecs.read_storage::<StoryFlags>();
+    let (level, gold) = pools.get(player).map_or((1, 0), |p| (p.level, p.gold));
+    let has_flag = |flag: &String| flags.get(player).is_some_and(|f| f.flags.contains(flag));
+
+    if conditions.min_level.is_some_and(|min| level < min) { return false; }
+    if conditions.gold.is_some_and(|min| gold < min) { return false; }
+    if conditions.flag.as_ref().is_some_and(|flag| !has_flag(flag)) { return false; }
+    if conditions.not_flag.as_ref().is_some_and(has_flag) { return false; }
+    if let Some(item) = &conditions.has_item {
+        if backpack_item(ecs, player, item).is_none() { return false; }
+    }
+    true
+}
+
+/// Finds the node a conversation with the given creature starts at, if they have anything to say.
+pub fn start_node(ecs: &World, npc: Entity) -> Option<usize> {
+    let raws = &RAWS.lock().unwrap();
+    tree_for(raws, ecs, npc).and_then(|tree| node_index(tree, &tree.start))
+}
+
+/// Gets the speaker's line for a node, and the (index, text) of each response the player is
+/// currently able to give.
+pub fn node_text(ecs: &World, npc: Entity, node: usize) -> (String, Vec<(usize, String)>) {
+    let raws = &RAWS.lock().unwrap();
+    match tree_for(raws, ecs, npc).and_then(|tree| tree.nodes.get(node)) {
+        None => (String::new(), Vec::new()),
+        Some(node) => {
+            let responses = node.responses.iter().enumerate()
+                .filter(|(_, r)| r.when.as_ref().is_none_or(|c| conditions_met(ecs, c)))
+                .map(|(i, r)| (i, r.text.clone()))
+                .collect();
+            (node.text.clone(), responses)
+        }
+    }
+}
+
+fn apply_action(raws: &RawMaster, ecs: &mut World, npc: Entity, action: DialogueAction) -> Option<usize> {
+    let player = *ecs.fetch::<Entity>();
+    let npc_name = ecs.read_storage::<Name>().get(npc).map_or("They".to_string(), |n| n.name.clone());
+    match action {
+        DialogueAction::GiveItem(item) => {
+            if spawn_named_item(raws, ecs, &item, SpawnType::Carried{ by: player }).is_some() {
+                ecs.fetch_mut::<GameLog>().entries.push(format!("{} gives you {}.", npc_name, item));
+            }
+        }
+        DialogueAction::TakeItem(item) => {
+            if let Some(entity) = backpack_item(ecs, player, &item) {
+                ecs.delete_entity(entity).expect("Unable to delete");
+                ecs.fetch_mut::<GameLog>().entries.push(format!("You hand over {}.", item));
+            }
+        }
+        DialogueAction::GiveGold(amount) => {
+            if let Some(pools) = ecs.write_storage::<Pools>().get_mut(player) {
+                pools.gold += amount;
+            }
+            ecs.fetch_mut::<GameLog>().entries.push(format!("{} gives you {} gold.", npc_name, amount));
+        }
+        DialogueAction::TakeGold(amount) => {
+            if let Some(pools) = ecs.write_storage::<Pools>().get_mut(player) {
+                pools.gold = i32::max(0, pools.gold - amount);
+            }
+            ecs.fetch_mut::<GameLog>().entries.push(format!("You pay {} gold.", amount));
+        }
+        DialogueAction::SetFlag(flag) => {
+            if let Some(flags) = ecs.write_storage::<StoryFlags>().get_mut(player) {
+                flags.flags.insert(flag);
+            }
+        }
+        DialogueAction::ChangeFaction(faction) => {
+            ecs.write_storage::<Faction>().insert(npc, Faction{ name: faction }).expect("Unable to insert");
+        }
+        DialogueAction::StartQuest(quest) => start_quest(raws, ecs, &quest),
+        DialogueAction::Recruit(order) => {
+            make_companion(raws, ecs, npc, player, order);
+            ecs.fetch_mut::<GameLog>().entries.push(format!("{} joins you!", npc_name));
+        }
+        DialogueAction::OpenShop(shop) => return shop_index(raws, &shop),
+    }
+    None
+}
+
+/// Carries out the actions of the chosen response, and works out where the conversation goes next.
+pub fn choose_response(ecs: &mut World, npc: Entity, node: usize, response: usize) -> DialogueOutcome {
+    let raws = &RAWS.lock().unwrap();
+    let (actions, next) = match tree_for(raws, ecs, npc).and_then(|tree| tree.nodes.get(node).map(|n| (tree, n))) {
+        None => return DialogueOutcome::End,
+        Some((tree, node)) => match node.responses.get(response) {
+            None => return DialogueOutcome::End,
+            Some(response) => {
+                let actions: Vec<DialogueAction> = response.actions.iter().flatten()
+                    .filter_map(|(action, value)| string_to_dialogue_action(action, value))
+                    .collect();
+                (actions, response.next.as_ref().and_then(|id| node_index(tree, id)))
+            }
+        }
+    };
+
+    let mut shop = None;
+    for action in actions {
+        if let Some(index) = apply_action(raws, ecs, npc, action) {
+            shop = Some(index);
+        }
+    }
+
+    match (shop, next) {
+        (Some(shop), _) => DialogueOutcome::OpenShop(shop),
+        (None, Some(next)) => DialogueOutcome::Continue(next),
+        (None, None) => DialogueOutcome::End,
+    }
+}
+
+/// Gets the (name, price) of everything a shop sells.
+pub fn shop_stock(shop: usize) -> Vec<(String, i32)> {
+    let raws = &RAWS.lock().unwrap();
+    get_shop(raws, shop).items.iter().map(|i| (i.name.clone(), i.price)).collect()
+}
+
+/// Buys an item from a shop, if the player can afford it.
+pub fn buy_item(ecs: &mut World, shop: usize, item: usize) {
+    let raws = &RAWS.lock().unwrap();
+    let player = *ecs.fetch::<Entity>();
+    let (name, price) = match get_shop(raws, shop).items.get(item) {
+        None => return,
+        Some(item) => (item.name.clone(), item.price),
+    };
+
+    let gold = ecs.read_storage::<Pools>().get(player).map_or(0, |p| p.gold);
+    if gold < price {
+        ecs.fetch_mut::<GameLog>().entries.push(format!("You can't afford the {}.", name));
+        return;
+    }
+    if spawn_named_item(raws, ecs, &name, SpawnType::Carried{ by: player }).is_some() {
+        if let Some(pools) = ecs.write_storage::<Pools>().get_mut(player) {
+            pools.gold -= price;
+        }
+        ecs.fetch_mut::<GameLog>().entries.push(format!("You buy the {} for {} gold.", name, price));
+    }
+}
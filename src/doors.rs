@@ -0,0 +1,190 @@
+use specs::prelude::*;
+use rltk::Point;
+use super::{
+    Door, Locked, Key, LinkedDoor, InBackpack, BlocksTile, BlocksVisibility, Renderable, Viewshed, Position, Map,
+    Name, Attributes, Skills, Skill, SkillUsed, skill_check, roll_check, gamelog::GameLog,
+    noise_system::{NoiseBuilder, DOOR_NOISE, COMBAT_NOISE},
+};
+
+/// How hard it is to bash in a door that isn't locked, just stuck shut.
+const STUCK_DOOR_DIFFICULTY: i32 = 10;
+
+/// Opens a door, so that it no longer blocks movement or sight.
+pub fn open_door(
+    door: Entity,
+    doors: &mut WriteStorage<Door>,
+    blocks_tile: &mut WriteStorage<BlocksTile>,
+    blocks_visibility: &mut WriteStorage<BlocksVisibility>,
+    renderables: &mut WriteStorage<Renderable>,
+) {
+    if let Some(door) = doors.get_mut(door) {
+        door.open = true;
+    }
+    blocks_tile.remove(door);
+    blocks_visibility.remove(door);
+    if let Some(renderable) = renderables.get_mut(door) {
+        renderable.glyph = rltk::to_cp437('/');
+    }
+}
+
+/// Closes a door, so that it blocks movement and sight again.
+pub fn shut_door(ecs: &mut World, door: Entity) {
+    if let Some(door) = ecs.write_storage::<Door>().get_mut(door) {
+        door.open = false;
+    }
+    ecs.write_storage::<BlocksTile>().insert(door, BlocksTile{}).expect("Unable to insert blocks tile");
+    ecs.write_storage::<BlocksVisibility>().insert(door, BlocksVisibility{}).expect("Unable to insert blocks visibility");
+    if let Some(renderable) = ecs.write_storage::<Renderable>().get_mut(door) {
+        renderable.glyph = rltk::to_cp437('+');
+    }
+}
+
+/// The doors around the player.
+fn adjacent_doors(ecs: &World) -> Vec<Entity> {
+    let ppos = *ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let doors = ecs.read_storage::<Door>();
+    let mut adjacent = Vec::new();
+    for y in ppos.y - 1 ..= ppos.y + 1 {
+        for x in ppos.x - 1 ..= ppos.x + 1 {
+            if x < 0 || x > map.width - 1 || y < 0 || y > map.height - 1 || (x == ppos.x && y == ppos.y) { continue; }
+            adjacent.extend(map.tile_content[map.xy_idx(x, y)].iter().filter(|e| doors.contains(**e)));
+        }
+    }
+    adjacent
+}
+
+/// Opening or closing a door changes what everyone can see, and can be heard.
+pub fn door_moved(ecs: &mut World, door: Entity) {
+    for viewshed in (&mut ecs.write_storage::<Viewshed>()).join() {
+        viewshed.dirty = true;
+    }
+    let player = *ecs.fetch::<Entity>();
+    if let Some(pos) = ecs.read_storage::<Position>().get(door) {
+        ecs.write_resource::<NoiseBuilder>().request(pos.x, pos.y, DOOR_NOISE, player);
+    }
+}
+
+/// Finds a locked door the player would walk into by moving in the given direction.
+pub fn locked_door_at(ecs: &World, delta_x: i32, delta_y: i32) -> Option<Entity> {
+    let ppos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let locked = ecs.read_storage::<Locked>();
+    let (x, y) = (ppos.x + delta_x, ppos.y + delta_y);
+    if x < 0 || x > map.width-1 || y < 0 || y > map.height-1 { return None; }
+
+    map.tile_content[map.xy_idx(x, y)].iter().find(|e| locked.contains(**e)).copied()
+}
+
+/// Tries to get through a locked door, using up a key if the player has one, and picking the lock
+/// if they don't. Doors worked by a lever can't be unlocked at all.
+pub fn unlock_door(ecs: &mut World, door: Entity) {
+    let player = *ecs.fetch::<Entity>();
+    if ecs.read_storage::<LinkedDoor>().join().any(|link| link.door == door) {
+        let name = ecs.read_storage::<Name>().get(door).map_or("door".to_string(), |n| n.name.clone());
+        ecs.fetch_mut::<GameLog>().entries.push(format!("The {} won't budge. There must be a lever somewhere.", name));
+        return;
+    }
+    let key = {
+        let entities = ecs.entities();
+        let keys = ecs.read_storage::<Key>();
+        let backpack = ecs.read_storage::<InBackpack>();
+        (&entities, &keys, &backpack).join().find(|(_, _, pack)| pack.owner == player).map(|(e, _, _)| e)
+    };
+
+    if let Some(key) = key {
+        ecs.delete_entity(key).expect("Unable to delete key");
+        ecs.fetch_mut::<GameLog>().entries.push("You unlock the door with your key.".to_string());
+    } else {
+        let picked = {
+            let difficulty = ecs.read_storage::<Locked>().get(door).map_or(0, |l| l.lock_difficulty);
+            let attributes = ecs.read_storage::<Attributes>();
+            let skills = ecs.read_storage::<Skills>();
+            let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+            SkillUsed::new_use(&mut ecs.write_storage::<SkillUsed>(), player, Skill::Lockpicking);
+            match (attributes.get(player), skills.get(player)) {
+                (Some(attributes), Some(skills)) => skill_check(&mut rng, Skill::Lockpicking, attributes, skills, difficulty),
+                _ => false
+            }
+        };
+        if !picked {
+            ecs.fetch_mut::<GameLog>().entries.push("You fail to pick the lock.".to_string());
+            return;
+        }
+        ecs.fetch_mut::<GameLog>().entries.push("You pick the lock.".to_string());
+    }
+
+    ecs.write_storage::<Locked>().remove(door);
+    if let Some(name) = ecs.write_storage::<Name>().get_mut(door) {
+        name.name = "Door".to_string();
+    }
+    open_door(
+        door,
+        &mut ecs.write_storage::<Door>(),
+        &mut ecs.write_storage::<BlocksTile>(),
+        &mut ecs.write_storage::<BlocksVisibility>(),
+        &mut ecs.write_storage::<Renderable>(),
+    );
+    door_moved(ecs, door);
+}
+
+/// Closes an open door next to the player, as long as nothing is standing in the doorway.
+/// Returns true if this took the player's turn.
+pub fn close_door(ecs: &mut World) -> bool {
+    let doors = adjacent_doors(ecs);
+    let target = {
+        let door_states = ecs.read_storage::<Door>();
+        let positions = ecs.read_storage::<Position>();
+        let map = ecs.fetch::<Map>();
+        let open_doors: Vec<Entity> = doors.into_iter().filter(|d| door_states.get(*d).is_some_and(|d| d.open)).collect();
+        let clear = open_doors.iter().find(|d| {
+            positions.get(**d).is_some_and(|pos| map.tile_content[map.xy_idx(pos.x, pos.y)].len() == 1)
+        }).copied();
+        if clear.is_none() {
+            let message = if open_doors.is_empty() { "There is no open door next to you." } else { "Something is in the way." };
+            ecs.fetch_mut::<GameLog>().entries.push(message.to_string());
+        }
+        clear
+    };
+
+    let Some(door) = target else { return false; };
+    shut_door(ecs, door);
+    ecs.fetch_mut::<GameLog>().entries.push("You close the door.".to_string());
+    door_moved(ecs, door);
+    true
+}
+
+/// Tries to bash in a closed door next to the player, with nothing but brute strength. Locked
+/// doors are tried first. Returns true if this took the player's turn.
+pub fn bash_door(ecs: &mut World) -> bool {
+    let player = *ecs.fetch::<Entity>();
+    let target = {
+        let door_states = ecs.read_storage::<Door>();
+        let locked = ecs.read_storage::<Locked>();
+        let mut closed: Vec<Entity> = adjacent_doors(ecs).into_iter().filter(|d| door_states.get(*d).is_some_and(|d| !d.open)).collect();
+        closed.sort_by_key(|d| !locked.contains(*d));
+        closed.first().map(|d| (*d, locked.get(*d).map_or(STUCK_DOOR_DIFFICULTY, |l| l.bash_difficulty)))
+    };
+    let Some((door, difficulty)) = target else {
+        ecs.fetch_mut::<GameLog>().entries.push("There is no closed door next to you.".to_string());
+        return false;
+    };
+
+    let smashed = {
+        let might = ecs.read_storage::<Attributes>().get(player).map_or(0, |a| a.might.bonus);
+        roll_check(&mut ecs.write_resource::<rltk::RandomNumberGenerator>(), might, difficulty)
+    };
+    if let Some(pos) = ecs.read_storage::<Position>().get(door) {
+        ecs.write_resource::<NoiseBuilder>().request(pos.x, pos.y, COMBAT_NOISE, player);
+    }
+    if smashed {
+        ecs.fetch_mut::<GameLog>().entries.push("You smash the door to splinters!".to_string());
+        ecs.delete_entity(door).expect("Unable to delete door");
+        for viewshed in (&mut ecs.write_storage::<Viewshed>()).join() {
+            viewshed.dirty = true;
+        }
+    } else {
+        ecs.fetch_mut::<GameLog>().entries.push("You throw yourself at the door, but it holds.".to_string());
+    }
+    true
+}
@@ -0,0 +1,42 @@
+use specs::prelude::*;
+use super::{Attributes, AttributeBonus, Equipped};
+use crate::attr_bonus;
+use std::collections::HashMap;
+
+/// Recalculates every entity's attribute modifiers from the equipment they're wearing.
+pub struct EquipmentBonusSystem {}
+
+impl<'a> System<'a> for EquipmentBonusSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, AttributeBonus>,
+        WriteStorage<'a, Attributes>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, equipped, attribute_bonus, mut attributes) = data;
+
+        // Total up the bonuses granted to each wearer: (might, fitness, quickness, intelligence)
+        let mut totals: HashMap<Entity, (i32, i32, i32, i32)> = HashMap::new();
+        for (worn, bonus) in (&equipped, &attribute_bonus).join() {
+            let total = totals.entry(worn.owner).or_insert((0, 0, 0, 0));
+            total.0 += bonus.might.unwrap_or(0);
+            total.1 += bonus.fitness.unwrap_or(0);
+            total.2 += bonus.quickness.unwrap_or(0);
+            total.3 += bonus.intelligence.unwrap_or(0);
+        }
+
+        for (entity, attr) in (&entities, &mut attributes).join() {
+            let total = totals.get(&entity).copied().unwrap_or((0, 0, 0, 0));
+            attr.might.modifiers = total.0;
+            attr.fitness.modifiers = total.1;
+            attr.quickness.modifiers = total.2;
+            attr.intelligence.modifiers = total.3;
+            attr.might.bonus = attr_bonus(attr.might.base + attr.might.modifiers);
+            attr.fitness.bonus = attr_bonus(attr.fitness.base + attr.fitness.modifiers);
+            attr.quickness.bonus = attr_bonus(attr.quickness.base + attr.quickness.modifiers);
+            attr.intelligence.bonus = attr_bonus(attr.intelligence.base + attr.intelligence.modifiers);
+        }
+    }
+}
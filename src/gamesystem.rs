@@ -1,4 +1,4 @@
-use super::{Skill, Skills};
+use super::{Skill, Skills, SkillAttribute, Attributes, Attribute};
 
 use regex::Regex;
 
@@ -54,10 +54,63 @@ pub fn mana_at_level(intelligence: i32, level: i32) -> i32 {
     mana_per_level(intelligence) * level
 }
 
+/// A creature's rank in a skill, or what it can manage untrained if it has no rank.
 pub fn skill_bonus(skill: Skill, skills: &Skills) -> i32 {
-    if skills.skills.contains_key(&skill) {
-        skills.skills[&skill]
-    } else {
-        -4
+    match skills.skills.get(&skill) {
+        Some(rank) => *rank,
+        None => skills.untrained.get(&skill).copied().unwrap_or(-4)
+    }
+}
+
+/// The attribute which governs a skill, as defined in the raws. Defaults to intelligence.
+pub fn skill_attribute<'a>(skill: Skill, attributes: &'a Attributes, skills: &Skills) -> &'a Attribute {
+    match skills.attributes.get(&skill) {
+        Some(SkillAttribute::Might) => &attributes.might,
+        Some(SkillAttribute::Fitness) => &attributes.fitness,
+        Some(SkillAttribute::Quickness) => &attributes.quickness,
+        _ => &attributes.intelligence,
+    }
+}
+
+/// Rolls a d20 plus any bonuses against a difficulty. A natural 20 always succeeds, and a
+/// natural 1 always fails.
+pub fn roll_check(rng: &mut rltk::RandomNumberGenerator, bonus: i32, difficulty: i32) -> bool {
+    let natural_roll = rng.roll_dice(1, 20);
+    natural_roll != 1 && (natural_roll == 20 || natural_roll + bonus >= difficulty)
+}
+
+/// How hard it is to notice a sneaking creature.
+pub fn sneak_difficulty(attributes: &Attributes, skills: &Skills) -> i32 {
+    10 + skill_attribute(Skill::Stealth, attributes, skills).bonus + skill_bonus(Skill::Stealth, skills)
+}
+
+/// How far a noise carries when made by someone trying to be quiet. Sneaking halves it, and
+/// every point of Stealth skill reduces it further.
+pub fn sneak_volume(volume: i32, skills: &Skills) -> i32 {
+    i32::max(0, volume / 2 - i32::max(0, skill_bonus(Skill::Stealth, skills)))
+}
+
+/// Makes a skill check for a creature, adding the skill and its governing attribute's bonus
+/// to the roll.
+pub fn skill_check(rng: &mut rltk::RandomNumberGenerator, skill: Skill, attributes: &Attributes, skills: &Skills, difficulty: i32) -> bool {
+    let bonus = skill_attribute(skill, attributes, skills).bonus + skill_bonus(skill, skills);
+    roll_check(rng, bonus, difficulty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn untrained_skills_use_their_raw_value() {
+        let skills = Skills{
+            skills: HashMap::from([(Skill::Melee, 2)]),
+            untrained: HashMap::from([(Skill::Melee, -1), (Skill::Stealth, -2)]),
+            attributes: HashMap::new(),
+        };
+        assert_eq!(skill_bonus(Skill::Melee, &skills), 2);
+        assert_eq!(skill_bonus(Skill::Stealth, &skills), -2);
+        assert_eq!(skill_bonus(Skill::Magic, &skills), -4);
     }
 }
@@ -3,7 +3,10 @@ use specs::prelude::*;
 use super::{
     Pools, GameLog, Name, Map, Position, State, InBackpack,
     Viewshed, RunState, Equipped, HungerClock, HungerState, Hidden,
-    rex_assets::RexAssets, camera, Attributes, Attribute, Consumable,
+    rex_assets::RexAssets, camera, Attributes, Attribute, Consumable, Skills, Skill, PendingLevelUp,
+    SkillPractice, Stunned, Rooted, Burning, Swimming, MeleeWeapon, Wearable, AttributeBonus, Cursed, Behaviour, Sneaking,
+    Asleep, Routed, clock_system::WorldClock, QuestLog, QuestProgress, QuestState, CompanionOrder,
+    Abilities, Poisoned, ability_system::describe_ability, RunRecord,
     options::OPTIONS, options::KeybindType,
 };
 
@@ -73,8 +76,11 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     ctx.print_color(50, 3, white, black, &xp);
     ctx.draw_bar_horizontal(64, 1, 14, player_pools.hit_points.current, player_pools.hit_points.max, RGB::named(rltk::RED), RGB::named(rltk::BLACK));
     ctx.draw_bar_horizontal(64, 2, 14, player_pools.mana.current, player_pools.mana.max, RGB::named(rltk::BLUE), RGB::named(rltk::BLACK));
-    let xp_level_start = (player_pools.level-1) * 1000;
-    ctx.draw_bar_horizontal(64, 3, 14, player_pools.xp - xp_level_start, 1000, RGB::named(rltk::GOLD), RGB::named(rltk::BLACK));
+    let (xp_level_start, xp_level_end) = {
+        let raws = &crate::raws::RAWS.lock().unwrap();
+        (crate::raws::xp_for_level(raws, player_pools.level), crate::raws::xp_for_level(raws, player_pools.level + 1))
+    };
+    ctx.draw_bar_horizontal(64, 3, 14, player_pools.xp - xp_level_start, xp_level_end - xp_level_start, RGB::named(rltk::GOLD), RGB::named(rltk::BLACK));
 
     // Attributes
     let attributes = ecs.read_storage::<Attributes>();
@@ -111,6 +117,11 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         }
     }
 
+    // Gold
+    ctx.print_color(50, 43, RGB::named(rltk::GOLD), black, format!("Gold: {}", player_pools.gold));
+    let clock = ecs.fetch::<WorldClock>();
+    ctx.print_color(66, 43, white, black, clock.time_of_day());
+
     // Status effects
     let hunger = ecs.read_storage::<HungerClock>();
     let hc = hunger.get(*player_entity).unwrap();
@@ -120,6 +131,24 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         HungerState::Hungry => ctx.print_color(50, 44, RGB::named(rltk::ORANGE), black, "Hungry"),
         HungerState::Starving => ctx.print_color(50, 44, RGB::named(rltk::RED), black, "Starving"),
     }
+    if ecs.read_storage::<Sneaking>().contains(*player_entity) {
+        ctx.print_color(70, 44, RGB::named(rltk::GREY), black, "Sneaking");
+    }
+
+    // Other conditions, listed upwards from just above the gold
+    let mut conditions: Vec<(RGB, String)> = Vec::new();
+    if let Some(rooted) = ecs.read_storage::<Rooted>().get(*player_entity) {
+        conditions.push((RGB::named(rltk::BROWN1), format!("Netted ({} turns)", rooted.turns)));
+    }
+    if let Some(burning) = ecs.read_storage::<Burning>().get(*player_entity) {
+        conditions.push((RGB::named(rltk::ORANGE), format!("Burning ({} turns)", burning.turns)));
+    }
+    if ecs.read_storage::<Swimming>().contains(*player_entity) {
+        conditions.push((RGB::named(rltk::CYAN), "Swimming".to_string()));
+    }
+    for (i, (colour, condition)) in conditions.iter().enumerate() {
+        ctx.print_color(50, 42 - i as i32, *colour, black, condition);
+    }
 
     // Draw log
     let log = ecs.fetch::<GameLog>();
@@ -133,10 +162,34 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     let mouse_pos = ctx.mouse_pos();
     ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::MAGENTA));
 
+    // Draw each creature's current decision
+    if OPTIONS.lock().unwrap().ai_debug {
+        draw_ai_debug(ecs, ctx);
+    }
+
     // Draw tooltips
     draw_tooltips(ecs, ctx);
 }
 
+/// Labels every visible creature with the last decision its AI made.
+fn draw_ai_debug(ecs: &World, ctx: &mut Rltk) {
+    let (min_x, max_x, min_y, max_y) = camera::get_screen_bounds(ecs, ctx);
+    let map = ecs.fetch::<Map>();
+    let positions = ecs.read_storage::<Position>();
+    let behaviours = ecs.read_storage::<Behaviour>();
+
+    for (pos, behaviour) in (&positions, &behaviours).join() {
+        if pos.x < min_x || pos.x >= max_x || pos.y < min_y || pos.y >= max_y { continue; }
+        if !map.visible_tiles[map.xy_idx(pos.x, pos.y)] || behaviour.decision.is_empty() { continue; }
+        let x = pos.x - min_x + 1;
+        let y = pos.y - min_y;
+        let width = i32::min(behaviour.decision.len() as i32, 49 - x);
+        if width > 0 {
+            ctx.print_color(x, y, RGB::named(rltk::YELLOW), RGB::named(rltk::DARKSLATEGRAY), &behaviour.decision[..width as usize]);
+        }
+    }
+}
+
 fn draw_attribute(name: &str, attribute: &Attribute, y: i32, ctx: &mut Rltk) {
     let black = RGB::named(rltk::BLACK);
     let attr_grey: RGB = RGB::from_hex("#CCCCCC").expect("Oops");
@@ -198,6 +251,9 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     let hidden = ecs.read_storage::<Hidden>();
     let attributes = ecs.read_storage::<Attributes>();
     let pools = ecs.read_storage::<Pools>();
+    let asleep = ecs.read_storage::<Asleep>();
+    let routed = ecs.read_storage::<Routed>();
+    let abilities = ecs.read_storage::<Abilities>();
     let entities = ecs.entities();
 
     let mouse_pos = ctx.mouse_pos();
@@ -237,6 +293,18 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
                 tip.add(s);
             }
 
+            if asleep.contains(entity) {
+                tip.add("Asleep".to_string());
+            }
+            if routed.contains(entity) {
+                tip.add("Fleeing in panic".to_string());
+            }
+
+            // Any special abilities we've seen it use
+            for ability in abilities.get(entity).iter().flat_map(|a| a.abilities.iter()).filter(|a| a.known) {
+                tip.add(describe_ability(ability));
+            }
+
             // Comment on pools
             let stat = pools.get(entity);
             if let Some(stat) = stat {
@@ -382,6 +450,44 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     }
 }
 
+/// Shows what's inside a container, for the player to take something out.
+pub fn container_menu(gs: &mut State, ctx: &mut Rltk, container: Entity) -> (ItemMenuResult, Option<Entity>) {
+    let contents = crate::props::container_contents(&gs.ecs, container);
+    let names = gs.ecs.read_storage::<Name>();
+    let title = names.get(container).map_or("Container".to_string(), |n| n.name.clone());
+    let count = contents.len();
+
+    let top = (25 - count / 2) as i32;
+    ctx.draw_box(15, top-2, 31, (count+3) as i32, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK));
+    ctx.print_color(18, top-2, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), format!("Take from the {}", title));
+    ctx.print_color(18, top+count as i32 + 1, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "ESCAPE to cancel");
+
+    for (j, item) in contents.iter().enumerate() {
+        let y = top + j as i32;
+        ctx.set(17, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437('('));
+        ctx.set(18, y, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), 97+j as rltk::FontCharType);
+        ctx.set(19, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437(')'));
+
+        ctx.print(21, y, names.get(*item).map_or("???", |n| n.name.as_str()));
+    }
+
+    match ctx.key {
+        None => (ItemMenuResult::NoResponse, None),
+        Some(key) => {
+            match key {
+                VirtualKeyCode::Escape => { (ItemMenuResult::Cancel, None) }
+                _ => {
+                    let selection = rltk::letter_to_option(key);
+                    if selection > -1 && selection < count as i32 {
+                        return (ItemMenuResult::Selected, Some(contents[selection as usize]));
+                    }
+                    (ItemMenuResult::NoResponse, None)
+                }
+            }
+        }
+    }
+}
+
 /// Shows a menu to allow the player to remove an equipped item and place it in their backpack.
 pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
     let player_entity = gs.ecs.fetch::<Entity>();
@@ -433,6 +539,7 @@ pub fn ranged_target(gs: &mut State, ctx: &mut Rltk, range: i32) -> (ItemMenuRes
     let player_entity = gs.ecs.fetch::<Entity>();
     let player_pos = gs.ecs.fetch::<Point>();
     let viewsheds = gs.ecs.read_storage::<Viewshed>();
+    let map = gs.ecs.fetch::<Map>();
 
     ctx.print_color(5, 0, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Select Target: ");
 
@@ -442,6 +549,7 @@ pub fn ranged_target(gs: &mut State, ctx: &mut Rltk, range: i32) -> (ItemMenuRes
     if let Some(visible) = visible {
         // We have a viewshed
         for idx in visible.visible_tiles.iter() {
+            if !map.visible_tiles[map.xy_idx(idx.x, idx.y)] { continue; } // Can't aim at what you can't see
             let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *idx);
             if distance <= range as f32 {
                 let screen_x = idx.x - min_x;
@@ -598,15 +706,676 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     MainMenuResult::NoSelection { selected: MainMenuSelection::NewGame }
 }
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum CharacterCreationResult { NoResponse, Cancel, Done }
+
+/// Shows the character creation screen, where the player spends attribute points, picks a
+/// background and names their character. The choices are stored in `gs.new_character`.
+pub fn character_creation(gs: &mut State, ctx: &mut Rltk) -> CharacterCreationResult {
+    use crate::character_creation::{CreationStage, ATTRIBUTE_NAMES};
+    let backgrounds = crate::raws::background_list(&crate::raws::RAWS.lock().unwrap());
+    let character = &mut gs.new_character;
+
+    let black = RGB::named(rltk::BLACK);
+    let white = RGB::named(rltk::WHITE);
+    let yellow = RGB::named(rltk::YELLOW);
+    let magenta = RGB::named(rltk::MAGENTA);
+    let grey = RGB::named(rltk::GREY);
+
+    ctx.draw_box_double(10, 4, 59, 44, RGB::named(rltk::WHEAT), black);
+    ctx.print_color_centered(5, yellow, black, "Create your character");
+
+    // Attributes
+    let attr_colour = if character.stage == CreationStage::Attributes { white } else { grey };
+    ctx.print_color(13, 8, attr_colour, black, "Attributes");
+    ctx.print_color(40, 8, attr_colour, black, format!("Points remaining: {}", character.points_remaining()));
+    for (i, name) in ATTRIBUTE_NAMES.iter().enumerate() {
+        let y = 10 + i as i32;
+        let selected = character.stage == CreationStage::Attributes && character.selected_attribute == i;
+        let fg = if selected { magenta } else { attr_colour };
+        ctx.print_color(15, y, fg, black, name);
+        ctx.print_color(30, y, fg, black, format!("{:>2}", character.attributes[i]));
+        let bonus = crate::attr_bonus(character.attributes[i]);
+        ctx.print_color(34, y, fg, black, format!("({:+})", bonus));
+    }
+
+    // Backgrounds
+    let bg_colour = if character.stage == CreationStage::Background { white } else { grey };
+    ctx.print_color(13, 16, bg_colour, black, "Background");
+    for (i, background) in backgrounds.iter().enumerate() {
+        let y = 18 + i as i32;
+        let fg = if character.background == i { magenta } else { bg_colour };
+        ctx.set(15, y, bg_colour, black, rltk::to_cp437('('));
+        ctx.set(16, y, yellow, black, 97 + i as rltk::FontCharType);
+        ctx.set(17, y, bg_colour, black, rltk::to_cp437(')'));
+        ctx.print_color(19, y, fg, black, &background.0);
+    }
+    let description_y = 19 + backgrounds.len() as i32;
+    if let Some(background) = backgrounds.get(character.background) {
+        ctx.print_color(15, description_y, bg_colour, black, &background.1);
+    }
+
+    // Name
+    let name_colour = if character.stage == CreationStage::Name { white } else { grey };
+    ctx.print_color(13, description_y + 3, name_colour, black, "Name");
+    let cursor = if character.stage == CreationStage::Name { "_" } else { "" };
+    ctx.print_color(15, description_y + 5, name_colour, black, format!("{}{}", character.name, cursor));
+
+    // Instructions for the current stage
+    let instructions = match character.stage {
+        CreationStage::Attributes => "[Up/Down] Choose  [Left/Right] Adjust  [Enter] Next",
+        CreationStage::Background => "[Letter] Choose  [Enter] Next  [Esc] Back",
+        CreationStage::Name => "Type a name  [Enter] Begin  [Esc] Back",
+    };
+    ctx.print_color(13, 46, yellow, black, instructions);
+
+    match ctx.key {
+        None => CharacterCreationResult::NoResponse,
+        Some(key) => {
+            match character.stage {
+                CreationStage::Attributes => match key {
+                    VirtualKeyCode::Escape => return CharacterCreationResult::Cancel,
+                    VirtualKeyCode::Up => {
+                        character.selected_attribute = (character.selected_attribute + ATTRIBUTE_NAMES.len() - 1) % ATTRIBUTE_NAMES.len();
+                    }
+                    VirtualKeyCode::Down => {
+                        character.selected_attribute = (character.selected_attribute + 1) % ATTRIBUTE_NAMES.len();
+                    }
+                    VirtualKeyCode::Left | VirtualKeyCode::Minus => { character.lower(character.selected_attribute); }
+                    VirtualKeyCode::Right | VirtualKeyCode::Equals => { character.raise(character.selected_attribute); }
+                    VirtualKeyCode::Return => character.stage = CreationStage::Background,
+                    _ => {}
+                },
+                CreationStage::Background => match key {
+                    VirtualKeyCode::Escape => character.stage = CreationStage::Attributes,
+                    VirtualKeyCode::Up => {
+                        if character.background > 0 { character.background -= 1; }
+                    }
+                    VirtualKeyCode::Down => {
+                        if character.background + 1 < backgrounds.len() { character.background += 1; }
+                    }
+                    VirtualKeyCode::Return => character.stage = CreationStage::Name,
+                    _ => {
+                        let selection = rltk::letter_to_option(key);
+                        if selection > -1 && (selection as usize) < backgrounds.len() {
+                            character.background = selection as usize;
+                        }
+                    }
+                },
+                CreationStage::Name => match key {
+                    VirtualKeyCode::Escape => character.stage = CreationStage::Background,
+                    VirtualKeyCode::Back => { character.name.pop(); }
+                    VirtualKeyCode::Space => {
+                        if !character.name.is_empty() && character.name.len() < 20 { character.name.push(' '); }
+                    }
+                    VirtualKeyCode::Return => return CharacterCreationResult::Done,
+                    _ => {
+                        let letter = rltk::letter_to_option(key);
+                        if letter > -1 && character.name.len() < 20 {
+                            let c = (b'a' + letter as u8) as char;
+                            // Capitalise the start of each word
+                            if ctx.shift || character.name.is_empty() || character.name.ends_with(' ') {
+                                character.name.push(c.to_ascii_uppercase());
+                            } else {
+                                character.name.push(c);
+                            }
+                        }
+                    }
+                },
+            }
+            CharacterCreationResult::NoResponse
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum LevelUpResult { NoResponse, Attribute(usize), Skill(Skill), Done }
+
+pub fn level_up(ecs: &World, ctx: &mut Rltk) -> LevelUpResult {
+    use crate::character_creation::ATTRIBUTE_NAMES;
+    let learnable_skills = crate::raws::skill_list(&crate::raws::RAWS.lock().unwrap());
+    let player_entity = ecs.fetch::<Entity>();
+    let pools = ecs.read_storage::<Pools>();
+    let attributes = ecs.read_storage::<Attributes>();
+    let skills = ecs.read_storage::<Skills>();
+    let pending = ecs.read_storage::<PendingLevelUp>();
+
+    let black = RGB::named(rltk::BLACK);
+    let white = RGB::named(rltk::WHITE);
+    let yellow = RGB::named(rltk::YELLOW);
+    let grey = RGB::named(rltk::GREY);
+
+    let (attribute_points, skill_points) = pending.get(*player_entity)
+        .map_or((0, 0), |p| (p.attribute_points, p.skill_points));
+    let level = pools.get(*player_entity).map_or(1, |p| p.level);
+
+    ctx.draw_box_double(15, 8, 49, 30, RGB::named(rltk::WHEAT), black);
+    ctx.print_color_centered(9, yellow, black, format!("You have reached level {}!", level));
+
+    ctx.print_color(18, 12, white, black, "Attributes");
+    ctx.print_color(45, 12, white, black, format!("Points: {}", attribute_points));
+    if let Some(attr) = attributes.get(*player_entity) {
+        let values = [&attr.might, &attr.fitness, &attr.quickness, &attr.intelligence];
+        for (i, name) in ATTRIBUTE_NAMES.iter().enumerate() {
+            let y = 14 + i as i32;
+            let fg = if attribute_points > 0 { white } else { grey };
+            ctx.set(20, y, fg, black, rltk::to_cp437('('));
+            ctx.set(21, y, yellow, black, 97 + i as rltk::FontCharType);
+            ctx.set(22, y, fg, black, rltk::to_cp437(')'));
+            ctx.print_color(24, y, fg, black, name);
+            ctx.print_color(40, y, fg, black, format!("{:>2}", values[i].base));
+        }
+    }
+
+    ctx.print_color(18, 20, white, black, "Skills");
+    ctx.print_color(45, 20, white, black, format!("Points: {}", skill_points));
+    if let Some(player_skills) = skills.get(*player_entity) {
+        for (i, skill) in learnable_skills.iter().enumerate() {
+            let y = 22 + i as i32;
+            let option = ATTRIBUTE_NAMES.len() + i;
+            let fg = if skill_points > 0 { white } else { grey };
+            ctx.set(20, y, fg, black, rltk::to_cp437('('));
+            ctx.set(21, y, yellow, black, 97 + option as rltk::FontCharType);
+            ctx.set(22, y, fg, black, rltk::to_cp437(')'));
+            ctx.print_color(24, y, fg, black, format!("{:?}", skill));
+            ctx.print_color(40, y, fg, black, format!("{:>2}", player_skills.skills.get(skill).copied().unwrap_or(0)));
+        }
+    }
+
+    if attribute_points > 0 || skill_points > 0 {
+        ctx.print_color(18, 35, grey, black, "Unspent points will be lost.");
+    }
+    ctx.print_color(18, 36, yellow, black, "[Letter] Spend a point  [Enter] Done");
+
+    match ctx.key {
+        None => LevelUpResult::NoResponse,
+        Some(key) => {
+            match key {
+                VirtualKeyCode::Return => LevelUpResult::Done,
+                _ => {
+                    let selection = rltk::letter_to_option(key);
+                    if selection < 0 {
+                        LevelUpResult::NoResponse
+                    } else if (selection as usize) < ATTRIBUTE_NAMES.len() {
+                        LevelUpResult::Attribute(selection as usize)
+                    } else if (selection as usize) < ATTRIBUTE_NAMES.len() + learnable_skills.len() {
+                        LevelUpResult::Skill(learnable_skills[selection as usize - ATTRIBUTE_NAMES.len()])
+                    } else {
+                        LevelUpResult::NoResponse
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Splits text into lines no wider than `width`, breaking between words.
+fn word_wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split_whitespace() {
+        let line = lines.last_mut().unwrap();
+        if !line.is_empty() && line.len() + word.len() + 1 > width {
+            lines.push(word.to_string());
+        } else {
+            if !line.is_empty() { line.push(' '); }
+            line.push_str(word);
+        }
+    }
+    lines
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum DialogueResult { NoResponse, Leave, Chose(usize) }
+
+/// Shows what a creature is saying, and the responses the player can give. Picking a response
+/// returns its index within the node.
+pub fn dialogue(ecs: &World, ctx: &mut Rltk, npc: Entity, node: usize) -> DialogueResult {
+    let black = RGB::named(rltk::BLACK);
+    let white = RGB::named(rltk::WHITE);
+    let yellow = RGB::named(rltk::YELLOW);
+
+    let name = ecs.read_storage::<Name>().get(npc).map_or("Someone".to_string(), |n| n.name.clone());
+    let (text, responses) = crate::dialogue::node_text(ecs, npc, node);
+    let lines = word_wrap(&text, 54);
+
+    let height = (lines.len() + responses.len() + 4) as i32;
+    let mut y = 22 - height / 2;
+    ctx.draw_box(10, y, 59, height + 1, white, black);
+    ctx.print_color(13, y, yellow, black, &name);
+    ctx.print_color(13, y + height + 1, yellow, black, "ESCAPE to leave");
+    y += 2;
+    for line in lines.iter() {
+        ctx.print(13, y, line);
+        y += 1;
+    }
+    y += 1;
+    for (j, (_, response)) in responses.iter().enumerate() {
+        ctx.set(13, y, white, black, rltk::to_cp437('('));
+        ctx.set(14, y, yellow, black, 97 + j as rltk::FontCharType);
+        ctx.set(15, y, white, black, rltk::to_cp437(')'));
+        ctx.print(17, y, response);
+        y += 1;
+    }
+
+    match ctx.key {
+        None => DialogueResult::NoResponse,
+        Some(VirtualKeyCode::Escape) => DialogueResult::Leave,
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && selection < responses.len() as i32 {
+                return DialogueResult::Chose(responses[selection as usize].0);
+            }
+            DialogueResult::NoResponse
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ShopResult { NoResponse, Leave, Buy(usize) }
+
+/// Shows what a shop has for sale, and how much gold the player has to spend.
+pub fn shop(ecs: &World, ctx: &mut Rltk, vendor: Entity, shop: usize) -> ShopResult {
+    let black = RGB::named(rltk::BLACK);
+    let white = RGB::named(rltk::WHITE);
+    let yellow = RGB::named(rltk::YELLOW);
+    let grey = RGB::named(rltk::GREY);
+
+    let player_entity = ecs.fetch::<Entity>();
+    let gold = ecs.read_storage::<Pools>().get(*player_entity).map_or(0, |p| p.gold);
+    let name = ecs.read_storage::<Name>().get(vendor).map_or("Shop".to_string(), |n| n.name.clone());
+    let stock = crate::dialogue::shop_stock(shop);
+    let count = stock.len() as i32;
+
+    let top = 25 - count / 2;
+    ctx.draw_box(15, top-2, 41, count + 3, white, black);
+    ctx.print_color(18, top-2, yellow, black, format!("{}'s wares", name));
+    ctx.print_color(18, top+count+1, yellow, black, format!("ESCAPE to leave   Gold: {}", gold));
+    for (j, (item, price)) in stock.iter().enumerate() {
+        let y = top + j as i32;
+        let fg = if *price > gold { grey } else { white };
+        ctx.set(17, y, white, black, rltk::to_cp437('('));
+        ctx.set(18, y, yellow, black, 97 + j as rltk::FontCharType);
+        ctx.set(19, y, white, black, rltk::to_cp437(')'));
+        ctx.print_color(21, y, fg, black, item);
+        ctx.print_color(48, y, fg, black, format!("{:>4} gp", price));
+    }
+
+    match ctx.key {
+        None => ShopResult::NoResponse,
+        Some(VirtualKeyCode::Escape) => ShopResult::Leave,
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && selection < count {
+                return ShopResult::Buy(selection as usize);
+            }
+            ShopResult::NoResponse
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CharacterSheetResult { NoResponse, Close }
+
+pub fn character_sheet(ecs: &World, ctx: &mut Rltk) -> CharacterSheetResult {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let pools = ecs.read_storage::<Pools>();
+    let attributes = ecs.read_storage::<Attributes>();
+    let skills = ecs.read_storage::<Skills>();
+    let practice = ecs.read_storage::<SkillPractice>();
+
+    let box_grey: RGB = RGB::from_hex("#999999").expect("Oops");
+    let black = RGB::named(rltk::BLACK);
+    let white = RGB::named(rltk::WHITE);
+    let yellow = RGB::named(rltk::YELLOW);
+    let grey = RGB::named(rltk::GREY);
+    let green = RGB::from_f32(0.0, 1.0, 0.0);
+    let red = RGB::from_f32(1.0, 0.0, 0.0);
+
+    draw_hollow_box(ctx, 0, 0, 79, 59, box_grey, black);
+    let name = names.get(*player_entity).map_or("Player".to_string(), |n| n.name.clone());
+    ctx.print_color_centered(1, yellow, black, format!("{} - Character Sheet", name));
+
+    // Attributes, with the breakdown of where they come from
+    ctx.print_color(2, 3, yellow, black, "Attributes");
+    ctx.print_color(18, 3, grey, black, "Base  Mod  Total  Bonus");
+    if let Some(attr) = attributes.get(*player_entity) {
+        let values = [
+            ("Might", &attr.might), ("Fitness", &attr.fitness),
+            ("Quickness", &attr.quickness), ("Intelligence", &attr.intelligence)
+        ];
+        for (i, (name, attribute)) in values.iter().enumerate() {
+            let y = 5 + i as i32;
+            let colour = if attribute.modifiers < 0 { red } else if attribute.modifiers == 0 { white } else { green };
+            ctx.print_color(3, y, white, black, name);
+            ctx.print_color(18, y, white, black, format!("{:>4}", attribute.base));
+            ctx.print_color(24, y, colour, black, format!("{:>+3}", attribute.modifiers));
+            ctx.print_color(29, y, colour, black, format!("{:>5}", attribute.base + attribute.modifiers));
+            ctx.print_color(36, y, colour, black, format!("{:>+5}", attribute.bonus));
+        }
+    }
+
+    // Pools and experience
+    ctx.print_color(2, 10, yellow, black, "Vitals");
+    if let Some(player_pools) = pools.get(*player_entity) {
+        let (xp_level_start, xp_level_end) = {
+            let raws = &crate::raws::RAWS.lock().unwrap();
+            (crate::raws::xp_for_level(raws, player_pools.level), crate::raws::xp_for_level(raws, player_pools.level + 1))
+        };
+        ctx.print_color(3, 12, white, black, format!("Health: {}/{}", player_pools.hit_points.current, player_pools.hit_points.max));
+        ctx.print_color(3, 13, white, black, format!("Mana: {}/{}", player_pools.mana.current, player_pools.mana.max));
+        ctx.print_color(3, 14, white, black, format!("Level: {}", player_pools.level));
+        ctx.print_color(3, 15, white, black, format!("XP: {} ({} to next level)", player_pools.xp, xp_level_end - player_pools.xp));
+        ctx.draw_bar_horizontal(3, 16, 36, player_pools.xp - xp_level_start, xp_level_end - xp_level_start, RGB::named(rltk::GOLD), black);
+        ctx.print_color(3, 17, RGB::named(rltk::GOLD), black, format!("Gold: {}", player_pools.gold));
+    }
+
+    // Status effects
+    ctx.print_color(2, 19, yellow, black, "Status");
+    let mut y = 21;
+    if let Some(hc) = ecs.read_storage::<HungerClock>().get(*player_entity) {
+        match hc.state {
+            HungerState::WellFed => ctx.print_color(3, y, green, black, "Well Fed"),
+            HungerState::Normal => ctx.print_color(3, y, white, black, "Not hungry"),
+            HungerState::Hungry => ctx.print_color(3, y, RGB::named(rltk::ORANGE), black, "Hungry"),
+            HungerState::Starving => ctx.print_color(3, y, red, black, "Starving"),
+        }
+        y += 1;
+    }
+    if let Some(stunned) = ecs.read_storage::<Stunned>().get(*player_entity) {
+        ctx.print_color(3, y, RGB::named(rltk::CYAN), black, format!("Stunned ({} turns)", stunned.turns));
+        y += 1;
+    }
+    if let Some(poisoned) = ecs.read_storage::<Poisoned>().get(*player_entity) {
+        ctx.print_color(3, y, green, black, format!("Poisoned ({} turns)", poisoned.turns));
+        y += 1;
+    }
+    if ecs.read_storage::<Sneaking>().contains(*player_entity) {
+        ctx.print_color(3, y, RGB::named(rltk::GREY), black, "Sneaking");
+        y += 1;
+    }
+    if ecs.read_storage::<PendingLevelUp>().contains(*player_entity) {
+        ctx.print_color(3, y, RGB::named(rltk::GOLD), black, "Unspent level-up points");
+    }
+
+    // Equipment, with the stats of each item
+    ctx.print_color(2, 26, yellow, black, "Equipment");
+    let equipped = ecs.read_storage::<Equipped>();
+    let weapons = ecs.read_storage::<MeleeWeapon>();
+    let wearables = ecs.read_storage::<Wearable>();
+    let bonuses = ecs.read_storage::<AttributeBonus>();
+    let cursed = ecs.read_storage::<Cursed>();
+    let entities = ecs.entities();
+    let mut y = 28;
+    for (item, equipped_by, item_name) in (&entities, &equipped, &names).join() {
+        if equipped_by.owner != *player_entity || y > 55 { continue; }
+        let colour = if cursed.get(item).is_some() { red } else { white };
+        ctx.print_color(3, y, grey, black, format!("{:?}", equipped_by.slot));
+        ctx.print_color(11, y, colour, black, &item_name.name);
+        y += 1;
+
+        let mut stats: Vec<String> = Vec::new();
+        if let Some(weapon) = weapons.get(item) {
+            let bonus = if weapon.damage_bonus != 0 { format!("{:+}", weapon.damage_bonus) } else { String::new() };
+            stats.push(format!("{}d{}{} damage", weapon.damage_n_dice, weapon.damage_die_type, bonus));
+            stats.push(format!("{:+} to hit", weapon.hit_bonus));
+            stats.push(format!("{:?}", weapon.attribute));
+        }
+        if let Some(wearable) = wearables.get(item) {
+            stats.push(format!("AC {:+}", wearable.armour_class));
+        }
+        if let Some(bonus) = bonuses.get(item) {
+            let attributes = [("Might", bonus.might), ("Fitness", bonus.fitness), ("Quickness", bonus.quickness), ("Int", bonus.intelligence)];
+            for (name, value) in attributes.iter() {
+                if let Some(value) = value { stats.push(format!("{:+} {}", value, name)); }
+            }
+        }
+        if cursed.get(item).is_some() {
+            stats.push("Cursed".to_string());
+        }
+        ctx.print_color(11, y, grey, black, stats.join(", "));
+        y += 1;
+    }
+
+    // Skills, and how close each is to improving
+    ctx.print_color(42, 3, yellow, black, "Skills");
+    if let Some(player_skills) = skills.get(*player_entity) {
+        let raws = &crate::raws::RAWS.lock().unwrap();
+        let mut y = 5;
+        for skill in crate::raws::skill_list(raws).iter() {
+            let definition = crate::raws::skill_definition(raws, *skill).unwrap();
+            let rank = player_skills.skills.get(skill).copied();
+            let fg = if rank.is_some() { white } else { grey };
+            ctx.print_color(43, y, fg, black, format!("{:?}", skill));
+            match rank {
+                Some(rank) => ctx.print_color(56, y, fg, black, format!("{:>2}", rank)),
+                None => ctx.print_color(56, y, fg, black, format!("{:>2} untrained", definition.untrained)),
+            }
+            if let (Some(rank), Some(practice)) = (rank, practice.get(*player_entity)) {
+                let uses = practice.uses.get(skill).copied().unwrap_or(0);
+                let needed = definition.training_uses * i32::max(1, rank + 1);
+                ctx.draw_bar_horizontal(67, y, 10, uses, needed, RGB::named(rltk::GOLD), black);
+            }
+            ctx.print_color(44, y + 1, grey, black, &definition.description);
+            y += 3;
+        }
+    }
+
+    ctx.print_color(2, 58, yellow, black, "[Esc] Close");
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::Return) => CharacterSheetResult::Close,
+        _ => CharacterSheetResult::NoResponse
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum OrdersResult { NoResponse, Cancel, Order(CompanionOrder) }
+
+/// Lists the player's companions and what they're doing, and lets the player give them all a
+/// new order.
+pub fn companion_orders(ecs: &World, ctx: &mut Rltk) -> OrdersResult {
+    const ORDERS: [(CompanionOrder, &str); 3] = [
+        (CompanionOrder::Follow, "Follow me"),
+        (CompanionOrder::Stay, "Stay here"),
+        (CompanionOrder::Attack, "Attack at will"),
+    ];
+    let black = RGB::named(rltk::BLACK);
+    let white = RGB::named(rltk::WHITE);
+    let yellow = RGB::named(rltk::YELLOW);
+    let grey = RGB::named(rltk::GREY);
+
+    let companions = crate::companion::companion_list(ecs);
+    let height = (companions.len() + ORDERS.len() + 1) as i32;
+    let mut y = 25 - height / 2;
+    ctx.draw_box(15, y-2, 41, height + 3, white, black);
+    ctx.print_color(18, y-2, yellow, black, "Companions");
+    ctx.print_color(18, y+height+1, yellow, black, "ESCAPE to cancel");
+    for (name, order) in companions.iter() {
+        let doing = ORDERS.iter().find(|o| o.0 == *order).map_or("", |o| o.1);
+        ctx.print_color(18, y, white, black, name);
+        ctx.print_color(36, y, grey, black, doing);
+        y += 1;
+    }
+    y += 1;
+    for (j, (_, text)) in ORDERS.iter().enumerate() {
+        ctx.set(17, y, white, black, rltk::to_cp437('('));
+        ctx.set(18, y, yellow, black, 97 + j as rltk::FontCharType);
+        ctx.set(19, y, white, black, rltk::to_cp437(')'));
+        ctx.print(21, y, text);
+        y += 1;
+    }
+
+    match ctx.key {
+        None => OrdersResult::NoResponse,
+        Some(VirtualKeyCode::Escape) => OrdersResult::Cancel,
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && selection < ORDERS.len() as i32 {
+                return OrdersResult::Order(ORDERS[selection as usize].0);
+            }
+            OrdersResult::NoResponse
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum JournalResult { NoResponse, Close }
+
+/// Lists the player's quests, current ones first, with how far along each objective is.
+pub fn quest_journal(ecs: &World, ctx: &mut Rltk) -> JournalResult {
+    use crate::quest_system::{objective_goal, objective_text};
+    let box_grey: RGB = RGB::from_hex("#999999").expect("Oops");
+    let black = RGB::named(rltk::BLACK);
+    let white = RGB::named(rltk::WHITE);
+    let yellow = RGB::named(rltk::YELLOW);
+    let grey = RGB::named(rltk::GREY);
+
+    draw_hollow_box(ctx, 0, 0, 79, 59, box_grey, black);
+    ctx.print_color_centered(1, yellow, black, "Quest Journal");
+
+    let player_entity = ecs.fetch::<Entity>();
+    let logs = ecs.read_storage::<QuestLog>();
+    let mut quests: Vec<&QuestProgress> = logs.get(*player_entity).map_or(Vec::new(), |l| l.quests.iter().collect());
+    quests.sort_by_key(|q| q.state != QuestState::Active);
+    if quests.is_empty() {
+        ctx.print_color(2, 3, grey, black, "You haven't taken on any quests yet.");
+    }
+
+    let raws = &crate::raws::RAWS.lock().unwrap();
+    let mut y = 3;
+    for progress in quests {
+        let quest = if let Some(quest) = crate::raws::get_quest(raws, &progress.name) { quest } else { continue; };
+        let (colour, status) = match progress.state {
+            QuestState::Active => (yellow, ""),
+            QuestState::Complete => (RGB::named(rltk::GREEN), " (complete)"),
+            QuestState::Failed => (RGB::named(rltk::RED), " (failed)"),
+        };
+        if y > 55 { break; }
+        ctx.print_color(2, y, colour, black, format!("{}{}", progress.name, status));
+        y += 1;
+        let fg = if progress.state == QuestState::Active { white } else { grey };
+        for line in word_wrap(&quest.description, 74) {
+            ctx.print_color(4, y, fg, black, line);
+            y += 1;
+        }
+        for (i, objective) in quest.objectives.iter().enumerate() {
+            let goal = objective_goal(objective);
+            let done = progress.progress.get(i).copied().unwrap_or(0);
+            let tick = if done >= goal { "[x]" } else { "[ ]" };
+            let count = if goal > 1 { format!(" ({}/{})", done, goal) } else { String::new() };
+            ctx.print_color(4, y, fg, black, format!("{} {}{}", tick, objective_text(objective), count));
+            y += 1;
+        }
+        y += 1;
+    }
+
+    for line in run_record_lines(ecs) {
+        if y > 56 { break; }
+        ctx.print_color(2, y, grey, black, line);
+        y += 1;
+    }
+
+    ctx.print_color(2, 58, yellow, black, "[Esc] Close");
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::Return) => JournalResult::Close,
+        _ => JournalResult::NoResponse
+    }
+}
+
+/// Describes the notable foes slain this run.
+fn run_record_lines(ecs: &World) -> Vec<String> {
+    let player_entity = ecs.fetch::<Entity>();
+    let records = ecs.read_storage::<RunRecord>();
+    let killed = records.get(*player_entity).map_or(Vec::new(), |r| r.uniques_killed.clone());
+    if killed.is_empty() {
+        return vec!["No notable foes have fallen.".to_string()];
+    }
+    let mut lines = vec!["Notable foes slain:".to_string()];
+    for (name, depth) in killed {
+        lines.push(format!("{} (depth {})", name, depth));
+    }
+    lines
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum MessageLogResult { NoResponse, Close, Scroll{ offset: usize } }
+
+/// Shows every message in the game log, oldest first. `offset` is how many lines the view has
+/// been scrolled up from the newest message. Typing filters the log to matching messages.
+pub fn message_log(gs: &mut State, ctx: &mut Rltk, offset: usize) -> MessageLogResult {
+    const PAGE_HEIGHT: usize = 53;
+    let box_grey: RGB = RGB::from_hex("#999999").expect("Oops");
+    let black = RGB::named(rltk::BLACK);
+    let white = RGB::named(rltk::WHITE);
+    let yellow = RGB::named(rltk::YELLOW);
+    let grey = RGB::named(rltk::GREY);
+
+    let filter = gs.log_filter.to_lowercase();
+    let messages: Vec<String> = gs.ecs.fetch::<GameLog>().entries.iter()
+        .filter(|m| filter.is_empty() || m.to_lowercase().contains(&filter))
+        .cloned()
+        .collect();
+    let max_offset = messages.len().saturating_sub(PAGE_HEIGHT);
+    let offset = usize::min(offset, max_offset);
+
+    draw_hollow_box(ctx, 0, 0, 79, 59, box_grey, black);
+    ctx.print_color_centered(1, yellow, black, "Message History");
+    ctx.print_color(2, 2, white, black, format!("Filter: {}_", gs.log_filter));
+    ctx.print_color(60, 2, grey, black, format!("{} messages", messages.len()));
+
+    let end = messages.len() - offset;
+    let start = end.saturating_sub(PAGE_HEIGHT);
+    for (i, message) in messages[start..end].iter().enumerate() {
+        ctx.print(2, 4 + i as i32, message);
+    }
+    if start > 0 { ctx.print_color(76, 4, grey, black, "^"); }
+    if offset > 0 { ctx.print_color(76, 56, grey, black, "v"); }
+
+    ctx.print_color(2, 58, yellow, black, "[Up/Down/PgUp/PgDn] Scroll  [Type] Filter  [Esc] Close");
+
+    match ctx.key {
+        None => MessageLogResult::NoResponse,
+        Some(key) => match key {
+            VirtualKeyCode::Escape => MessageLogResult::Close,
+            VirtualKeyCode::Up => MessageLogResult::Scroll{ offset: usize::min(offset + 1, max_offset) },
+            VirtualKeyCode::Down => MessageLogResult::Scroll{ offset: offset.saturating_sub(1) },
+            VirtualKeyCode::PageUp => MessageLogResult::Scroll{ offset: usize::min(offset + PAGE_HEIGHT, max_offset) },
+            VirtualKeyCode::PageDown => MessageLogResult::Scroll{ offset: offset.saturating_sub(PAGE_HEIGHT) },
+            VirtualKeyCode::Back => {
+                gs.log_filter.pop();
+                MessageLogResult::Scroll{ offset: 0 }
+            }
+            VirtualKeyCode::Space => {
+                gs.log_filter.push(' ');
+                MessageLogResult::Scroll{ offset: 0 }
+            }
+            _ => {
+                let letter = rltk::letter_to_option(key);
+                if letter > -1 {
+                    gs.log_filter.push((b'a' + letter as u8) as char);
+                    MessageLogResult::Scroll{ offset: 0 }
+                } else {
+                    MessageLogResult::NoResponse
+                }
+            }
+        }
+    }
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum GameOverResult { NoSelection, QuitToMenu }
 
-pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
+pub fn game_over(ecs: &World, ctx: &mut Rltk) -> GameOverResult {
     ctx.print_color_centered(15, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Your journey has ended!");
-    ctx.print_color_centered(16, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), "One day, we'll tell you about how you did...");
-    ctx.print_color_centered(17, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), "But sadly, that day is not today.");
+    let mut y = 17;
+    for line in run_record_lines(ecs) {
+        ctx.print_color_centered(y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), line);
+        y += 1;
+    }
 
-    ctx.print_color_centered(20, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "Press ENTER or ESC to return to the menu");
+    ctx.print_color_centered(y + 1, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "Press ENTER or ESC to return to the menu");
     match ctx.key {
         None => GameOverResult::NoSelection,
         Some(key) => {
@@ -625,7 +1394,7 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
 pub enum HelpResult { NoSelection, ReturnToGame }
 
 pub fn help_screen(ctx: &mut Rltk) -> HelpResult {
-    ctx.draw_box_double(15, 14, 49, 15, RGB::named(rltk::WHEAT), RGB::named(rltk::BLACK));
+    ctx.draw_box_double(15, 14, 49, 21, RGB::named(rltk::WHEAT), RGB::named(rltk::BLACK));
     ctx.print_color_centered(15, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Key help");
     let mut y = 17;
     let mut x = 17;
@@ -653,6 +1422,14 @@ pub fn help_screen(ctx: &mut Rltk) -> HelpResult {
             y += 1;
             print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[d] Drop");
             y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[q] Quest journal");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[c] Close door");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[s] Search for traps");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[e] Use or disarm nearby");
+            y += 1;
         }
         KeybindType::Numpad => {
             print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[8] Move up");
@@ -676,6 +1453,14 @@ pub fn help_screen(ctx: &mut Rltk) -> HelpResult {
             y += 1;
             print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[d] Drop");
             y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[j] Quest journal");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[c] Close door");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[s] Search for traps");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[e] Use or disarm nearby");
+            y += 1;
         }
         KeybindType::Wasd => {
             print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[w] Move up");
@@ -699,6 +1484,14 @@ pub fn help_screen(ctx: &mut Rltk) -> HelpResult {
             y += 1;
             print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[t] Drop");
             y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[j] Quest journal");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[v] Close door");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[k] Search for traps");
+            y += 1;
+            print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[u] Use or disarm nearby");
+            y += 1;
         }
     }
 
@@ -710,6 +1503,18 @@ pub fn help_screen(ctx: &mut Rltk) -> HelpResult {
     y += 1;
     print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[/] Help");
     y += 1;
+    print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[p] Character sheet");
+    y += 1;
+    print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[o] Message history");
+    y += 1;
+    print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[Tab] Sneak");
+    y += 1;
+    print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[b] Bash door");
+    y += 1;
+    print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[Bump] Talk");
+    y += 1;
+    print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[f] Order companions");
+    y += 1;
     print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[.] Descend");
     y += 1;
     print_with_brackets(ctx, x, y, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), "[Esc] Main menu");
@@ -5,7 +5,8 @@ use super::{
     Consumable, InflictsDamage, Map, SufferDamage, AreaOfEffect,
     Stunned, Equippable, Equipped, WantsToRemoveItem,
     particle_system::ParticleBuilder, ProvidesFood, HungerClock,
-    HungerState, MagicMapper, RunState,
+    HungerState, MagicMapper, RunState, Cursed, ProvidesRemoveCurse, Ignites,
+    noise_system::{NoiseBuilder, EXPLOSION_NOISE}, terrain_system::TerrainBuilder, quest_system::{QuestEvents, QuestEvent},
 };
 
 pub struct ItemCollectionSystem {}
@@ -18,10 +19,11 @@ impl<'a> System<'a> for ItemCollectionSystem {
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
         WriteStorage<'a, InBackpack>,
+        WriteExpect<'a, QuestEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack) = data;
+        let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack, mut quest_events) = data;
 
         for pickup in wants_pickup.join() {
             // Iterate through all entities which want to pick something up.
@@ -30,7 +32,9 @@ impl<'a> System<'a> for ItemCollectionSystem {
 
             if pickup.collected_by == *player_entity {
                 // If picked up by player, log
-                gamelog.entries.push(format!("You pick up the {}", names.get(pickup.item).unwrap().name));
+                let name = &names.get(pickup.item).unwrap().name;
+                gamelog.entries.push(format!("You pick up the {}", name));
+                quest_events.request(QuestEvent::Collected(name.clone()));
             }
         }
 
@@ -64,6 +68,9 @@ impl<'a> System<'a> for ItemUseSystem {
         WriteStorage<'a, HungerClock>,
         ReadStorage<'a, MagicMapper>,
         WriteExpect<'a, RunState>,
+        WriteStorage<'a, Cursed>,
+        ReadStorage<'a, ProvidesRemoveCurse>,
+        (WriteExpect<'a, NoiseBuilder>, WriteExpect<'a, TerrainBuilder>, ReadStorage<'a, Ignites>),
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -72,7 +79,8 @@ impl<'a> System<'a> for ItemUseSystem {
             consumables, healing, inflict_damage, mut combat_stats,
             mut suffer_damage, aoe, mut stunned, equippable, mut equipped,
             mut backpack, mut particle_builder, positions, provides_food,
-            mut hungerclocks, magic_mapper, mut runstate,
+            mut hungerclocks, magic_mapper, mut runstate, mut cursed,
+            remove_curse, (mut noise_builder, mut terrain_builder, ignites),
         ) = data;
 
         for (entity, useitem) in (&entities, &wants_use).join() {
@@ -95,6 +103,7 @@ impl<'a> System<'a> for ItemUseSystem {
                         }
                         Some(area_effect) => {
                             // AoE
+                            noise_builder.request(target.x, target.y, EXPLOSION_NOISE, entity);
                             let mut blast_tiles = rltk::field_of_view(target, area_effect.radius, &*map);
                             blast_tiles.retain(|p| p.x > 0 && p.x < map.width-1 && p.y > 0 && p.y < map.height-1);
                             for tile_idx in blast_tiles.iter() {
@@ -103,6 +112,9 @@ impl<'a> System<'a> for ItemUseSystem {
                                     targets.push(*mob);
                                 }
                                 particle_builder.request(tile_idx.x, tile_idx.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('░'), 200.0);
+                                if ignites.contains(useitem.item) {
+                                    terrain_builder.ignite(tile_idx.x, tile_idx.y);
+                                }
                             }
                         }
                     }
@@ -117,26 +129,43 @@ impl<'a> System<'a> for ItemUseSystem {
                     let target_slot = can_equip.slot;
                     let target = targets[0];
 
-                    // Remove any items the target has in the item's slot
-                    let mut to_unequip: Vec<Entity> = Vec::new();
-                    for (item_entity, already_equipped, name) in (&entities, &equipped, &names).join() {
+                    // A cursed item in the slot can't be swapped out
+                    let mut stuck_item: Option<Entity> = None;
+                    for (item_entity, already_equipped, _cursed) in (&entities, &equipped, &cursed).join() {
                         if already_equipped.owner == target && already_equipped.slot == target_slot {
-                            to_unequip.push(item_entity);
-                            if target == *player_entity {
-                                gamelog.entries.push(format!("You unequip the {}.", name.name));
-                            }
+                            stuck_item = Some(item_entity);
                         }
                     }
-                    for item in to_unequip.iter() {
-                        equipped.remove(*item);
-                        backpack.insert(*item, InBackpack{ owner: target }).expect("Unable to insert item");
-                    }
 
-                    // Equip the item
-                    equipped.insert(useitem.item, Equipped{ owner: target, slot: target_slot }).expect("Unable to insert equipped component");
-                    backpack.remove(useitem.item);
-                    if target == *player_entity {
-                        gamelog.entries.push(format!("You equip the {}.", names.get(useitem.item).unwrap().name));
+                    if let Some(stuck_item) = stuck_item {
+                        if target == *player_entity {
+                            gamelog.entries.push(format!("You can't take off the {} - it's cursed!", names.get(stuck_item).unwrap().name));
+                        }
+                    } else {
+                        // Remove any items the target has in the item's slot
+                        let mut to_unequip: Vec<Entity> = Vec::new();
+                        for (item_entity, already_equipped, name) in (&entities, &equipped, &names).join() {
+                            if already_equipped.owner == target && already_equipped.slot == target_slot {
+                                to_unequip.push(item_entity);
+                                if target == *player_entity {
+                                    gamelog.entries.push(format!("You unequip the {}.", name.name));
+                                }
+                            }
+                        }
+                        for item in to_unequip.iter() {
+                            equipped.remove(*item);
+                            backpack.insert(*item, InBackpack{ owner: target }).expect("Unable to insert item");
+                        }
+
+                        // Equip the item
+                        equipped.insert(useitem.item, Equipped{ owner: target, slot: target_slot }).expect("Unable to insert equipped component");
+                        backpack.remove(useitem.item);
+                        if target == *player_entity {
+                            gamelog.entries.push(format!("You equip the {}.", names.get(useitem.item).unwrap().name));
+                            if cursed.get(useitem.item).is_some() {
+                                gamelog.entries.push(format!("The {} clings to you. It's cursed!", names.get(useitem.item).unwrap().name));
+                            }
+                        }
                     }
                 }
             }
@@ -178,6 +207,32 @@ impl<'a> System<'a> for ItemUseSystem {
                 }
             }
             
+            let lifts_curses = remove_curse.get(useitem.item);
+            match lifts_curses {
+                None => {}
+                Some(_) => {
+                    _used_item = true;
+                    let target = targets[0];
+                    let mut to_uncurse: Vec<Entity> = Vec::new();
+                    for (item_entity, worn, _cursed) in (&entities, &equipped, &cursed).join() {
+                        if worn.owner == target { to_uncurse.push(item_entity); }
+                    }
+                    for (item_entity, carried, _cursed) in (&entities, &backpack, &cursed).join() {
+                        if carried.owner == target { to_uncurse.push(item_entity); }
+                    }
+                    for item in to_uncurse.iter() {
+                        cursed.remove(*item);
+                    }
+                    if target == *player_entity {
+                        if to_uncurse.is_empty() {
+                            gamelog.entries.push("You feel as if someone is watching over you.".to_string());
+                        } else {
+                            gamelog.entries.push("A malevolent presence departs from your belongings.".to_string());
+                        }
+                    }
+                }
+            }
+
             let is_mapper = magic_mapper.get(useitem.item);
             match is_mapper {
                 None => {}
@@ -309,15 +364,22 @@ impl<'a> System<'a> for ItemRemoveSystem {
         WriteStorage<'a, WantsToRemoveItem>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
+        ReadStorage<'a, Cursed>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
             entities, player_entity, names, mut gamelog, mut wants_remove,
-            mut equipped, mut backpack
+            mut equipped, mut backpack, cursed
         ) = data;
 
         for (entity, to_remove) in (&entities, &wants_remove).join() {
+            if cursed.get(to_remove.item).is_some() {
+                if entity == *player_entity {
+                    gamelog.entries.push(format!("You can't remove the {} - it's cursed!", names.get(to_remove.item).unwrap().name));
+                }
+                continue;
+            }
             equipped.remove(to_remove.item);
             backpack.insert(to_remove.item, InBackpack{ owner: entity }).expect("Unable to insert backpack");
             if entity == *player_entity {
@@ -0,0 +1,69 @@
+use specs::prelude::*;
+use super::{Attributes, Attribute, Pools, Skills, Skill, PendingLevelUp, attr_bonus,
+    player_hp_at_level, mana_at_level};
+use crate::raws::{RAWS, progression};
+
+fn attribute_mut(attributes: &mut Attributes, index: usize) -> &mut Attribute {
+    match index {
+        0 => &mut attributes.might,
+        1 => &mut attributes.fitness,
+        2 => &mut attributes.quickness,
+        _ => &mut attributes.intelligence,
+    }
+}
+
+/// Spends one of the player's attribute points raising the given attribute (might, fitness,
+/// quickness, intelligence in that order). Returns false if it couldn't be raised.
+pub fn spend_attribute_point(ecs: &mut World, index: usize) -> bool {
+    let player = *ecs.fetch::<Entity>();
+    let max_attribute = progression(&RAWS.lock().unwrap()).max_attribute;
+    let mut pending = ecs.write_storage::<PendingLevelUp>();
+    let mut attributes = ecs.write_storage::<Attributes>();
+    if let (Some(pending), Some(attributes)) = (pending.get_mut(player), attributes.get_mut(player)) {
+        let attribute = attribute_mut(attributes, index);
+        if pending.attribute_points > 0 && attribute.base < max_attribute {
+            pending.attribute_points -= 1;
+            attribute.base += 1;
+            attribute.bonus = attr_bonus(attribute.base + attribute.modifiers);
+            return true;
+        }
+    }
+    false
+}
+
+/// Spends one of the player's skill points training the given skill. Returns false if it
+/// couldn't be trained.
+pub fn spend_skill_point(ecs: &mut World, skill: Skill) -> bool {
+    let player = *ecs.fetch::<Entity>();
+    let max_skill = progression(&RAWS.lock().unwrap()).max_skill;
+    let mut pending = ecs.write_storage::<PendingLevelUp>();
+    let mut skills = ecs.write_storage::<Skills>();
+    if let (Some(pending), Some(skills)) = (pending.get_mut(player), skills.get_mut(player)) {
+        let level = skills.skills.entry(skill).or_insert(0);
+        if pending.skill_points > 0 && *level < max_skill {
+            pending.skill_points -= 1;
+            *level += 1;
+            return true;
+        }
+    }
+    false
+}
+
+/// Finishes levelling up: hit points and mana are recalculated from the player's new
+/// attributes and fully restored. Any unspent points are lost.
+pub fn finish_level_up(ecs: &mut World) {
+    let player = *ecs.fetch::<Entity>();
+    ecs.write_storage::<PendingLevelUp>().remove(player);
+    let attributes = ecs.read_storage::<Attributes>();
+    let mut pools = ecs.write_storage::<Pools>();
+    if let (Some(attributes), Some(pools)) = (attributes.get(player), pools.get_mut(player)) {
+        pools.hit_points.max = player_hp_at_level(
+            attributes.fitness.base + attributes.fitness.modifiers,
+            pools.level);
+        pools.hit_points.current = pools.hit_points.max;
+        pools.mana.max = mana_at_level(
+            attributes.intelligence.base + attributes.intelligence.modifiers,
+            pools.level);
+        pools.mana.current = pools.mana.max;
+    }
+}
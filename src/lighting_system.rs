@@ -0,0 +1,124 @@
+use specs::prelude::*;
+use rltk::{RGB, Point, field_of_view};
+use super::{
+    Map, Position, LightSource, Fuel, Equipped, Name, Viewshed, RunState, gamelog::GameLog,
+    clock_system::WorldClock,
+};
+
+/// How far a burning tile lights up its surroundings.
+const FIRE_LIGHT_RANGE: i32 = 3;
+/// How many turns before a light runs out the player is warned about it.
+const LOW_FUEL_WARNING: i32 = 20;
+
+/// How much of the day's light reaches the ground at a given hour: full daylight, dusk and dawn,
+/// or moonlight.
+fn daylight(hour: i32) -> f32 {
+    match hour {
+        7 ..= 18 => 1.0,
+        5 | 6 | 19 | 20 => 0.6,
+        _ => 0.3
+    }
+}
+
+/// Works out how much light reaches each tile of the map, from the level's ambient light (which
+/// follows the clock outdoors), light sources, and anything on fire. Equipped lights also burn
+/// through their fuel, once per turn, and go out when it's gone.
+pub struct LightingSystem {}
+
+impl<'a> System<'a> for LightingSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, Map>,
+        ReadExpect<'a, WorldClock>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, LightSource>,
+        WriteStorage<'a, Fuel>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities, mut map, clock, player_entity, runstate, positions, lights, mut fuel,
+            equipped, names, mut viewsheds, mut gamelog,
+        ) = data;
+
+        if *runstate == RunState::PlayerTurn {
+            let mut burnt_out: Vec<Entity> = Vec::new();
+            for (entity, fuel, worn) in (&entities, &mut fuel, &equipped).join() {
+                fuel.turns -= 1;
+                let name = names.get(entity).map_or("light".to_string(), |n| n.name.to_lowercase());
+                if fuel.turns < 1 {
+                    burnt_out.push(entity);
+                    if worn.owner == *player_entity {
+                        gamelog.entries.push(format!("Your {} gutters and goes out.", name));
+                        if let Some(viewshed) = viewsheds.get_mut(*player_entity) {
+                            viewshed.dirty = true;
+                        }
+                    }
+                } else if fuel.turns == LOW_FUEL_WARNING && worn.owner == *player_entity {
+                    gamelog.entries.push(format!("Your {} is burning low.", name));
+                }
+            }
+            for e in burnt_out {
+                entities.delete(e).expect("Unable to delete");
+            }
+        }
+
+        let ambient = if map.outdoors { map.ambient_light * daylight(clock.hour()) } else { map.ambient_light };
+        let mut light = vec![RGB::from_f32(ambient, ambient, ambient); map.tiles.len()];
+
+        // Lights carried by someone shine from wherever they are. Anything which needs fuel is
+        // only lit while it's in use.
+        let mut sources: Vec<(Point, RGB, i32)> = Vec::new();
+        for (entity, source) in (&entities, &lights).join() {
+            let pos = match equipped.get(entity) {
+                Some(worn) => positions.get(worn.owner),
+                None if fuel.contains(entity) => None,
+                None => positions.get(entity)
+            };
+            if let Some(pos) = pos {
+                sources.push((Point::new(pos.x, pos.y), source.colour, source.range));
+            }
+        }
+        for idx in map.fire.keys() {
+            let pos = Point::new(*idx as i32 % map.width, *idx as i32 / map.width);
+            sources.push((pos, RGB::named(rltk::ORANGE), FIRE_LIGHT_RANGE));
+        }
+
+        // Each light fades out towards the edge of its range
+        for (pos, colour, range) in sources {
+            for tile in field_of_view(pos, range, &*map).iter()
+                .filter(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height)
+            {
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(pos, *tile);
+                let strength = f32::max(0.0, 1.0 - distance / (range as f32 + 1.0));
+                let lit = &mut light[map.xy_idx(tile.x, tile.y)];
+                lit.r = f32::min(1.0, lit.r + colour.r * strength);
+                lit.g = f32::min(1.0, lit.g + colour.g * strength);
+                lit.b = f32::min(1.0, lit.b + colour.b * strength);
+            }
+        }
+
+        map.light = light;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daylight_follows_the_sun() {
+        assert_eq!(daylight(12), 1.0);
+        assert_eq!(daylight(6), 0.6);
+        assert_eq!(daylight(20), 0.6);
+        assert_eq!(daylight(0), 0.3);
+        assert!(daylight(7) > daylight(5) && daylight(5) > daylight(4));
+    }
+}
@@ -21,12 +21,7 @@ pub use options::OPTIONS;
 
 mod visibility_system;
 use visibility_system::VisibilitySystem;
-mod monster_ai_system;
-use monster_ai_system::MonsterAI;
-mod bystander_ai_system;
-use bystander_ai_system::BystanderAI;
-mod animal_ai_system;
-use animal_ai_system::AnimalAI;
+mod ai;
 mod map_indexing_system;
 use map_indexing_system::MapIndexingSystem;
 mod melee_combat_system;
@@ -42,14 +37,34 @@ use inventory_system::ItemCollectionSystem;
 use inventory_system::ItemUseSystem;
 use inventory_system::ItemDropSystem;
 use inventory_system::ItemRemoveSystem;
+mod equipment_system;
+use equipment_system::EquipmentBonusSystem;
+mod skill_system;
+use skill_system::SkillTrainingSystem;
 mod saveload_system;
 pub mod random_table;
 mod particle_system;
+mod noise_system;
+mod benchmark;
 mod hunger_system;
+mod clock_system;
 mod trigger_system;
 pub mod map_builders;
 mod gamesystem;
 pub use gamesystem::*;
+mod character_creation;
+mod level_up;
+mod dialogue;
+mod quest_system;
+mod companion;
+mod ability_system;
+mod boss_system;
+mod spawn_director;
+mod doors;
+mod traps;
+mod props;
+mod terrain_system;
+mod lighting_system;
 
 // Constants
 
@@ -65,6 +80,15 @@ pub enum RunState { AwaitingInput, PreRun, PlayerTurn, MonsterTurn, ShowInventor
     MapGeneration,
     Wait,
     ShowHelp,
+    CharacterCreation,
+    LevelUp,
+    ShowCharacterSheet,
+    ShowMessageLog{ offset: usize },
+    ShowDialogue{ npc: Entity, node: usize },
+    ShowShop{ vendor: Entity, shop: usize },
+    ShowJournal,
+    ShowOrders,
+    ShowContainer{ container: Entity },
 }
 
 pub struct State{
@@ -73,10 +97,15 @@ pub struct State{
     mapgen_history: Vec<Map>,
     mapgen_index: usize,
     mapgen_timer: f32,
+    new_character: character_creation::NewCharacter,
+    log_filter: String,
 }
 
 impl State {
     fn run_systems(&mut self) {
+        let mut lighting = lighting_system::LightingSystem{};
+        lighting.run_now(&self.ecs);
+
         let mut vis = VisibilitySystem{};
         vis.run_now(&self.ecs);
 
@@ -84,16 +113,38 @@ impl State {
         pickup.run_now(&self.ecs);
 
         // AI systems
-        let mut mob = MonsterAI{};
-        mob.run_now(&self.ecs);
-        let mut bystander = BystanderAI{};
-        bystander.run_now(&self.ecs);
-        let mut animal = AnimalAI{};
-        animal.run_now(&self.ecs);
+        let mut noise = noise_system::NoiseSystem{};
+        noise.run_now(&self.ecs);
+        let mut initiative = ai::InitiativeSystem{};
+        initiative.run_now(&self.ecs);
+        let mut turnstatus = ai::TurnStatusSystem{};
+        turnstatus.run_now(&self.ecs);
+        let mut quipper = ai::QuipSystem{};
+        quipper.run_now(&self.ecs);
+        let mut schedules = ai::ScheduleSystem{};
+        schedules.run_now(&self.ecs);
+        let mut groups = ai::GroupAI{};
+        groups.run_now(&self.ecs);
+        let mut behaviour = ai::BehaviourAI{};
+        behaviour.run_now(&self.ecs);
+        let mut abilities = ability_system::AbilitySystem{};
+        abilities.run_now(&self.ecs);
+        let mut flee = ai::FleeAI{};
+        flee.run_now(&self.ecs);
+        let mut approach = ai::ApproachAI{};
+        approach.run_now(&self.ecs);
+        let mut defaultmove = ai::DefaultMoveAI{};
+        defaultmove.run_now(&self.ecs);
+        let mut moving = ai::MovementSystem{};
+        moving.run_now(&self.ecs);
+        ability_system::summon_creatures(&mut self.ecs);
 
         let mut triggers = trigger_system::TriggerSystem{};
         triggers.run_now(&self.ecs);
 
+        let mut terrain = terrain_system::TerrainSystem{};
+        terrain.run_now(&self.ecs);
+
         let mut mapindex = MapIndexingSystem{};
         mapindex.run_now(&self.ecs);
 
@@ -101,6 +152,9 @@ impl State {
         melee.run_now(&self.ecs);
         let mut damage = DamageSystem{};
         damage.run_now(&self.ecs);
+        let mut boss_phases = boss_system::BossPhaseSystem{};
+        boss_phases.run_now(&self.ecs);
+        quest_system::update_quests(&mut self.ecs);
 
         let mut potions = ItemUseSystem{};
         potions.run_now(&self.ecs);
@@ -111,9 +165,23 @@ impl State {
         let mut item_remove = ItemRemoveSystem{};
         item_remove.run_now(&self.ecs);
 
+        let mut equipment_bonus = EquipmentBonusSystem{};
+        equipment_bonus.run_now(&self.ecs);
+
+        let mut skill_training = SkillTrainingSystem{};
+        skill_training.run_now(&self.ecs);
+
         let mut hunger = hunger_system::HungerSystem{};
         hunger.run_now(&self.ecs);
 
+        let mut status_effects = ability_system::StatusEffectSystem{};
+        status_effects.run_now(&self.ecs);
+
+        let mut clock = clock_system::ClockSystem{};
+        clock.run_now(&self.ecs);
+
+        spawn_director::wandering_monsters(&mut self.ecs);
+
         let mut particles = particle_system::ParticleSpawnSystem{};
         particles.run_now(&self.ecs);
 
@@ -136,6 +204,10 @@ impl GameState for State {
             // Only draw the map/entities/gui if we're not in the main menu/gameover screen
             RunState::MainMenu{..} => {}
             RunState::GameOver{..} => {}
+            RunState::CharacterCreation => {}
+            RunState::ShowCharacterSheet => {}
+            RunState::ShowMessageLog{..} => {}
+            RunState::ShowJournal => {}
             _ => {
                 camera::render_camera(&self.ecs, ctx);
                 gui::draw_ui(&self.ecs, ctx);
@@ -186,9 +258,8 @@ impl GameState for State {
                     gui::MainMenuResult::Selected{ selected } => {
                         match selected {
                             gui::MainMenuSelection::NewGame => {
-                                self.game_over_cleanup();
-                                self.mapgen_next_state = Some(RunState::PreRun);
-                                newrunstate = RunState::MapGeneration;
+                                self.new_character = character_creation::NewCharacter::new();
+                                newrunstate = RunState::CharacterCreation;
                             },
                             gui::MainMenuSelection::LoadGame => {
                                 saveload_system::load_game(&mut self.ecs);
@@ -200,6 +271,20 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::CharacterCreation => {
+                let result = gui::character_creation(self, ctx);
+                match result {
+                    gui::CharacterCreationResult::NoResponse => {}
+                    gui::CharacterCreationResult::Cancel => {
+                        newrunstate = RunState::MainMenu{ menu_selection: gui::MainMenuSelection::NewGame };
+                    }
+                    gui::CharacterCreationResult::Done => {
+                        self.game_over_cleanup();
+                        self.mapgen_next_state = Some(RunState::PreRun);
+                        newrunstate = RunState::MapGeneration;
+                    }
+                }
+            }
             RunState::PreRun => {
                 self.run_systems();
                 self.ecs.maintain();
@@ -213,13 +298,31 @@ impl GameState for State {
                 self.ecs.maintain();
                 match *self.ecs.fetch::<RunState>() {
                     RunState::MagicMapReveal{ .. } => newrunstate = RunState::MagicMapReveal{ row: 0 },
+                    RunState::NextLevel => newrunstate = RunState::NextLevel,
                     _ => newrunstate = RunState::MonsterTurn
                 }
             }
             RunState::MonsterTurn => {
                 self.run_systems();
                 self.ecs.maintain();
-                newrunstate = RunState::AwaitingInput;
+                let player = *self.ecs.fetch::<Entity>();
+                if self.ecs.read_storage::<PendingLevelUp>().contains(player) {
+                    newrunstate = RunState::LevelUp;
+                } else {
+                    newrunstate = RunState::AwaitingInput;
+                }
+            }
+            RunState::LevelUp => {
+                let result = gui::level_up(&self.ecs, ctx);
+                match result {
+                    gui::LevelUpResult::NoResponse => {}
+                    gui::LevelUpResult::Attribute(index) => { level_up::spend_attribute_point(&mut self.ecs, index); }
+                    gui::LevelUpResult::Skill(skill) => { level_up::spend_skill_point(&mut self.ecs, skill); }
+                    gui::LevelUpResult::Done => {
+                        level_up::finish_level_up(&mut self.ecs);
+                        newrunstate = RunState::AwaitingInput;
+                    }
+                }
             }
             RunState::ShowInventory => {
                 let result = gui::show_inventory(self, ctx);
@@ -253,6 +356,17 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::ShowContainer{ container } => {
+                let result = gui::container_menu(self, ctx, container);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {},
+                    gui::ItemMenuResult::Selected => {
+                        props::take_item(&mut self.ecs, container, result.1.unwrap());
+                        newrunstate = RunState::PlayerTurn;
+                    }
+                }
+            }
             RunState::ShowTargeting{range, item} => {
                 let result = gui::ranged_target(self, ctx, range);
                 match result.0 {
@@ -288,7 +402,7 @@ impl GameState for State {
                 }
             }
             RunState::GameOver => {
-                let result = gui::game_over(ctx);
+                let result = gui::game_over(&self.ecs, ctx);
                 match result {
                     gui::GameOverResult::NoSelection => {}
                     gui::GameOverResult::QuitToMenu => {
@@ -297,6 +411,61 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::ShowCharacterSheet => {
+                let result = gui::character_sheet(&self.ecs, ctx);
+                match result {
+                    gui::CharacterSheetResult::NoResponse => {}
+                    gui::CharacterSheetResult::Close => newrunstate = RunState::AwaitingInput,
+                }
+            }
+            RunState::ShowMessageLog{offset} => {
+                let result = gui::message_log(self, ctx, offset);
+                match result {
+                    gui::MessageLogResult::NoResponse => {}
+                    gui::MessageLogResult::Scroll{ offset } => newrunstate = RunState::ShowMessageLog{ offset },
+                    gui::MessageLogResult::Close => newrunstate = RunState::AwaitingInput,
+                }
+            }
+            RunState::ShowJournal => {
+                let result = gui::quest_journal(&self.ecs, ctx);
+                match result {
+                    gui::JournalResult::NoResponse => {}
+                    gui::JournalResult::Close => newrunstate = RunState::AwaitingInput,
+                }
+            }
+            RunState::ShowOrders => {
+                let result = gui::companion_orders(&self.ecs, ctx);
+                match result {
+                    gui::OrdersResult::NoResponse => {}
+                    gui::OrdersResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::OrdersResult::Order(order) => {
+                        companion::give_order(&mut self.ecs, order);
+                        newrunstate = RunState::AwaitingInput;
+                    }
+                }
+            }
+            RunState::ShowDialogue{npc, node} => {
+                let result = gui::dialogue(&self.ecs, ctx, npc, node);
+                match result {
+                    gui::DialogueResult::NoResponse => {}
+                    gui::DialogueResult::Leave => newrunstate = RunState::AwaitingInput,
+                    gui::DialogueResult::Chose(response) => {
+                        match dialogue::choose_response(&mut self.ecs, npc, node, response) {
+                            dialogue::DialogueOutcome::Continue(node) => newrunstate = RunState::ShowDialogue{ npc, node },
+                            dialogue::DialogueOutcome::OpenShop(shop) => newrunstate = RunState::ShowShop{ vendor: npc, shop },
+                            dialogue::DialogueOutcome::End => newrunstate = RunState::AwaitingInput,
+                        }
+                    }
+                }
+            }
+            RunState::ShowShop{vendor, shop} => {
+                let result = gui::shop(&self.ecs, ctx, vendor, shop);
+                match result {
+                    gui::ShopResult::NoResponse => {}
+                    gui::ShopResult::Leave => newrunstate = RunState::AwaitingInput,
+                    gui::ShopResult::Buy(item) => dialogue::buy_item(&mut self.ecs, shop, item),
+                }
+            }
             RunState::ShowHelp => {
                 let result = gui::help_screen(ctx);
                 match result {
@@ -340,7 +509,10 @@ impl State {
         let player = self.ecs.read_storage::<Player>();
         let backpack = self.ecs.read_storage::<InBackpack>();
         let equipped = self.ecs.read_storage::<Equipped>();
+        let members = self.ecs.read_storage::<GroupMember>();
         let player_entity = self.ecs.fetch::<Entity>();
+        let following = |entity: Entity| members.get(entity).is_some_and(|m| m.leader == *player_entity);
+        let travelling = |owner: Entity| owner == *player_entity || following(owner);
 
         let mut to_delete: Vec<Entity> = Vec::new();
         for entity in entities.join() {
@@ -352,18 +524,23 @@ impl State {
                 should_delete = false;
             }
 
-            // Don't delete the player's items
+            // Don't delete the player's companions, or anyone they're escorting
+            if following(entity) {
+                should_delete = false;
+            }
+
+            // Don't delete the player's items, or those of whoever is travelling with them
             let bp = backpack.get(entity);
             if let Some(bp) = bp {
-                if bp.owner == *player_entity {
+                if travelling(bp.owner) {
                     should_delete = false;
                 }
             }
 
-            // Don't delete the player's equipment
+            // Don't delete the player's equipment, or that of whoever is travelling with them
             let eq = equipped.get(entity);
             if let Some(eq) = eq {
-                if eq.owner == *player_entity {
+                if travelling(eq.owner) {
                     should_delete = false;
                 }
             }
@@ -390,6 +567,7 @@ impl State {
             current_depth = worldmap_resource.depth;
         }
         self.generate_world_map(current_depth + 1);
+        self.ecs.write_resource::<quest_system::QuestEvents>().request(quest_system::QuestEvent::ReachedDepth(current_depth + 1));
 
         // Notify the player and regenerate some health.
         let _player_entity = self.ecs.fetch::<Entity>();
@@ -407,9 +585,13 @@ impl State {
             self.ecs.delete_entity(*del).expect("Deletion failed");
         }
 
+        // Start the clock again
+        *self.ecs.write_resource::<clock_system::WorldClock>() = clock_system::WorldClock::new();
+        *self.ecs.write_resource::<quest_system::QuestEvents>() = quest_system::QuestEvents::new();
+
         // Spawn a new player
         {
-            let player_entity = spawner::player(&mut self.ecs, 0, 0);
+            let player_entity = spawner::player(&mut self.ecs, 0, 0, &self.new_character);
             let mut player_entity_writer = self.ecs.write_resource::<Entity>();
             *player_entity_writer = player_entity;
         }
@@ -438,41 +620,43 @@ impl State {
 
         // Spawn entities
         builder.spawn_entities(&mut self.ecs);
+        *self.ecs.write_resource::<spawn_director::SpawnDirector>() = spawn_director::SpawnDirector::new(&builder.build_data.biome);
 
-        // Place the player and update resources
-        let (player_x, player_y) = (player_start.x, player_start.y);
-        let mut player_position = self.ecs.write_resource::<Point>();
-        *player_position = Point::new(player_x, player_y);
-        let mut position_components = self.ecs.write_storage::<Position>();
-        let player_entity = self.ecs.fetch::<Entity>();
-        let player_pos_comp = position_components.get_mut(*player_entity);
-        if let Some(player_pos_comp) = player_pos_comp {
-            player_pos_comp.x = player_x;
-            player_pos_comp.y = player_y;
-        }
+        {
+            // Place the player and update resources
+            let (player_x, player_y) = (player_start.x, player_start.y);
+            let mut player_position = self.ecs.write_resource::<Point>();
+            *player_position = Point::new(player_x, player_y);
+            let mut position_components = self.ecs.write_storage::<Position>();
+            let player_entity = self.ecs.fetch::<Entity>();
+            let player_pos_comp = position_components.get_mut(*player_entity);
+            if let Some(player_pos_comp) = player_pos_comp {
+                player_pos_comp.x = player_x;
+                player_pos_comp.y = player_y;
+            }
 
-        // Mark the player's viewshed as dirty
-        let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
-        let vs = viewshed_components.get_mut(*player_entity);
-        if let Some(vs) = vs {
-            vs.dirty = true;
+            // Mark the player's viewshed as dirty
+            let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
+            let vs = viewshed_components.get_mut(*player_entity);
+            if let Some(vs) = vs {
+                vs.dirty = true;
+            }
         }
+
+        // Bring along anyone travelling with the player
+        companion::bring_followers(&mut self.ecs);
     }
 }
 
 fn main() -> rltk::BError {
-    use rltk::RltkBuilder;
-    let mut context = RltkBuilder::simple(80, 60)
-        .unwrap()
-        .with_title("Roguelike Tutorial")
-        .build()?;
-    context.with_post_scanlines(true);
     let mut gs = State{
         ecs: World::new(),
         mapgen_next_state: Some(RunState::MainMenu{ menu_selection: gui::MainMenuSelection::NewGame }),
         mapgen_index: 0,
         mapgen_history: Vec::new(),
         mapgen_timer: 0.0,
+        new_character: character_creation::NewCharacter::new(),
+        log_filter: String::new(),
     };
     // Component registration
     // General entity properties
@@ -481,11 +665,8 @@ fn main() -> rltk::BError {
     gs.ecs.register::<Player>();
     gs.ecs.register::<SufferDamage>();
     // AIs
-    gs.ecs.register::<Monster>();
-    gs.ecs.register::<Bystander>();
-    gs.ecs.register::<Vendor>();
-    gs.ecs.register::<Carnivore>();
-    gs.ecs.register::<Herbivore>();
+    gs.ecs.register::<Faction>();
+    gs.ecs.register::<MoveMode>();
     // Mob properties
     gs.ecs.register::<Name>();
     gs.ecs.register::<Viewshed>();
@@ -519,15 +700,66 @@ fn main() -> rltk::BError {
     gs.ecs.register::<EntryTrigger>();
     gs.ecs.register::<EntityMoved>();
     gs.ecs.register::<SingleActivation>();
+    gs.ecs.register::<TeleportTrap>();
+    gs.ecs.register::<Alarm>();
+    gs.ecs.register::<Pit>();
+    gs.ecs.register::<PoisonGas>();
+    gs.ecs.register::<Ensnares>();
+    gs.ecs.register::<Disarmable>();
+    gs.ecs.register::<Rooted>();
+    gs.ecs.register::<Container>();
+    gs.ecs.register::<Lever>();
+    gs.ecs.register::<LinkedDoor>();
+    gs.ecs.register::<Fountain>();
+    gs.ecs.register::<Altar>();
+    gs.ecs.register::<Burning>();
+    gs.ecs.register::<Swimming>();
+    gs.ecs.register::<Ignites>();
+    gs.ecs.register::<Heavy>();
+    gs.ecs.register::<LightSource>();
+    gs.ecs.register::<Fuel>();
+    gs.ecs.register::<NoticedPlayer>();
     gs.ecs.register::<RemembersPlayer>();
     gs.ecs.register::<BlocksVisibility>();
     gs.ecs.register::<Door>();
+    gs.ecs.register::<Locked>();
+    gs.ecs.register::<Key>();
+    gs.ecs.register::<OpensDoors>();
     gs.ecs.register::<Quips>();
     gs.ecs.register::<Attributes>();
     gs.ecs.register::<Skills>();
     gs.ecs.register::<Pools>();
     gs.ecs.register::<NaturalAttackDefense>();
     gs.ecs.register::<LootTable>();
+    gs.ecs.register::<Cursed>();
+    gs.ecs.register::<AttributeBonus>();
+    gs.ecs.register::<PendingLevelUp>();
+    gs.ecs.register::<SkillPractice>();
+    gs.ecs.register::<SkillUsed>();
+    gs.ecs.register::<ProvidesRemoveCurse>();
+    gs.ecs.register::<MyTurn>();
+    gs.ecs.register::<WantsToApproach>();
+    gs.ecs.register::<WantsToFlee>();
+    gs.ecs.register::<WantsToMove>();
+    gs.ecs.register::<Behaviour>();
+    gs.ecs.register::<Sneaking>();
+    gs.ecs.register::<Asleep>();
+    gs.ecs.register::<GroupMember>();
+    gs.ecs.register::<Routed>();
+    gs.ecs.register::<Schedule>();
+    gs.ecs.register::<Dialogue>();
+    gs.ecs.register::<StoryFlags>();
+    gs.ecs.register::<QuestLog>();
+    gs.ecs.register::<Escorted>();
+    gs.ecs.register::<Companion>();
+    gs.ecs.register::<Abilities>();
+    gs.ecs.register::<WantsToUseAbility>();
+    gs.ecs.register::<WantsToSummon>();
+    gs.ecs.register::<Poisoned>();
+    gs.ecs.register::<Regenerating>();
+    gs.ecs.register::<RunRecord>();
+    gs.ecs.register::<Unique>();
+    gs.ecs.register::<BossPhases>();
 
     gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
 
@@ -545,13 +777,30 @@ fn main() -> rltk::BError {
     gs.ecs.insert(rltk::RandomNumberGenerator::new());
 
     // Create player entity
-    let player_entity = spawner::player(&mut gs.ecs, 0, 0);
+    let player_entity = spawner::player(&mut gs.ecs, 0, 0, &gs.new_character);
     gs.ecs.insert(player_entity);
 
     gs.ecs.insert(RunState::MainMenu { menu_selection: gui::MainMenuSelection::LoadGame } );
     gs.ecs.insert(gamelog::GameLog{ entries: vec!["Welcome to Rustlike!".to_string()]});
     gs.ecs.insert(particle_system::ParticleBuilder::new());
+    gs.ecs.insert(noise_system::NoiseBuilder::new());
+    gs.ecs.insert(terrain_system::TerrainBuilder::new());
+    gs.ecs.insert(ai::FlowMaps::new());
+    gs.ecs.insert(clock_system::WorldClock::new());
+    gs.ecs.insert(quest_system::QuestEvents::new());
+    gs.ecs.insert(spawn_director::SpawnDirector::default());
     gs.ecs.insert(rex_assets::RexAssets::new());
 
+    if std::env::args().any(|arg| arg == "--benchmark") {
+        benchmark::run(&mut gs);
+        return Ok(());
+    }
+
+    use rltk::RltkBuilder;
+    let mut context = RltkBuilder::simple(80, 60)
+        .unwrap()
+        .with_title("Roguelike Tutorial")
+        .build()?;
+    context.with_post_scanlines(true);
     rltk::main_loop(context, gs)
 }
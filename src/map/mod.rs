@@ -1,13 +1,20 @@
 use rltk::{ Point, Algorithm2D, BaseMap};
-use std::collections::HashSet;
+use std::collections::{HashSet, HashMap};
 use specs::prelude::*;
 use serde::{ Serialize, Deserialize, };
+use super::Rect;
 
 mod tiletype;
-pub use tiletype::{TileType, tile_walkable, tile_opaque, tile_cost};
+pub use tiletype::{TileType, tile_walkable, tile_opaque, tile_cost, tile_flammable, tile_wet};
 mod themes;
 pub use themes::*;
 
+/// Gas at least this thick blocks sight.
+pub const GAS_OPAQUE_DENSITY: i32 = 4;
+
+/// Tiles need at least this much light to be seen.
+pub const LIT_THRESHOLD: f32 = 0.2;
+
 
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Map {
@@ -21,6 +28,30 @@ pub struct Map {
     pub bloodstains: HashSet<usize>,
     pub view_blocked: HashSet<usize>,
     pub name: String,
+    /// Named buildings on the map, such as the town's pub, which creatures can go to.
+    #[serde(default)]
+    pub buildings: Vec<(String, Rect)>,
+    /// Tiles which are on fire, and how many more turns each will burn for.
+    #[serde(default)]
+    pub fire: HashMap<usize, i32>,
+    /// Tiles filled with poison gas, and how thick it is there.
+    #[serde(default)]
+    pub gas: HashMap<usize, i32>,
+    /// How brightly the level is lit before any light sources are taken into account, from 0 to 1.
+    #[serde(default)]
+    pub ambient_light: f32,
+    /// Whether the level is open to the sky, so its ambient light follows the time of day.
+    #[serde(default)]
+    pub outdoors: bool,
+    /// How much light reaches each tile; worked out afresh every turn by the lighting system.
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub light: Vec<rltk::RGB>,
+
+    /// Tiles holding a closed, unlocked door; rebuilt by the map indexing system.
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub closed_doors: HashSet<usize>,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
@@ -40,10 +71,26 @@ impl Map {
 
     pub fn populate_blocked(&mut self) {
         for (i, tile) in self.tiles.iter_mut().enumerate() {
-            self.blocked[i] = !tile_walkable(*tile); // Walls block their position.
+            self.blocked[i] = !tile_walkable(*tile) || self.fire.contains_key(&i); // Walls (and flames) block their position.
         }
     }
 
+    /// Whether a tile's gas is thick enough to hide what's behind it.
+    pub fn thick_gas(&self, idx: usize) -> bool {
+        self.gas.get(&idx).is_some_and(|density| *density >= GAS_OPAQUE_DENSITY)
+    }
+
+    /// How much light a tile gets, as the brightest of its colour channels. Until the lighting
+    /// system has been over it, the whole map counts as fully lit.
+    pub fn light_level(&self, idx: usize) -> f32 {
+        self.light.get(idx).map_or(1.0, |l| f32::max(l.r, f32::max(l.g, l.b)))
+    }
+
+    /// Whether there's enough light on a tile to see it by.
+    pub fn is_lit(&self, idx: usize) -> bool {
+        self.light_level(idx) >= LIT_THRESHOLD
+    }
+
     pub fn clear_content_index(&mut self) {
         for content in self.tile_content.iter_mut() {
             content.clear();
@@ -64,6 +111,13 @@ impl Map {
             bloodstains: HashSet::new(),
             view_blocked: HashSet::new(),
             name: name.to_string(),
+            buildings: Vec::new(),
+            fire: HashMap::new(),
+            gas: HashMap::new(),
+            ambient_light: 1.0,
+            outdoors: false,
+            light: Vec::new(),
+            closed_doors: HashSet::new(),
         }
     }
 }
@@ -77,7 +131,7 @@ impl Algorithm2D for Map {
 impl BaseMap for Map {
     fn is_opaque(&self, idx:usize) -> bool {
         if idx > 0 && idx < self.tiles.len() {
-            tile_opaque(self.tiles[idx]) || self.view_blocked.contains(&idx)
+            tile_opaque(self.tiles[idx]) || self.view_blocked.contains(&idx) || self.thick_gas(idx)
         } else {
             true
         }
@@ -112,3 +166,28 @@ impl BaseMap for Map {
         exits
     }
 }
+
+/// The map as seen by a creature which can't open doors: closed doors are as good as walls.
+pub struct DoorsShut<'a>(pub &'a Map);
+
+impl Algorithm2D for DoorsShut<'_> {
+    fn dimensions(&self) -> Point {
+        self.0.dimensions()
+    }
+}
+
+impl BaseMap for DoorsShut<'_> {
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.0.is_opaque(idx)
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.0.get_pathing_distance(idx1, idx2)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        self.0.get_available_exits(idx).into_iter()
+            .filter(|(exit, _)| !self.0.closed_doors.contains(exit))
+            .collect()
+    }
+}
@@ -2,20 +2,40 @@ use super::{Map, TileType};
 use rltk::RGB;
 
 pub fn tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let (glyph, mut fg, mut bg) = match map.depth{
+    let (mut glyph, mut fg, mut bg) = match map.depth{
         2 => get_forest_glyph(idx, map),
         _ => get_tile_glyph_default(idx, map)
     };
 
     if map.bloodstains.contains(&idx) { bg = RGB::from_f32(0.75, 0.0, 0.0); }
+    if let Some(density) = map.gas.get(&idx) {
+        bg = RGB::from_f32(0.0, f32::min(0.6, 0.1 * *density as f32), 0.0);
+    }
+    if map.fire.contains_key(&idx) {
+        let (fire_glyph, fire_fg) = if idx.is_multiple_of(2) { ('▲', RGB::named(rltk::ORANGE)) } else { ('^', RGB::named(rltk::YELLOW)) };
+        glyph = rltk::to_cp437(fire_glyph);
+        fg = fire_fg;
+        bg = RGB::from_f32(0.5, 0.1, 0.0);
+    }
     if !map.visible_tiles[idx] {
         fg = fg.to_greyscale();
-        bg = RGB::from_f32(0., 0., 0.); // Don't show bloodstains outside visual range
+        bg = RGB::from_f32(0., 0., 0.); // Don't show bloodstains (or fire, or gas) outside visual range
+    } else if let Some(light) = map.light.get(idx) {
+        fg = shade(fg, *light);
+        bg = shade(bg, *light);
     }
 
     (glyph, fg, bg)
 }
 
+/// Darkens a colour to match how well lit its tile is, and tints it the colour of the light.
+/// Even dimly lit tiles stay readable.
+fn shade(colour: RGB, light: RGB) -> RGB {
+    const MIN_SHADE: f32 = 0.35;
+    let channel = |c: f32, l: f32| c * (MIN_SHADE + (1.0 - MIN_SHADE) * f32::min(1.0, l));
+    RGB::from_f32(channel(colour.r, light.r), channel(colour.g, light.g), channel(colour.b, light.b))
+}
+
 fn get_tile_glyph_default(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
     let glyph;
     let fg;
@@ -37,6 +57,7 @@ fn get_tile_glyph_default(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RG
         TileType::ShallowWater => { glyph = rltk::to_cp437('~'); fg = RGB::named(rltk::CYAN); }
         TileType::DeepWater => { glyph = rltk::to_cp437('~'); fg = RGB::named(rltk::NAVY_BLUE); }
         TileType::Gravel => { glyph = rltk::to_cp437(';'); fg = RGB::named(rltk::GREY); }
+        TileType::Ash => { glyph = rltk::to_cp437('.'); fg = RGB::named(rltk::DIMGREY); }
     }
 
     (glyph, fg, bg)
@@ -56,6 +77,7 @@ fn get_forest_glyph(idx:usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
         TileType::DeepWater => { glyph = rltk::to_cp437('~'); fg = RGB::named(rltk::BLUE); }
         TileType::Gravel => { glyph = rltk::to_cp437(';'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
         TileType::DownStairs => { glyph = rltk::to_cp437('>'); fg = RGB::from_f32(0., 1.0, 1.0); }
+        TileType::Ash => { glyph = rltk::to_cp437('.'); fg = RGB::named(rltk::DIMGREY); }
         _ => { glyph = rltk::to_cp437('"'); fg = RGB::from_f32(0.0, 0.6, 0.0); }
     }
 
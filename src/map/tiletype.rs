@@ -11,13 +11,15 @@ pub enum TileType {
     DeepWater,
     WoodFloor,
     Bridge,
-    Gravel
+    Gravel,
+    Ash
 }
 
 pub fn tile_walkable(tt: TileType) -> bool {
     match tt {
         TileType::Floor | TileType::DownStairs | TileType::Road | TileType::Grass |
-        TileType::ShallowWater | TileType::WoodFloor | TileType::Bridge | TileType::Gravel
+        TileType::ShallowWater | TileType::WoodFloor | TileType::Bridge | TileType::Gravel |
+        TileType::Ash
             => true,
         _ => false
     }
@@ -38,3 +40,13 @@ pub fn tile_cost(tt: TileType) -> f32 {
         _ => 1.0
     }
 }
+
+/// Tiles which catch fire, and burn down to ash.
+pub fn tile_flammable(tt: TileType) -> bool {
+    matches!(tt, TileType::Grass | TileType::WoodFloor)
+}
+
+/// Tiles which put out anything burning that enters them.
+pub fn tile_wet(tt: TileType) -> bool {
+    matches!(tt, TileType::ShallowWater | TileType::DeepWater)
+}
@@ -0,0 +1,91 @@
+use super::{BuilderChain, BuilderMap, InitialMapBuilder, Position, TileType, Rect};
+
+pub fn boss_arena_builder(
+    new_depth: i32,
+    _rng: &mut rltk::RandomNumberGenerator,
+    width: i32,
+    height: i32,
+    name: &str,
+    boss: &str,
+    supplies: &[String],
+) -> BuilderChain {
+    let mut chain = BuilderChain::new(new_depth, width, height, name);
+    chain.set_biome("arena");
+    chain.start_with(BossArenaBuilder::new(boss, supplies));
+    chain
+}
+
+/// Builds a level around a boss fight: a small antechamber to gather yourself in, a passage
+/// leading into a round arena with the boss waiting in the middle, and the way down on the far
+/// side of the arena.
+pub struct BossArenaBuilder {
+    boss: String,
+    supplies: Vec<String>,
+}
+
+impl InitialMapBuilder for BossArenaBuilder {
+    #![allow(dead_code)]
+    fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap) {
+        self.build(rng, build_data);
+    }
+}
+
+impl BossArenaBuilder {
+    pub fn new(boss: &str, supplies: &[String]) -> Box<BossArenaBuilder> {
+        Box::new(BossArenaBuilder{ boss: boss.to_string(), supplies: supplies.to_vec() })
+    }
+
+    fn build(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap) {
+        let mid_y = build_data.height / 2;
+
+        // The antechamber, where the player arrives
+        let antechamber = Rect::new(2, mid_y - 4, 10, 8);
+        for y in antechamber.y1 ..= antechamber.y2 {
+            for x in antechamber.x1 ..= antechamber.x2 {
+                let idx = build_data.map.xy_idx(x, y);
+                build_data.map.tiles[idx] = TileType::Floor;
+            }
+        }
+        build_data.starting_position = Some(Position{ x: antechamber.x1 + 1, y: mid_y });
+        build_data.take_snapshot();
+
+        // The arena itself, with a ring of pillars to duck behind
+        let radius = i32::min(build_data.height / 2 - 3, rng.range(12, 17));
+        let (centre_x, centre_y) = (build_data.width - radius - 3, mid_y);
+        for y in centre_y - radius ..= centre_y + radius {
+            for x in centre_x - radius ..= centre_x + radius {
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(rltk::Point::new(x, y), rltk::Point::new(centre_x, centre_y));
+                if distance <= radius as f32 {
+                    let idx = build_data.map.xy_idx(x, y);
+                    build_data.map.tiles[idx] = TileType::Gravel;
+                }
+            }
+        }
+        let pillars = rng.range(6, 11);
+        for i in 0..pillars {
+            let angle = i as f32 * std::f32::consts::TAU / pillars as f32;
+            let x = centre_x + (angle.cos() * radius as f32 * 0.6).round() as i32;
+            let y = centre_y + (angle.sin() * radius as f32 * 0.6).round() as i32;
+            let idx = build_data.map.xy_idx(x, y);
+            build_data.map.tiles[idx] = TileType::Wall;
+        }
+        build_data.take_snapshot();
+
+        // A passage joins the two, and the way down is on the far side of the arena
+        for x in antechamber.x2 .. centre_x - radius + 1 {
+            let idx = build_data.map.xy_idx(x, mid_y);
+            build_data.map.tiles[idx] = TileType::Floor;
+        }
+        let stairs_idx = build_data.map.xy_idx(centre_x + radius - 1, centre_y);
+        build_data.map.tiles[stairs_idx] = TileType::DownStairs;
+        build_data.take_snapshot();
+
+        // The boss waits in the middle, and anything that might help is laid out by the entrance
+        let boss_idx = build_data.map.xy_idx(centre_x, centre_y);
+        build_data.spawn_list.push((boss_idx, self.boss.clone()));
+        for (i, item) in self.supplies.iter().enumerate() {
+            let (x, y) = (antechamber.x1 + 1 + i as i32 % 9, antechamber.y1 + 1 + i as i32 / 9);
+            build_data.spawn_list.push((build_data.map.xy_idx(x, y), item.clone()));
+        }
+    }
+}
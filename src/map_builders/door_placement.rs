@@ -1,7 +1,10 @@
 use super::{MetaMapBuilder, BuilderMap, TileType};
 use rltk::RandomNumberGenerator;
 
-pub struct DoorPlacement {}
+pub struct DoorPlacement {
+    /// Percent chance that a door between rooms is locked
+    lock_chance: i32,
+}
 
 impl MetaMapBuilder for DoorPlacement {
     fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap) {
@@ -12,7 +15,14 @@ impl MetaMapBuilder for DoorPlacement {
 impl DoorPlacement {
     pub fn new() -> Box<DoorPlacement> {
         #![allow(dead_code)]
-        Box::new(DoorPlacement{})
+        Box::new(DoorPlacement{ lock_chance: 0 })
+    }
+
+    /// Places doors as usual, but locks some of the doors between rooms, leaving a key for each
+    /// lock somewhere the player can get to without going through a locked door.
+    pub fn with_locks(lock_chance: i32) -> Box<DoorPlacement> {
+        #![allow(dead_code)]
+        Box::new(DoorPlacement{ lock_chance })
     }
 
     /// Given a tile index, indicates whether it is possible to place a door in that tile.
@@ -58,10 +68,15 @@ impl DoorPlacement {
             for hall in halls.iter() {
                 if hall.len() == 1 || hall.len() > 3 { // We aren't interested in tiny corridors
                     if self.door_possible(build_data, hall[0]) {
-                        build_data.spawn_list.push((hall[0], "Door".to_string()));
+                        if rng.roll_dice(1, 100) <= self.lock_chance {
+                            build_data.spawn_list.push((hall[0], "Locked Door".to_string()));
+                        } else {
+                            build_data.spawn_list.push((hall[0], "Door".to_string()));
+                        }
                     }
                 }
             }
+            self.keys(rng, build_data);
         } else {
             // There are no corridors - scan for possible places
             let tiles = build_data.map.tiles.clone();
@@ -72,4 +87,42 @@ impl DoorPlacement {
             }
         }
     }
+
+    /// Leaves a key for every locked door, on a tile the player can reach from the start without
+    /// passing through one. Some locked doors become portcullises instead, with a lever to raise
+    /// them rather than a key.
+    fn keys(&mut self, rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
+        let locks: Vec<usize> = build_data.spawn_list.iter().filter(|(_, name)| name == "Locked Door").map(|(idx, _)| *idx).collect();
+        if locks.is_empty() { return; }
+        let start = match &build_data.starting_position {
+            Some(pos) => build_data.map.xy_idx(pos.x, pos.y),
+            None => return
+        };
+
+        build_data.map.populate_blocked();
+        for idx in locks.iter() {
+            build_data.map.blocked[*idx] = true;
+        }
+        let dijkstra_map = rltk::DijkstraMap::new(build_data.map.width as usize, build_data.map.height as usize, &[start], &build_data.map, 1000.0);
+        build_data.map.populate_blocked();
+
+        let mut candidates: Vec<usize> = build_data.map.tiles.iter().enumerate()
+            .filter(|(idx, tile)| **tile == TileType::Floor && *idx != start && dijkstra_map.map[*idx] != f32::MAX)
+            .filter(|(idx, _)| !build_data.spawn_list.iter().any(|(spawn_idx, _)| spawn_idx == idx))
+            .map(|(idx, _)| idx)
+            .collect();
+        for lock in locks.iter() {
+            if candidates.is_empty() { return; }
+            let idx = candidates.remove((rng.roll_dice(1, candidates.len() as i32) - 1) as usize);
+            if rng.roll_dice(1, 3) == 1 {
+                if let Some(door) = build_data.spawn_list.iter_mut().find(|(door_idx, _)| door_idx == lock) {
+                    door.1 = "Portcullis".to_string();
+                }
+                build_data.spawn_list.push((idx, "Lever".to_string()));
+                build_data.lever_links.push((idx, *lock));
+            } else {
+                build_data.spawn_list.push((idx, "Iron Key".to_string()));
+            }
+        }
+    }
 }
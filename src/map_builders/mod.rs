@@ -22,6 +22,8 @@ mod town;
 use town::town_builder;
 mod forest;
 use forest::forest_builder;
+mod boss_arena;
+use boss_arena::boss_arena_builder;
 mod voronoi;
 use voronoi::VoronoiCellBuilder;
 mod prefab_builder;
@@ -80,6 +82,11 @@ pub struct BuilderMap {
     pub history: Vec<Map>,
     pub width: i32,
     pub height: i32,
+    /// What sort of place the level is, such as "forest" or "caves". Spawn tables and room themes
+    /// can be limited to particular biomes.
+    pub biome: String,
+    /// Levers and the doors they work, as (lever tile_idx, door tile_idx), linked once they're spawned
+    pub lever_links: Vec<(usize, usize)>,
 }
 
 impl BuilderMap {
@@ -118,7 +125,9 @@ impl BuilderChain {
                 corridors: None,
                 history: Vec::new(),
                 width,
-                height
+                height,
+                biome: "dungeon".to_string(),
+                lever_links: Vec::new(),
             }
         }
     }
@@ -132,6 +141,11 @@ impl BuilderChain {
         };
     }
 
+    /// Sets the biome of the level being built
+    pub fn set_biome<S: ToString>(&mut self, biome: S) {
+        self.build_data.biome = biome.to_string();
+    }
+
     /// Add a meta builder to the queue
     pub fn with(&mut self, metabuilder: Box<dyn MetaMapBuilder>) {
         self.builders.push(metabuilder);
@@ -152,12 +166,28 @@ impl BuilderChain {
         for metabuilder in self.builders.iter_mut() {
             metabuilder.build_map(rng, &mut self.build_data);
         }
+
+        let (ambient_light, outdoors) = biome_lighting(&self.build_data.biome);
+        self.build_data.map.ambient_light = ambient_light;
+        self.build_data.map.outdoors = outdoors;
     }
 
     pub fn spawn_entities(&mut self, ecs: &mut World) {
         for entity in self.build_data.spawn_list.iter() {
             spawner::spawn_entity(ecs, &(&entity.0, &entity.1));
         }
+        crate::props::link_levers(ecs, &self.build_data.lever_links);
+    }
+}
+
+/// How brightly lit a biome is without any light sources, and whether it's open to the sky.
+/// Dungeons and caves are pitch black; bring a torch.
+fn biome_lighting(biome: &str) -> (f32, bool) {
+    match biome {
+        "town" => (1.0, true),
+        "forest" => (0.8, true),
+        "arena" => (0.5, false),
+        _ => (0.0, false)
     }
 }
 
@@ -302,7 +332,10 @@ pub fn random_builder(new_depth: i32, rng: &mut rltk::RandomNumberGenerator, wid
     let type_roll = rng.roll_dice(1, 2);
     match type_roll {
         1 => random_room_builder(rng, &mut builder),
-        _ => random_shape_builder(rng, &mut builder)
+        _ => {
+            builder.set_biome("caves");
+            random_shape_builder(rng, &mut builder)
+        }
     }
 
     if rng.roll_dice(1, 3) == 1 {
@@ -321,18 +354,50 @@ pub fn random_builder(new_depth: i32, rng: &mut rltk::RandomNumberGenerator, wid
         builder.with(PrefabBuilder::sectional(prefab_builder::prefab_sections::UNDERGROUND_FORT));
     }
 
-    builder.with(DoorPlacement::new());
+    // Vaults go in first, so they can't cover up the keys and levers for the locked doors
     builder.with(PrefabBuilder::vaults());
+    builder.with(DoorPlacement::with_locks(15));
     // */
 
     builder
 }
 
+/// A large, open cave with no spawns, used to benchmark the AI.
+pub fn benchmark_builder(width: i32, height: i32) -> BuilderChain {
+    let mut builder = BuilderChain::new(1, width, height, "Benchmark");
+    builder.start_with(CellularAutomataBuilder::new());
+    builder.with(AreaStartingPosition::new(XStart::CENTER, YStart::CENTER));
+    builder.with(CullUnreachable::new());
+    builder
+}
+
 pub fn level_builder(new_depth: i32, rng: &mut rltk::RandomNumberGenerator, width: i32, height: i32) -> BuilderChain {
     rltk::console::log(format!("Depth: {}", new_depth));
+    if let Some(level) = crate::raws::get_boss_level(&crate::raws::RAWS.lock().unwrap(), new_depth) {
+        let supplies = level.supplies.clone().unwrap_or_default();
+        return boss_arena_builder(new_depth, rng, width, height, &level.name, &level.boss, &supplies);
+    }
     match new_depth {
         1 => town_builder(new_depth, rng, width, height),
         2 => forest_builder(new_depth, rng, width, height),
         _ => random_builder(new_depth, rng, width, height)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_surface_is_open_to_the_sky() {
+        assert_eq!(biome_lighting("town"), (1.0, true));
+        assert!(biome_lighting("forest").1);
+        assert!(!biome_lighting("arena").1);
+    }
+
+    #[test]
+    fn dungeons_and_caves_are_dark() {
+        assert_eq!(biome_lighting("dungeon"), (0.0, false));
+        assert_eq!(biome_lighting("caves"), (0.0, false));
+    }
+}
@@ -18,7 +18,7 @@ impl RoomBasedSpawner {
     fn build(&mut self, rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
         if let Some(rooms) = &build_data.rooms {
             for room in rooms.iter().skip(1) {
-                spawner::spawn_room(&build_data.map, rng, room, build_data.map.depth, &mut build_data.spawn_list);
+                spawner::spawn_room(&build_data.map, rng, room, build_data.map.depth, &build_data.biome, &mut build_data.spawn_list);
             }
         } else {
             panic!("Room Based Spawning only works after rooms have been created!");
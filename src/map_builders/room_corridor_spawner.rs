@@ -23,6 +23,7 @@ impl CorridorSpawner {
                     rng,
                     &c,
                     depth,
+                    &build_data.biome,
                     &mut build_data.spawn_list
                 );
             }
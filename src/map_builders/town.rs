@@ -1,6 +1,7 @@
-use super::{BuilderChain, BuilderMap, InitialMapBuilder, Position, TileType};
+use super::{BuilderChain, BuilderMap, InitialMapBuilder, Position, TileType, Rect};
 use std::collections::HashSet;
 
+#[derive(Debug)]
 enum BuildingTag {
     Pub,
     Temple,
@@ -20,6 +21,7 @@ pub fn town_builder(
     height: i32,
 ) -> BuilderChain {
     let mut chain = BuilderChain::new(new_depth, width, height, "The Town of Quinndolin");
+    chain.set_biome("town");
     chain.start_with(TownBuilder::new());
     chain
 }
@@ -67,6 +69,12 @@ impl TownBuilder {
         let building_size = self.sort_buildings(&buildings);
         self.building_factory(rng, build_data, &buildings, &building_size);
 
+        // Let the townsfolk know where everything is
+        for (i, building) in buildings.iter().enumerate() {
+            let tag = format!("{:?}", building_size[i].2);
+            build_data.map.buildings.push((tag, Rect::new(building.0, building.1, building.2, building.3)));
+        }
+
         // Spawn outdoor NPCs
         self.spawn_dockers(build_data, rng);
         self.spawn_townsfolk(build_data, rng, &mut available_building_tiles);
@@ -394,7 +402,7 @@ impl TownBuilder {
     }
 
     /// Builds the pub. Adds some other hung-over patrons, a "lost" goods salesperson, a barkeep,
-    /// tables, chairs and barrels.
+    /// a sellsword for hire, tables, chairs and barrels.
     fn build_pub(&mut self,
         building: &(i32, i32, i32, i32),
         build_data: &mut BuilderMap,
@@ -408,7 +416,7 @@ impl TownBuilder {
         let player_idx = build_data.map.xy_idx(build_data.starting_position.as_ref().unwrap().x, build_data.starting_position.as_ref().unwrap().y);
 
         // Place other items
-        let mut to_place: Vec<&str> = vec!["Barkeep", "Shady Vendor", "Patron", "Patron", "Table", "Chair", "Table", "Chair"];
+        let mut to_place: Vec<&str> = vec!["Barkeep", "Shady Vendor", "Mercenary", "Patron", "Patron", "Table", "Chair", "Table", "Chair"];
         self.random_building_spawn(building, build_data, rng, &mut to_place, player_idx);
     }
 
@@ -517,11 +525,12 @@ impl TownBuilder {
     fn spawn_townsfolk(&mut self, build_data: &mut BuilderMap, rng: &mut rltk::RandomNumberGenerator, available_building_tiles: &mut HashSet<usize>) {
         for idx in available_building_tiles.iter() {
             if rng.roll_dice(1, 10) == 1{
-                let roll = rng.roll_dice(1, 4);
+                let roll = rng.roll_dice(1, 5);
                 match roll {
                     1 => build_data.spawn_list.push((*idx, "Peasant".to_string())),
                     2 => build_data.spawn_list.push((*idx, "Drunk".to_string())),
                     3 => build_data.spawn_list.push((*idx, "Dock Worker".to_string())),
+                    4 => build_data.spawn_list.push((*idx, "Town Guard".to_string())),
                     _ => build_data.spawn_list.push((*idx, "Fisher".to_string())),
                 }
             }
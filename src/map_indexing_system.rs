@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use super::{Map, Position, BlocksTile};
+use super::{Map, Position, BlocksTile, Door, Locked};
 
 pub struct MapIndexingSystem {}
 
@@ -7,20 +7,26 @@ impl<'a> System<'a> for MapIndexingSystem {
     type SystemData = ( WriteExpect<'a, Map>,
                         ReadStorage<'a, Position>,
                         ReadStorage<'a, BlocksTile>,
-                        Entities<'a>,);
+                        Entities<'a>,
+                        ReadStorage<'a, Door>,
+                        ReadStorage<'a, Locked>,);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, position, blockers, entities) = data;
+        let (mut map, position, blockers, entities, doors, locked) = data;
+
+        // Closed doors don't block paths, since most creatures can open them. Locked ones do. Those
+        // who can't open doors path around them, using the list of closed doors.
+        let blocks = |entity: Entity| blockers.contains(entity) && (!doors.contains(entity) || locked.contains(entity));
 
         map.populate_blocked(); // all walls are blocked
         map.clear_content_index(); // Clear the index of entities
+        map.closed_doors.clear();
 
         for (entity, position) in (&entities, &position).join() {
             let idx = map.xy_idx(position.x, position.y);
 
             // If the entity blocks, update the blocking list
-            let _p: Option<&BlocksTile> = blockers.get(entity);
-            if let Some(_p) = _p {
+            if blocks(entity) {
                 map.blocked[idx] = true;
             }
 
@@ -29,11 +35,17 @@ impl<'a> System<'a> for MapIndexingSystem {
             // ECS!)
             map.tile_content[idx].push(entity);
 
+            if doors.get(entity).is_some_and(|d| !d.open) && !locked.contains(entity) {
+                map.closed_doors.insert(idx);
+            }
+
         }
-        for (position, _blocks) in (&position, &blockers).join() {
+        for (entity, position, _blocks) in (&entities, &position, &blockers).join() {
             // All entities on the map which block movement
             let idx = map.xy_idx(position.x, position.y);
-            map.blocked[idx] = true;
+            if blocks(entity) {
+                map.blocked[idx] = true;
+            }
         }
     }
 }
\ No newline at end of file
@@ -3,10 +3,14 @@ use super::{
     Attributes, WantsToMelee, Name, SufferDamage, gamelog::GameLog,
     HungerClock, HungerState, particle_system::ParticleBuilder, Position,
     Skills, Pools, Skill, Equipped, MeleeWeapon, WeaponAttribute, EquipmentSlot,
-    Wearable, NaturalAttackDefense
+    Wearable, NaturalAttackDefense, SkillUsed, Asleep, NoticedPlayer,
+    noise_system::{NoiseBuilder, COMBAT_NOISE}, clock_system::WorldClock,
 };
 use crate::{skill_bonus};
 
+/// How many turns a creature stays on its guard after it last noticed the player.
+const WARY_TURNS: i32 = 20;
+
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
@@ -28,6 +32,11 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, Wearable>,
         ReadStorage<'a, NaturalAttackDefense>,
         ReadExpect<'a, Entity>,
+        WriteStorage<'a, SkillUsed>,
+        WriteExpect<'a, NoiseBuilder>,
+        ReadStorage<'a, Asleep>,
+        WriteStorage<'a, NoticedPlayer>,
+        ReadExpect<'a, WorldClock>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -35,7 +44,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
             entities, mut log, mut wants_melee, names, attributes, skills,
             mut inflict_damage, mut particle_builder, positions, hunger_clock,
             pools, mut rng, equipped_items, meleeweapons, wearables, natural,
-            player_entity
+            player_entity, mut skill_used, mut noise_builder, asleep, mut noticed, clock
         ) = data;
 
         for (entity, wants_melee, name, attacker_attributes, attacker_skills, attacker_pools) in (&entities, &wants_melee, &names, &attributes, &skills, &pools).join() {
@@ -46,6 +55,11 @@ impl<'a> System<'a> for MeleeCombatSystem {
             if attacker_pools.hit_points.current > 0 && target_pools.hit_points.current > 0 {
                 let target_name = names.get(wants_melee.target).unwrap();
 
+                // Fighting is noisy
+                if let Some(pos) = positions.get(entity) {
+                    noise_builder.request(pos.x, pos.y, COMBAT_NOISE, entity);
+                }
+
                 let mut weapon_info = MeleeWeapon{
                     attribute: WeaponAttribute::Might,
                     hit_bonus: 0,
@@ -102,11 +116,24 @@ impl<'a> System<'a> for MeleeCombatSystem {
                 let armour_item_bonus = armour_item_bonus_f as i32;
                 let armour_class = base_armour_class + armour_quickness_bonus + armour_skill_bonus + armour_item_bonus;
 
+                // Creatures which are asleep, or haven't noticed the player, can't defend themselves
+                let on_guard = noticed.get(wants_melee.target).is_some_and(|n| clock.turn - n.turn <= WARY_TURNS);
+                let unaware = asleep.get(wants_melee.target).is_some() || (entity == *player_entity && !on_guard);
+                if entity == *player_entity {
+                    // Nobody stays unaware of being attacked
+                    noticed.insert(wants_melee.target, NoticedPlayer{ turn: clock.turn }).expect("Unable to insert noticed");
+                }
+
                 // Determine if the attack hits
-                if natural_roll != 1 && (natural_roll == 20 || modified_hit_roll >= armour_class) {
+                if unaware || (natural_roll != 1 && (natural_roll == 20 || modified_hit_roll >= armour_class)) {
                     // Target hit! Until we support weapons, we'll just deal 1d4 damage
                     let mut base_damage = rng.roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
-                    if natural_roll == 20 {
+                    if unaware {
+                        log.entries.push("Sneak attack!".to_string());
+                        base_damage += rng.roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
+                        base_damage += i32::max(0, skill_bonus(Skill::Stealth, attacker_skills));
+                        SkillUsed::new_use(&mut skill_used, entity, Skill::Stealth);
+                    } else if natural_roll == 20 {
                         log.entries.push("Critical hit!!".to_string());
                         base_damage += rng.roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
                     }
@@ -117,6 +144,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
                     let damage = i32::max(0, base_damage + attr_damage_bonus + skill_hit_bonus + skill_damage_bonus + weapon_damage_bonus);
                     let from_player = entity == *player_entity;
                     SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage, from_player);
+                    SkillUsed::new_use(&mut skill_used, entity, Skill::Melee);
                     log.entries.push(format!("{} hits {} for {} damage!", name.name, target_name.name, damage));
                     if let Some(pos) = positions.get(wants_melee.target) {
                         particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 200.0);
@@ -132,6 +160,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
                     if modified_hit_roll > armour_class - armour_item_bonus {
                         // The attack wouldn't have hit even without armour, so it's a dodge
                         log.entries.push(format!("{} attacks {}, but {} dodges!", name.name, target_name.name, target_name.name));
+                        SkillUsed::new_use(&mut skill_used, wants_melee.target, Skill::Defense);
                     } else {
                         log.entries.push(format!("{} attacks {}, but bounces off the armour!", name.name, target_name.name));
                     }
@@ -0,0 +1,174 @@
+use specs::prelude::*;
+use super::{Position, Viewshed, Faction, Behaviour, RemembersPlayer, Asleep, Map, tile_walkable, Name, gamelog::GameLog};
+use crate::raws::{RAWS, Reaction, faction_reaction};
+use rltk::Point;
+use std::collections::{HashMap, BinaryHeap};
+use std::cmp::Reverse;
+
+/// How far the player's footsteps carry.
+pub const FOOTSTEP_NOISE: i32 = 3;
+/// How far the sound of a door opening carries.
+pub const DOOR_NOISE: i32 = 6;
+/// How far the sounds of a fight carry.
+pub const COMBAT_NOISE: i32 = 8;
+/// How far an explosion, such as a fireball, can be heard.
+pub const EXPLOSION_NOISE: i32 = 14;
+
+/// How much harder it is for sound to get through a closed door than through open space.
+const MUFFLED_COST: i32 = 4;
+
+struct NoiseRequest {
+    x: i32,
+    y: i32,
+    volume: i32,
+    source: Entity,
+}
+
+/// Collects the noises made during a turn, so creatures can hear them on the next one.
+pub struct NoiseBuilder {
+    requests: Vec<NoiseRequest>,
+}
+
+impl NoiseBuilder {
+    pub fn new() -> NoiseBuilder {
+        NoiseBuilder{ requests: Vec::new() }
+    }
+
+    /// Makes a noise at (x, y), which can be heard up to `volume` tiles away.
+    pub fn request(&mut self, x: i32, y: i32, volume: i32, source: Entity) {
+        if volume > 0 {
+            self.requests.push(NoiseRequest{ x, y, volume, source });
+        }
+    }
+}
+
+/// Lets creatures react to noises they can't see the source of. A creature goes to investigate
+/// a noise if it would attack whoever made it, and hearing the player counts as seeing them.
+/// Sleeping creatures only wake up if they're close enough to hear the noise clearly.
+pub struct NoiseSystem {}
+
+impl<'a> System<'a> for NoiseSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, NoiseBuilder>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Faction>,
+        WriteStorage<'a, Behaviour>,
+        WriteStorage<'a, RemembersPlayer>,
+        WriteStorage<'a, Asleep>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, GameLog>,
+        ReadExpect<'a, Entity>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities, mut noise_builder, map, positions, viewsheds, factions, mut behaviours,
+            mut remembers_player, mut asleep, names, mut gamelog, player_entity
+        ) = data;
+
+        if noise_builder.requests.is_empty() { return; }
+        let raws = &RAWS.lock().unwrap();
+
+        for noise in noise_builder.requests.iter() {
+            let noise_pos = Point::new(noise.x, noise.y);
+            let source_faction = if let Some(f) = factions.get(noise.source) { &f.name } else { continue; };
+            let reach = noise_reach(&map, map.xy_idx(noise.x, noise.y), noise.volume);
+            for (entity, pos, viewshed, faction, behaviour) in (&entities, &positions, &viewsheds, &factions, &mut behaviours).join() {
+                if entity == noise.source { continue; }
+                let distance = if let Some(d) = reach.get(&map.xy_idx(pos.x, pos.y)) { *d } else { continue; };
+
+                if asleep.get(entity).is_some() {
+                    if distance * 2 > noise.volume { continue; }
+                    asleep.remove(entity);
+                    if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                        if let Some(name) = names.get(entity) {
+                            gamelog.entries.push(format!("{} wakes up!", name.name));
+                        }
+                    }
+                }
+
+                if viewshed.visible_tiles.contains(&noise_pos) { continue; }
+                if faction_reaction(&faction.name, source_faction, raws) != Reaction::Attack { continue; }
+
+                if noise.source == *player_entity {
+                    if let Some(remembers) = remembers_player.get_mut(entity) {
+                        remembers.memory = i32::max(remembers.max_memory, remembers.memory);
+                        remembers.last_seen = Some((noise.x, noise.y));
+                        continue;
+                    }
+                }
+                behaviour.alerted = Some((noise.x, noise.y));
+            }
+        }
+
+        noise_builder.requests.clear();
+    }
+}
+
+/// Works out how far a noise travels along walkable tiles, returning how far it had to travel
+/// to reach each tile it gets to. Closed doors, and anything else which blocks sight, muffle it.
+fn noise_reach(map: &Map, start: usize, volume: i32) -> HashMap<usize, i32> {
+    let mut reach: HashMap<usize, i32> = HashMap::new();
+    let mut open: BinaryHeap<Reverse<(i32, usize)>> = BinaryHeap::new();
+    reach.insert(start, 0);
+    open.push(Reverse((0, start)));
+
+    while let Some(Reverse((cost, idx))) = open.pop() {
+        if reach.get(&idx).is_some_and(|c| *c < cost) { continue; }
+        let x = idx as i32 % map.width;
+        let y = idx as i32 / map.width;
+        for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || nx >= map.width || ny < 0 || ny >= map.height { continue; }
+            let next = map.xy_idx(nx, ny);
+            if !tile_walkable(map.tiles[next]) { continue; }
+            let step = if map.view_blocked.contains(&next) { MUFFLED_COST } else { 1 };
+            let next_cost = cost + step;
+            if next_cost <= volume && reach.get(&next).is_none_or(|c| next_cost < *c) {
+                reach.insert(next, next_cost);
+                open.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+
+    reach
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TileType;
+
+    /// A straight corridor along y = 1, from x = 1 to x = 8.
+    fn corridor() -> Map {
+        let mut map = Map::new(1, 10, 3, "Test");
+        for x in 1..9 {
+            let idx = map.xy_idx(x, 1);
+            map.tiles[idx] = TileType::Floor;
+        }
+        map
+    }
+
+    #[test]
+    fn noise_carries_as_far_as_its_volume() {
+        let map = corridor();
+        let reach = noise_reach(&map, map.xy_idx(1, 1), 3);
+        assert_eq!(reach.get(&map.xy_idx(4, 1)), Some(&3));
+        assert!(!reach.contains_key(&map.xy_idx(5, 1)));
+        assert!(!reach.contains_key(&map.xy_idx(2, 0)));
+    }
+
+    #[test]
+    fn closed_doors_muffle_noise() {
+        let mut map = corridor();
+        map.view_blocked.insert(map.xy_idx(3, 1));
+        let reach = noise_reach(&map, map.xy_idx(1, 1), 6);
+        assert_eq!(reach.get(&map.xy_idx(3, 1)), Some(&(1 + MUFFLED_COST)));
+        assert_eq!(reach.get(&map.xy_idx(4, 1)), Some(&(2 + MUFFLED_COST)));
+        assert!(!reach.contains_key(&map.xy_idx(5, 1)));
+    }
+}
@@ -10,6 +10,7 @@ lazy_static!{
 const DEFAULT_OPTIONS: Options = Options {
     keybinds: KeybindType::Vi,
     vis_mapgen: false,
+    ai_debug: false,
 };
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
@@ -23,6 +24,8 @@ pub enum KeybindType {
 pub struct Options {
     pub keybinds: KeybindType,
     pub vis_mapgen: bool,
+    #[serde(default)]
+    pub ai_debug: bool,
 }
 
 impl Options {
@@ -33,6 +36,7 @@ impl Options {
     pub fn overwrite(&mut self, options: Options) {
         self.keybinds = options.keybinds;
         self.vis_mapgen = options.vis_mapgen;
+        self.ai_debug = options.ai_debug;
     }
 }
 
@@ -2,10 +2,12 @@ use rltk::{VirtualKeyCode, Rltk, Point};
 use specs::prelude::*;
 use super::{
     Position, Player, State, Map, Viewshed, RunState, Pools,
-    WantsToMelee, Item, gamelog::GameLog, WantsToPickupItem, TileType, Monster,
+    WantsToMelee, Item, gamelog::GameLog, WantsToPickupItem, TileType, Faction,
     HungerClock, HungerState, EntityMoved, Door, BlocksVisibility, BlocksTile,
-    Renderable, Bystander, Vendor, options::OPTIONS, options::KeybindType,
+    Renderable, Dialogue, options::OPTIONS, options::KeybindType, Sneaking, Skills, Skill, SkillUsed, sneak_volume, Rooted,
+    noise_system::{NoiseBuilder, FOOTSTEP_NOISE, DOOR_NOISE},
 };
+use crate::raws::{RAWS, Reaction, faction_reaction};
 use std::cmp::{min, max};
 
 #[derive(PartialEq, Copy, Clone)]
@@ -19,6 +21,16 @@ enum Command {
     Menu,
     Descend,
     Help,
+    CharacterSheet,
+    MessageLog,
+    Journal,
+    Orders,
+    Sneak,
+    CloseDoor,
+    BashDoor,
+    Search,
+    Interact,
+    ToggleAiDebug,
     Undefined,
 }
 
@@ -38,6 +50,10 @@ fn key_to_command(key: VirtualKeyCode) -> Command {
                 VirtualKeyCode::M => return Command::Move{x: 1, y: 1},
                 VirtualKeyCode::Semicolon => return Command::Wait,
                 VirtualKeyCode::D => return Command::Drop,
+                VirtualKeyCode::Q => return Command::Journal,
+                VirtualKeyCode::C => return Command::CloseDoor,
+                VirtualKeyCode::S => return Command::Search,
+                VirtualKeyCode::E => return Command::Interact,
                 _ => {}
             }
         }
@@ -53,6 +69,10 @@ fn key_to_command(key: VirtualKeyCode) -> Command {
                 VirtualKeyCode::Numpad3 => return Command::Move{x: 1, y: 1},
                 VirtualKeyCode::Numpad5 => return Command::Wait,
                 VirtualKeyCode::D => return Command::Drop,
+                VirtualKeyCode::J => return Command::Journal,
+                VirtualKeyCode::C => return Command::CloseDoor,
+                VirtualKeyCode::S => return Command::Search,
+                VirtualKeyCode::E => return Command::Interact,
                 _ => {}
             }
         }
@@ -68,6 +88,10 @@ fn key_to_command(key: VirtualKeyCode) -> Command {
                 VirtualKeyCode::C => return Command::Move{x: 1, y: 1},
                 VirtualKeyCode::X => return Command::Wait,
                 VirtualKeyCode::T => return Command::Drop,
+                VirtualKeyCode::J => return Command::Journal,
+                VirtualKeyCode::V => return Command::CloseDoor,
+                VirtualKeyCode::K => return Command::Search,
+                VirtualKeyCode::U => return Command::Interact,
                 _ => {}
             }
         }
@@ -80,11 +104,33 @@ fn key_to_command(key: VirtualKeyCode) -> Command {
         VirtualKeyCode::I => return Command::Inventory,
         VirtualKeyCode::R => return Command::Remove,
         VirtualKeyCode::Slash => return Command::Help,
+        VirtualKeyCode::P => return Command::CharacterSheet,
+        VirtualKeyCode::O => return Command::MessageLog,
+        VirtualKeyCode::F => return Command::Orders,
+        VirtualKeyCode::Tab => return Command::Sneak,
+        VirtualKeyCode::B => return Command::BashDoor,
+        VirtualKeyCode::F3 => return Command::ToggleAiDebug,
         _ => {}
     }
     return Command::Undefined;
 }
 
+/// Finds someone the player would talk to by moving in the given direction, rather than
+/// attacking them or swapping places.
+fn talk_target(delta_x: i32, delta_y: i32, ecs: &World) -> Option<Entity> {
+    let player = *ecs.fetch::<Entity>();
+    let ppos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let dialogue = ecs.read_storage::<Dialogue>();
+    let factions = ecs.read_storage::<Faction>();
+    let (x, y) = (ppos.x + delta_x, ppos.y + delta_y);
+    if x < 0 || x > map.width-1 || y < 0 || y > map.height-1 { return None; }
+
+    map.tile_content[map.xy_idx(x, y)].iter()
+        .find(|e| dialogue.contains(**e) && reaction_to(&factions, player, **e) != Some(Reaction::Attack))
+        .copied()
+}
+
 pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     let mut positions = ecs.write_storage::<Position>();
     let players = ecs.read_storage::<Player>();
@@ -98,8 +144,11 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     let mut blocks_visibility = ecs.write_storage::<BlocksVisibility>();
     let mut blocks_movement = ecs.write_storage::<BlocksTile>();
     let mut renderables = ecs.write_storage::<Renderable>();
-    let bystanders = ecs.read_storage::<Bystander>();
-    let vendors = ecs.read_storage::<Vendor>();
+    let factions = ecs.read_storage::<Faction>();
+    let sneaking = ecs.read_storage::<Sneaking>();
+    let skills = ecs.read_storage::<Skills>();
+    let mut noise_builder = ecs.write_resource::<NoiseBuilder>();
+    let rooted = ecs.read_storage::<Rooted>();
 
     let mut swap_entities: Vec<(Entity, i32, i32)> = Vec::new();
 
@@ -112,10 +161,9 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
 
         for potential_target in map.tile_content[destination_idx].iter() {
-            let bystander = bystanders.get(*potential_target);
-            let vendor = vendors.get(*potential_target);
-            if bystander.is_some() || vendor.is_some() {
-                // Note that we want to move the bystander
+            if reaction_to(&factions, entity, *potential_target) == Some(Reaction::Ignore) {
+                if rooted.contains(entity) { continue; }
+                // Note that we want to move the creature, who doesn't mind us
                 swap_entities.push((*potential_target, pos.x, pos.y));
 
                 // Move the player anyway, even though the space is "blocked"
@@ -134,18 +182,18 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
                     return; // Don't move after attacking.
                 }
             }
-            let door = doors.get_mut(*potential_target);
-            if let Some(door) = door {
-                door.open = true;
-                blocks_visibility.remove(*potential_target);
-                blocks_movement.remove(*potential_target);
-                let glyph = renderables.get_mut(*potential_target).unwrap();
-                glyph.glyph = rltk::to_cp437('/');
+            if doors.get(*potential_target).is_some_and(|d| !d.open) {
+                crate::doors::open_door(*potential_target, &mut doors, &mut blocks_movement, &mut blocks_visibility, &mut renderables);
                 viewshed.dirty = true;
                 opened_door = true;
+                let volume = quieter_if_sneaking(DOOR_NOISE, entity, &sneaking, &skills);
+                noise_builder.request(pos.x + delta_x, pos.y + delta_y, volume, entity);
             }
         }
-        if !map.blocked[destination_idx] {
+        // Opening a door takes the whole move
+        if !opened_door && rooted.contains(entity) {
+            ecs.fetch_mut::<GameLog>().entries.push("You struggle against the net.".to_string());
+        } else if !opened_door && (!map.blocked[destination_idx] || can_swim_into(&map, destination_idx, &blocks_movement)) {
             pos.x = min(map.width-1, max(0, pos.x + delta_x));
             pos.y = min(map.height-1, max(0, pos.y + delta_y));
 
@@ -154,6 +202,8 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             ppos.x = pos.x;
             ppos.y = pos.y;
             entity_moved.insert(entity, EntityMoved{}).expect("Unable to insert marker");
+            let volume = quieter_if_sneaking(FOOTSTEP_NOISE, entity, &sneaking, &skills);
+            noise_builder.request(pos.x, pos.y, volume, entity);
         }
     }
 
@@ -173,6 +223,14 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     }
 }
 
+/// Deep water is off limits to anything finding its own way around, but the player can jump in
+/// if they really want to.
+fn can_swim_into(map: &Map, idx: usize, blocks_movement: &WriteStorage<BlocksTile>) -> bool {
+    map.tiles[idx] == TileType::DeepWater
+        && !map.fire.contains_key(&idx)
+        && !map.tile_content[idx].iter().any(|e| blocks_movement.contains(*e))
+}
+
 fn get_item(ecs: &mut World) {
     let player_pos = ecs.fetch::<Point>();
     let player_entity = ecs.fetch::<Entity>();
@@ -211,10 +269,57 @@ pub fn try_next_level(ecs: &mut World) -> bool {
     }
 }
 
+/// How far a noise made by `entity` carries: `volume`, unless they're sneaking, in which case
+/// their Stealth skill quietens it.
+fn quieter_if_sneaking(volume: i32, entity: Entity, sneaking: &ReadStorage<Sneaking>, skills: &ReadStorage<Skills>) -> i32 {
+    match (sneaking.get(entity), skills.get(entity)) {
+        (Some(_), Some(skills)) => sneak_volume(volume, skills),
+        _ => volume
+    }
+}
+
+/// Practises Stealth if the player moved or attacked while sneaking, and some creature which
+/// could see them has failed to notice them since they last did. This counts once a turn at
+/// most, so standing still in view of a crowd teaches nothing.
+fn practise_stealth(ecs: &mut World) {
+    let player = *ecs.fetch::<Entity>();
+    let acted = ecs.read_storage::<EntityMoved>().contains(player) || ecs.read_storage::<WantsToMelee>().contains(player);
+    if !acted { return; }
+    let practised = ecs.write_storage::<Sneaking>().get_mut(player)
+        .is_some_and(|s| std::mem::take(&mut s.went_unnoticed));
+    if practised {
+        SkillUsed::new_use(&mut ecs.write_storage::<SkillUsed>(), player, Skill::Stealth);
+    }
+}
+
+/// Switches the player between sneaking and walking normally. Sneaking muffles the noise they
+/// make moving around and opening doors.
+fn toggle_sneak(ecs: &mut World) {
+    let player_entity = ecs.fetch::<Entity>();
+    let mut sneaking = ecs.write_storage::<Sneaking>();
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+    if sneaking.remove(*player_entity).is_some() {
+        gamelog.entries.push("You stop sneaking.".to_string());
+    } else {
+        sneaking.insert(*player_entity, Sneaking::default()).expect("Unable to insert sneaking");
+        gamelog.entries.push("You start sneaking.".to_string());
+    }
+}
+
+/// How `other` reacts to `me`, if they both belong to a faction.
+fn reaction_to(factions: &ReadStorage<Faction>, me: Entity, other: Entity) -> Option<Reaction> {
+    match (factions.get(me), factions.get(other)) {
+        (Some(my_faction), Some(their_faction)) => {
+            Some(faction_reaction(&their_faction.name, &my_faction.name, &RAWS.lock().unwrap()))
+        }
+        _ => None
+    }
+}
+
 fn skip_turn(ecs: &mut World) -> RunState {
     let player_entity = ecs.fetch::<Entity>();
     let viewshed_components = ecs.read_storage::<Viewshed>();
-    let monsters = ecs.read_storage::<Monster>();
+    let factions = ecs.read_storage::<Faction>();
 
     let worldmap_resource = ecs.fetch::<Map>();
     
@@ -223,10 +328,9 @@ fn skip_turn(ecs: &mut World) -> RunState {
     for tile in viewshed.visible_tiles.iter() {
         let idx = worldmap_resource.xy_idx(tile.x, tile.y);
         for entity_id in worldmap_resource.tile_content[idx].iter() {
-            let mob = monsters.get(*entity_id);
-            match mob {
-                None => {}
-                Some(_) => {can_heal = false;}
+            // You can't rest with enemies in sight
+            if reaction_to(&factions, *player_entity, *entity_id) == Some(Reaction::Attack) {
+                can_heal = false;
             }
         }
     }
@@ -326,7 +430,19 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
                 Command::Remove => return RunState::ShowRemoveItem,
 
                 // Movement
-                Command::Move{x, y} => try_move_player(x, y, &mut gs.ecs),
+                Command::Move{x, y} => {
+                    if let Some(npc) = talk_target(x, y, &gs.ecs) {
+                        if let Some(node) = crate::dialogue::start_node(&gs.ecs, npc) {
+                            return RunState::ShowDialogue{ npc, node };
+                        }
+                    }
+                    if let Some(door) = crate::doors::locked_door_at(&gs.ecs, x, y) {
+                        crate::doors::unlock_door(&mut gs.ecs, door);
+                        return RunState::PlayerTurn;
+                    }
+                    try_move_player(x, y, &mut gs.ecs);
+                    practise_stealth(&mut gs.ecs);
+                }
 
                 // Level changes
                 Command::Descend => {
@@ -341,9 +457,78 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
                 // Show help
                 Command::Help => return RunState::ShowHelp,
 
+                // Show character sheet
+                Command::CharacterSheet => return RunState::ShowCharacterSheet,
+
+                // Show message history
+                Command::MessageLog => {
+                    gs.log_filter.clear();
+                    return RunState::ShowMessageLog{ offset: 0 };
+                }
+
+                // Show quest journal
+                Command::Journal => return RunState::ShowJournal,
+
+                // Give orders to companions
+                Command::Orders => {
+                    if crate::companion::companion_list(&gs.ecs).is_empty() {
+                        gs.ecs.fetch_mut::<GameLog>().entries.push("You have no companions to give orders to.".to_string());
+                        return RunState::AwaitingInput;
+                    }
+                    return RunState::ShowOrders;
+                }
+
+                // Sneaking doesn't take a turn
+                Command::Sneak => {
+                    toggle_sneak(&mut gs.ecs);
+                    return RunState::AwaitingInput;
+                }
+
+                // Doors
+                Command::CloseDoor => {
+                    if !crate::doors::close_door(&mut gs.ecs) { return RunState::AwaitingInput; }
+                }
+                Command::BashDoor => {
+                    if !crate::doors::bash_door(&mut gs.ecs) { return RunState::AwaitingInput; }
+                }
+
+                // Look for traps
+                Command::Search => crate::traps::search(&mut gs.ecs),
+
+                // Use a prop, or disarm a trap
+                Command::Interact => return crate::props::interact(&mut gs.ecs),
+
+                // Debug: show what each creature is thinking
+                Command::ToggleAiDebug => {
+                    let mut options = OPTIONS.lock().unwrap();
+                    options.ai_debug = !options.ai_debug;
+                    return RunState::AwaitingInput;
+                }
+
                 _ => { return RunState::AwaitingInput } // Key not recognised
             }
         }
     }
     RunState::PlayerTurn
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn sneaking_quietens_noise() {
+        let mut ecs = World::new();
+        ecs.register::<Sneaking>();
+        ecs.register::<Skills>();
+        let skills = Skills{ skills: HashMap::from([(Skill::Stealth, 1)]), untrained: HashMap::new(), attributes: HashMap::new() };
+        let walker = ecs.create_entity().with(skills.clone()).build();
+        let sneaker = ecs.create_entity().with(skills).with(Sneaking::default()).build();
+
+        let sneaking = ecs.read_storage::<Sneaking>();
+        let skills = ecs.read_storage::<Skills>();
+        assert_eq!(quieter_if_sneaking(DOOR_NOISE, walker, &sneaking, &skills), DOOR_NOISE);
+        assert_eq!(quieter_if_sneaking(DOOR_NOISE, sneaker, &sneaking, &skills), DOOR_NOISE / 2 - 1);
+    }
+}
@@ -0,0 +1,275 @@
+use specs::prelude::*;
+use rltk::Point;
+use super::{
+    Container, Lever, LinkedDoor, Fountain, Altar, Door, Position, Map, Name, Renderable, InBackpack,
+    Equipped, Cursed, Pools, HungerClock, HungerState, Poisoned, RunState, BlocksTile,
+    BlocksVisibility, gamelog::GameLog, particle_system::ParticleBuilder, random_table::RandomTable,
+    quest_system::{QuestEvents, QuestEvent}, doors,
+};
+use crate::raws::{RAWS, SpawnType, get_item_drop, spawn_generated_item};
+
+/// Finds something the player could use, on their own tile or next to it. Their own tile comes
+/// first.
+fn interactable_near(ecs: &World) -> Option<Entity> {
+    let ppos = *ecs.fetch::<Point>();
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let containers = ecs.read_storage::<Container>();
+    let levers = ecs.read_storage::<Lever>();
+    let fountains = ecs.read_storage::<Fountain>();
+    let altars = ecs.read_storage::<Altar>();
+    (&entities, &positions).join()
+        .filter(|(e, _)| containers.contains(*e) || levers.contains(*e) || fountains.contains(*e) || altars.contains(*e))
+        .filter(|(_, pos)| (pos.x - ppos.x).abs() <= 1 && (pos.y - ppos.y).abs() <= 1)
+        .min_by_key(|(_, pos)| (pos.x - ppos.x).abs() + (pos.y - ppos.y).abs())
+        .map(|(e, _)| e)
+}
+
+/// Uses whatever prop is nearest the player, or failing that tries to disarm a trap they know
+/// about next to them. Returns the state to move to: containers open up a menu to take things
+/// from, and anything else takes a turn.
+pub fn interact(ecs: &mut World) -> RunState {
+    let Some(prop) = interactable_near(ecs) else {
+        if let Some(trap) = crate::traps::known_trap_near(ecs) {
+            crate::traps::disarm_trap(ecs, trap);
+            return RunState::PlayerTurn;
+        }
+        ecs.fetch_mut::<GameLog>().entries.push("There's nothing here to use.".to_string());
+        return RunState::AwaitingInput;
+    };
+
+    if ecs.read_storage::<Container>().contains(prop) {
+        return open_container(ecs, prop);
+    }
+    if ecs.read_storage::<Lever>().contains(prop) {
+        pull_lever(ecs, prop);
+    } else if ecs.read_storage::<Fountain>().contains(prop) {
+        drink_from_fountain(ecs, prop);
+    } else if ecs.read_storage::<Altar>().contains(prop) {
+        pray_at_altar(ecs, prop);
+    }
+    RunState::PlayerTurn
+}
+
+/// The name of a prop, for messages.
+fn prop_name(ecs: &World, prop: Entity) -> String {
+    ecs.read_storage::<Name>().get(prop).map_or("thing".to_string(), |n| n.name.clone())
+}
+
+/// Greys out a prop which has been used up.
+fn spent(ecs: &mut World, prop: Entity) {
+    if let Some(renderable) = ecs.write_storage::<Renderable>().get_mut(prop) {
+        renderable.fg = rltk::RGB::named(rltk::GREY);
+    }
+}
+
+/// Everything inside a container.
+pub fn container_contents(ecs: &World, container: Entity) -> Vec<Entity> {
+    let entities = ecs.entities();
+    let backpack = ecs.read_storage::<InBackpack>();
+    (&entities, &backpack).join().filter(|(_, pack)| pack.owner == container).map(|(e, _)| e).collect()
+}
+
+/// Opens a container, filling it from its loot table if this is the first time anyone has
+/// looked inside.
+fn open_container(ecs: &mut World, container: Entity) -> RunState {
+    let fill = {
+        let mut containers = ecs.write_storage::<Container>();
+        let Some(contents) = containers.get_mut(container) else { return RunState::AwaitingInput; };
+        let fill = if contents.opened { None } else { Some((contents.loot_table.clone(), contents.rolls)) };
+        contents.opened = true;
+        fill
+    };
+
+    if let Some((table, rolls)) = fill {
+        let raws = &RAWS.lock().unwrap();
+        let depth = ecs.fetch::<Map>().depth;
+        for _ in 0 .. rolls {
+            let drop = get_item_drop(raws, &mut ecs.write_resource::<rltk::RandomNumberGenerator>(), &table);
+            if let Some(item) = drop.filter(|item| item != "None") {
+                spawn_generated_item(raws, ecs, &item, SpawnType::Carried{ by: container }, depth);
+            }
+        }
+    }
+
+    if container_contents(ecs, container).is_empty() {
+        let name = prop_name(ecs, container);
+        ecs.fetch_mut::<GameLog>().entries.push(format!("The {} is empty.", name));
+        return RunState::PlayerTurn;
+    }
+    RunState::ShowContainer{ container }
+}
+
+/// Moves an item out of a container, and into the player's backpack.
+pub fn take_item(ecs: &mut World, container: Entity, item: Entity) {
+    let player = *ecs.fetch::<Entity>();
+    ecs.write_storage::<InBackpack>().insert(item, InBackpack{ owner: player }).expect("Unable to insert backpack entry.");
+    let (item_name, container_name) = (prop_name(ecs, item), prop_name(ecs, container));
+    ecs.fetch_mut::<GameLog>().entries.push(format!("You take the {} from the {}.", item_name, container_name));
+    ecs.write_resource::<QuestEvents>().request(QuestEvent::Collected(item_name));
+}
+
+/// Throws a lever, opening or closing the door it's linked to. A door can't be shut on
+/// something standing in the doorway.
+fn pull_lever(ecs: &mut World, lever: Entity) {
+    if let Some(lever_state) = ecs.write_storage::<Lever>().get_mut(lever) {
+        lever_state.pulled = !lever_state.pulled;
+        if let Some(renderable) = ecs.write_storage::<Renderable>().get_mut(lever) {
+            renderable.glyph = rltk::to_cp437(if lever_state.pulled { '\\' } else { '/' });
+        }
+    }
+    ecs.fetch_mut::<GameLog>().entries.push("You pull the lever.".to_string());
+
+    let Some(door) = ecs.read_storage::<LinkedDoor>().get(lever).map(|link| link.door) else {
+        ecs.fetch_mut::<GameLog>().entries.push("Nothing seems to happen.".to_string());
+        return;
+    };
+    let Some(open) = ecs.read_storage::<Door>().get(door).map(|d| d.open) else { return; };
+    let door_name = prop_name(ecs, door);
+
+    if open {
+        let in_the_way = {
+            let map = ecs.fetch::<Map>();
+            ecs.read_storage::<Position>().get(door).is_some_and(|pos| map.tile_content[map.xy_idx(pos.x, pos.y)].len() > 1)
+        };
+        if in_the_way {
+            ecs.fetch_mut::<GameLog>().entries.push(format!("The {} grinds against something in the way.", door_name));
+            return;
+        }
+        doors::shut_door(ecs, door);
+        ecs.fetch_mut::<GameLog>().entries.push(format!("The {} rumbles shut.", door_name));
+    } else {
+        doors::open_door(
+            door,
+            &mut ecs.write_storage::<Door>(),
+            &mut ecs.write_storage::<BlocksTile>(),
+            &mut ecs.write_storage::<BlocksVisibility>(),
+            &mut ecs.write_storage::<Renderable>(),
+        );
+        ecs.fetch_mut::<GameLog>().entries.push(format!("The {} grinds open.", door_name));
+    }
+    doors::door_moved(ecs, door);
+}
+
+/// Takes a drink from a fountain, with whatever random effect it has.
+fn drink_from_fountain(ecs: &mut World, fountain: Entity) {
+    let player = *ecs.fetch::<Entity>();
+    let effect = {
+        let mut fountains = ecs.write_storage::<Fountain>();
+        let Some(fountain) = fountains.get_mut(fountain) else { return; };
+        if fountain.uses < 1 {
+            None
+        } else {
+            fountain.uses -= 1;
+            let table = fountain.effects.iter().fold(RandomTable::new(), |table, (effect, weight)| table.add(effect, *weight));
+            Some((table.roll(&mut ecs.write_resource::<rltk::RandomNumberGenerator>()), fountain.uses))
+        }
+    };
+    let Some((effect, uses_left)) = effect else {
+        ecs.fetch_mut::<GameLog>().entries.push("The fountain has run dry.".to_string());
+        return;
+    };
+
+    let message = match effect.as_str() {
+        "heal" => {
+            if let Some(pools) = ecs.write_storage::<Pools>().get_mut(player) {
+                pools.hit_points.current = pools.hit_points.max;
+            }
+            "The water is wonderfully refreshing. Your wounds close!"
+        }
+        "mana" => {
+            if let Some(pools) = ecs.write_storage::<Pools>().get_mut(player) {
+                pools.mana.current = pools.mana.max;
+            }
+            "The water tingles on your tongue. Your mind feels clear."
+        }
+        "food" => {
+            if let Some(clock) = ecs.write_storage::<HungerClock>().get_mut(player) {
+                clock.state = HungerState::WellFed;
+                clock.duration = 20;
+            }
+            "The water is strangely filling."
+        }
+        "poison" => {
+            ecs.write_storage::<Poisoned>().insert(player, Poisoned{ damage: 1, turns: 5 }).expect("Unable to insert status");
+            "The water tastes foul. You feel sick!"
+        }
+        _ => "The water is cool and clear, but nothing happens."
+    };
+    ecs.fetch_mut::<GameLog>().entries.push(message.to_string());
+    if let Some(pos) = ecs.read_storage::<Position>().get(player) {
+        ecs.fetch_mut::<ParticleBuilder>().request(pos.x, pos.y, rltk::RGB::named(rltk::CYAN), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('~'), 200.0);
+    }
+
+    if uses_left < 1 {
+        ecs.fetch_mut::<GameLog>().entries.push("The fountain runs dry.".to_string());
+        spent(ecs, fountain);
+    }
+}
+
+/// Prays at an altar, which lifts the curses from everything the player has. If there's
+/// nothing for it to do, the altar keeps its blessing for later.
+fn pray_at_altar(ecs: &mut World, altar: Entity) {
+    let player = *ecs.fetch::<Entity>();
+    let Some(blessing) = ecs.read_storage::<Altar>().get(altar).cloned() else { return; };
+    if blessing.used {
+        ecs.fetch_mut::<GameLog>().entries.push("The altar is cold and silent.".to_string());
+        return;
+    }
+
+    let belongings: Vec<Entity> = {
+        let entities = ecs.entities();
+        let backpack = ecs.read_storage::<InBackpack>();
+        let equipped = ecs.read_storage::<Equipped>();
+        entities.join()
+            .filter(|e| backpack.get(*e).is_some_and(|b| b.owner == player) || equipped.get(*e).is_some_and(|e| e.owner == player))
+            .collect()
+    };
+
+    let mut messages: Vec<String> = Vec::new();
+    if blessing.uncurse {
+        let mut cursed = ecs.write_storage::<Cursed>();
+        let names = ecs.read_storage::<Name>();
+        for item in belongings.iter() {
+            if cursed.remove(*item).is_some() {
+                messages.push(format!("The curse on your {} lifts.", names.get(*item).map_or("item", |n| n.name.as_str())));
+            }
+        }
+    }
+
+    if messages.is_empty() {
+        ecs.fetch_mut::<GameLog>().entries.push("You pray at the altar, but nothing happens.".to_string());
+        return;
+    }
+    let mut log = ecs.fetch_mut::<GameLog>();
+    log.entries.push("You pray at the altar, and it glows with a soft light.".to_string());
+    log.entries.extend(messages);
+    std::mem::drop(log);
+    if let Some(altar) = ecs.write_storage::<Altar>().get_mut(altar) {
+        altar.used = true;
+    }
+    spent(ecs, altar);
+}
+
+/// Links each lever placed by the map builder to its door, given the tile indices of the two.
+pub fn link_levers(ecs: &mut World, links: &[(usize, usize)]) {
+    let width = ecs.fetch::<Map>().width;
+    let at = |idx: usize| Position{ x: idx as i32 % width, y: idx as i32 / width };
+    let found: Vec<(Entity, Entity)> = {
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let levers = ecs.read_storage::<Lever>();
+        let doors = ecs.read_storage::<Door>();
+        let find = |idx: usize, wanted: &dyn Fn(Entity) -> bool| {
+            let target = at(idx);
+            (&entities, &positions).join().find(|(e, pos)| pos.x == target.x && pos.y == target.y && wanted(*e)).map(|(e, _)| e)
+        };
+        links.iter()
+            .filter_map(|(lever, door)| Some((find(*lever, &|e| levers.contains(e))?, find(*door, &|e| doors.contains(e))?)))
+            .collect()
+    };
+    let mut linked = ecs.write_storage::<LinkedDoor>();
+    for (lever, door) in found {
+        linked.insert(lever, LinkedDoor{ door }).expect("Unable to link lever");
+    }
+}
@@ -0,0 +1,199 @@
+use specs::prelude::*;
+use rltk::Point;
+use super::{
+    QuestLog, QuestProgress, QuestState, Escorted, GroupMember, Formation, Pools, PendingLevelUp,
+    InBackpack, Name, StoryFlags, gamelog::GameLog,
+    particle_system::ParticleBuilder, damage_system::{award_xp, level_up_particles},
+};
+use crate::raws::{RAWS, RawMaster, QuestObjective, SpawnType, get_quest, spawn_named_item,
+    spawn_named_mob, free_tiles_near};
+
+/// Something that happened which might move a quest along.
+pub enum QuestEvent {
+    Killed(String),
+    Collected(String),
+    ReachedDepth(i32),
+}
+
+/// Collects quest events from around the game, to be checked against the player's quests at the
+/// end of the turn.
+pub struct QuestEvents {
+    events: Vec<QuestEvent>,
+}
+
+impl QuestEvents {
+    pub fn new() -> QuestEvents {
+        QuestEvents{ events: Vec::new() }
+    }
+
+    pub fn request(&mut self, event: QuestEvent) {
+        self.events.push(event);
+    }
+}
+
+/// How much progress an objective needs before it's done.
+pub fn objective_goal(objective: &QuestObjective) -> i32 {
+    if objective.kill.is_some() || objective.fetch.is_some() { objective.count.unwrap_or(1) } else { 1 }
+}
+
+/// Describes an objective for the quest journal.
+pub fn objective_text(objective: &QuestObjective) -> String {
+    let depth = objective.reach_depth.unwrap_or(1);
+    if let Some(mob) = &objective.kill {
+        format!("Kill {} x{}", mob, objective_goal(objective))
+    } else if let Some(item) = &objective.fetch {
+        format!("Find {} x{}", item, objective_goal(objective))
+    } else if let Some(mob) = &objective.escort {
+        format!("Bring the {} safely to depth {}", mob, depth)
+    } else {
+        format!("Reach depth {}", depth)
+    }
+}
+
+/// Adds a quest to the player's quest log, if they don't already have it. Anyone who needs
+/// escorting turns up next to the player.
+pub fn start_quest(raws: &RawMaster, ecs: &mut World, name: &str) {
+    let quest = if let Some(quest) = get_quest(raws, name) { quest } else { return; };
+    let player = *ecs.fetch::<Entity>();
+    {
+        let mut logs = ecs.write_storage::<QuestLog>();
+        let log = if let Some(log) = logs.get_mut(player) { log } else { return; };
+        if log.quests.iter().any(|q| q.name == name) { return; }
+        log.quests.push(QuestProgress{
+            name: name.to_string(),
+            progress: vec![0; quest.objectives.len()],
+            state: QuestState::Active
+        });
+    }
+    if let Some(flags) = ecs.write_storage::<StoryFlags>().get_mut(player) {
+        flags.flags.insert(format!("quest:{}", name));
+    }
+    ecs.fetch_mut::<GameLog>().entries.push(format!("New quest: {}", name));
+
+    let ppos = *ecs.fetch::<Point>();
+    for objective in quest.objectives.iter() {
+        // Anything already being carried counts towards a fetch quest
+        if let Some(item) = &objective.fetch {
+            ecs.write_resource::<QuestEvents>().request(QuestEvent::Collected(item.clone()));
+        }
+        if let Some(escort) = &objective.escort {
+            if let Some((x, y)) = free_tiles_near(ecs, ppos.x, ppos.y, 3).first().copied() {
+                if let Some(npc) = spawn_named_mob(raws, ecs, escort, SpawnType::AtPosition{ x, y }) {
+                    ecs.write_storage::<Escorted>()
+                        .insert(npc, Escorted{ quest: name.to_string() })
+                        .expect("Unable to insert escort");
+                    let slot = crate::companion::followers(ecs, player).len() as i32;
+                    ecs.write_storage::<GroupMember>()
+                        .insert(npc, GroupMember{ leader: player, formation: Formation::Pack, slot, morale: 100 })
+                        .expect("Unable to insert group member");
+                }
+            }
+        }
+    }
+}
+
+/// Checks this turn's quest events against the player's active quests, then hands out rewards
+/// for any that are finished. A quest fails if someone being escorted dies.
+pub fn update_quests(ecs: &mut World) {
+    let events: Vec<QuestEvent> = ecs.write_resource::<QuestEvents>().events.drain(..).collect();
+    let player = *ecs.fetch::<Entity>();
+    let raws = &RAWS.lock().unwrap();
+
+    let mut finished: Vec<(String, QuestState)> = Vec::new();
+    {
+        let mut logs = ecs.write_storage::<QuestLog>();
+        let escorted = ecs.read_storage::<Escorted>();
+        let pools = ecs.read_storage::<Pools>();
+        let backpack = ecs.read_storage::<InBackpack>();
+        let names = ecs.read_storage::<Name>();
+        let escorts_alive: Vec<&String> = (&escorted, &pools).join()
+            .filter(|(_, p)| p.hit_points.current > 0)
+            .map(|(e, _)| &e.quest)
+            .collect();
+        let carried = |item: &String| (&backpack, &names).join()
+            .filter(|(b, n)| b.owner == player && n.name == *item)
+            .count() as i32;
+
+        let log = if let Some(log) = logs.get_mut(player) { log } else { return; };
+        for progress in log.quests.iter_mut().filter(|q| q.state == QuestState::Active) {
+            let quest = if let Some(quest) = get_quest(raws, &progress.name) { quest } else { continue; };
+            for (i, objective) in quest.objectives.iter().enumerate() {
+                if objective.escort.is_some() && !escorts_alive.contains(&&progress.name) {
+                    progress.state = QuestState::Failed;
+                }
+                for event in events.iter() {
+                    match event {
+                        QuestEvent::Killed(mob) if objective.kill.as_ref() == Some(mob) => progress.progress[i] += 1,
+                        QuestEvent::Collected(item) if objective.fetch.as_ref() == Some(item) => progress.progress[i] = carried(item),
+                        QuestEvent::ReachedDepth(depth) if objective.kill.is_none() && objective.fetch.is_none()
+                            && objective.reach_depth.is_some_and(|d| *depth >= d) => progress.progress[i] = 1,
+                        _ => {}
+                    }
+                }
+                progress.progress[i] = i32::min(progress.progress[i], objective_goal(objective));
+            }
+            if progress.state == QuestState::Active
+                && quest.objectives.iter().enumerate().all(|(i, o)| progress.progress[i] >= objective_goal(o))
+            {
+                progress.state = QuestState::Complete;
+            }
+            if progress.state != QuestState::Active {
+                finished.push((progress.name.clone(), progress.state));
+            }
+        }
+    }
+
+    for (name, state) in finished {
+        if state == QuestState::Failed {
+            ecs.fetch_mut::<GameLog>().entries.push(format!("Quest failed: {}", name));
+        } else {
+            complete_quest(raws, ecs, &name);
+        }
+        release_escorts(ecs, &name);
+    }
+}
+
+fn complete_quest(raws: &RawMaster, ecs: &mut World, name: &str) {
+    let quest = if let Some(quest) = get_quest(raws, name) { quest } else { return; };
+    let player = *ecs.fetch::<Entity>();
+    ecs.fetch_mut::<GameLog>().entries.push(format!("Quest complete: {}!", name));
+    if let Some(flags) = ecs.write_storage::<StoryFlags>().get_mut(player) {
+        flags.flags.insert(format!("quest_done:{}", name));
+    }
+
+    if let Some(gold) = quest.reward.gold {
+        if let Some(pools) = ecs.write_storage::<Pools>().get_mut(player) {
+            pools.gold += gold;
+        }
+        ecs.fetch_mut::<GameLog>().entries.push(format!("You receive {} gold.", gold));
+    }
+    for item in quest.reward.items.iter().flatten() {
+        if spawn_named_item(raws, ecs, item, SpawnType::Carried{ by: player }).is_some() {
+            ecs.fetch_mut::<GameLog>().entries.push(format!("You receive {}.", item));
+        }
+    }
+    if let Some(xp) = quest.reward.xp {
+        let mut pools = ecs.write_storage::<Pools>();
+        let mut pending = ecs.write_storage::<PendingLevelUp>();
+        let mut log = ecs.fetch_mut::<GameLog>();
+        log.entries.push(format!("You gain {} experience.", xp));
+        if award_xp(raws, xp, player, &mut pools, &mut pending, &mut log) > 0 {
+            level_up_particles(&mut ecs.fetch_mut::<ParticleBuilder>(), &ecs.fetch::<Point>());
+        }
+    }
+}
+
+/// Once a quest is over, anyone who was being escorted for it goes their own way.
+fn release_escorts(ecs: &mut World, quest: &str) {
+    let entities = ecs.entities();
+    let mut escorted = ecs.write_storage::<Escorted>();
+    let mut members = ecs.write_storage::<GroupMember>();
+    let released: Vec<Entity> = (&entities, &escorted).join()
+        .filter(|(_, e)| e.quest == quest)
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in released {
+        escorted.remove(entity);
+        members.remove(entity);
+    }
+}
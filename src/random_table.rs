@@ -47,3 +47,15 @@ impl RandomTable {
         "None".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_table_rolls_none() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        assert_eq!(RandomTable::new().roll(&mut rng), "None");
+        assert_eq!(RandomTable::new().add("Rat", 0).roll(&mut rng), "None");
+    }
+}
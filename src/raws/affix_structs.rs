@@ -0,0 +1,12 @@
+use serde::{Deserialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct Affix {
+    pub name: String,
+    pub weight: i32,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub applies_to: Vec<String>,
+    pub effects: HashMap<String, String>,
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize};
+
+#[derive(Deserialize, Debug)]
+pub struct BehaviourProfile {
+    pub name: String,
+    pub options: Vec<BehaviourOption>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BehaviourOption {
+    pub action: String,
+    pub score: f32,
+    pub scale: Option<String>,
+    pub when: Option<BehaviourConditions>,
+    pub cooldown: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BehaviourConditions {
+    pub enemy_adjacent: Option<bool>,
+    pub enemy_visible: Option<bool>,
+    pub fear_visible: Option<bool>,
+    pub ally_visible: Option<bool>,
+    pub item_visible: Option<bool>,
+    pub remembers_target: Option<bool>,
+    pub health_below: Option<f32>,
+    pub away_from_home: Option<i32>,
+    pub out_of_formation: Option<i32>,
+    pub routed: Option<bool>,
+    pub order: Option<String>,
+}
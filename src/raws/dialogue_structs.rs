@@ -0,0 +1,45 @@
+use serde::{Deserialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct DialogueTree {
+    pub name: String,
+    pub start: String,
+    pub nodes: Vec<DialogueNode>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DialogueNode {
+    pub id: String,
+    pub text: String,
+    pub responses: Vec<DialogueResponse>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DialogueResponse {
+    pub text: String,
+    pub next: Option<String>,
+    pub when: Option<DialogueConditions>,
+    pub actions: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DialogueConditions {
+    pub min_level: Option<i32>,
+    pub gold: Option<i32>,
+    pub has_item: Option<String>,
+    pub flag: Option<String>,
+    pub not_flag: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Shop {
+    pub name: String,
+    pub items: Vec<ShopItem>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ShopItem {
+    pub name: String,
+    pub price: i32,
+}
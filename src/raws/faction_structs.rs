@@ -0,0 +1,8 @@
+use serde::{Deserialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct FactionInfo {
+    pub name: String,
+    pub responses: HashMap<String, String>,
+}
@@ -8,6 +8,11 @@ pub struct Item {
     pub consumable: Option<Consumable>,
     pub weapon: Option<Weapon>,
     pub wearable: Option<Wearable>,
+    pub key: Option<bool>,
+    pub heavy: Option<bool>,
+    pub light: Option<Light>,
+    /// How many turns a light source burns for before it runs out.
+    pub fuel: Option<i32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -18,6 +23,13 @@ pub struct Renderable {
     pub order: i32,
 }
 
+/// Light given off by an item, mob or prop: how far it reaches, and its colour.
+#[derive(Deserialize, Debug)]
+pub struct Light {
+    pub range: i32,
+    pub colour: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Consumable {
     pub effects: HashMap<String, String>
@@ -3,7 +3,8 @@ use serde::Deserialize;
 #[derive(Deserialize, Debug)]
 pub struct LootTable {
     pub name: String,
-    pub drops: Vec<LootDrop>
+    pub drops: Vec<LootDrop>,
+    pub guaranteed: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -1,5 +1,5 @@
 use serde::{Deserialize};
-use super::{Renderable};
+use super::{Renderable, Light, BehaviourConditions};
 use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
@@ -8,7 +8,11 @@ pub struct Mob {
     pub renderable: Option<Renderable>,
     pub blocks_tile: bool,
     pub vision_range: i32,
-    pub ai: String,
+    pub faction: String,
+    pub movement: String,
+    pub behaviour: Option<String>,
+    pub schedule: Option<String>,
+    pub dialogue: Option<String>,
     pub memory: Option<MobMemory>,
     pub quips: Option<Vec<String>>,
     pub attributes: MobAttributes,
@@ -18,7 +22,14 @@ pub struct Mob {
     pub mana: Option<i32>,
     pub equipped: Option<Vec<String>>,
     pub natural: Option<MobNatural>,
+    pub abilities: Option<Vec<MobAbility>>,
+    pub phases: Option<Vec<MobPhase>>,
+    pub unique: Option<bool>,
     pub loot_table: Option<String>,
+    pub sleep_chance: Option<i32>,
+    /// Whether the creature can open doors. Most can, but animals can't.
+    pub opens_doors: Option<bool>,
+    pub light: Option<Light>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -46,3 +57,33 @@ pub struct NaturalAttack {
     pub hit_bonus: i32,
     pub damage: String
 }
+
+/// A special ability a mob can use instead of its normal behaviour. It competes with the options in
+/// the mob's behaviour profile on `score`, and can't be used again for `cooldown` turns.
+#[derive(Deserialize, Debug)]
+pub struct MobAbility {
+    pub name: String,
+    pub effect: String,
+    pub score: f32,
+    pub cooldown: i32,
+    pub range: Option<f32>,
+    pub when: Option<BehaviourConditions>,
+    pub damage: Option<String>,
+    pub shape: Option<String>,
+    pub radius: Option<i32>,
+    pub amount: Option<i32>,
+    pub turns: Option<i32>,
+    pub summon: Option<String>,
+    pub count: Option<i32>,
+    pub ignites: Option<bool>,
+}
+
+/// A change which comes over a boss once its health drops below `health_below`: it may switch to
+/// a new behaviour profile, and gains any abilities listed.
+#[derive(Deserialize, Debug)]
+pub struct MobPhase {
+    pub health_below: f32,
+    pub message: Option<String>,
+    pub behaviour: Option<String>,
+    pub abilities: Option<Vec<MobAbility>>,
+}
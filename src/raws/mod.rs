@@ -7,11 +7,29 @@ use mob_structs::*;
 mod prop_structs;
 use prop_structs::*;
 mod spawn_table_structs;
-use spawn_table_structs::*;
+pub use spawn_table_structs::*;
 mod rawmaster;
 pub use rawmaster::*;
 mod loot_structs;
 use loot_structs::*;
+mod affix_structs;
+use affix_structs::*;
+mod background_structs;
+use background_structs::*;
+mod skill_structs;
+use skill_structs::*;
+mod faction_structs;
+use faction_structs::*;
+mod progression_structs;
+use progression_structs::*;
+mod behaviour_structs;
+use behaviour_structs::*;
+mod schedule_structs;
+use schedule_structs::*;
+mod dialogue_structs;
+pub use dialogue_structs::*;
+mod quest_structs;
+pub use quest_structs::*;
 
 use std::sync::Mutex;
 use serde::{Deserialize};
@@ -26,7 +44,21 @@ pub struct Raws {
     pub mobs: Vec<Mob>,
     pub props: Vec<Prop>,
     pub spawn_table: Vec<SpawnTableEntry>,
+    pub spawn_rules: SpawnRules,
+    pub room_themes: Vec<RoomTheme>,
     pub loot_tables: Vec<LootTable>,
+    pub affixes: Vec<Affix>,
+    pub backgrounds: Vec<Background>,
+    pub skills: Vec<SkillDefinition>,
+    pub faction_table: Vec<FactionInfo>,
+    pub progression: Progression,
+    pub behaviours: Vec<BehaviourProfile>,
+    pub spawn_groups: Vec<SpawnGroup>,
+    pub schedules: Vec<ScheduleProfile>,
+    pub dialogues: Vec<DialogueTree>,
+    pub shops: Vec<Shop>,
+    pub quests: Vec<Quest>,
+    pub boss_levels: Vec<BossLevel>,
 }
 
 pub fn load_raws() {
@@ -0,0 +1,28 @@
+use serde::{Deserialize};
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Progression {
+    pub xp_curve: XpCurve,
+    pub kill_xp: KillXp,
+    pub attribute_points_per_level: i32,
+    pub skill_points_per_level: i32,
+    pub max_attribute: i32,
+    pub max_skill: i32,
+}
+
+/// Reaching level `n + 1` from level `n` costs `base * n ^ exponent` xp.
+#[derive(Deserialize, Debug, Default)]
+pub struct XpCurve {
+    pub base: i32,
+    pub exponent: f32,
+}
+
+/// Kills award `per_victim_level` xp for each of the victim's levels, scaled by
+/// `level_difference_scale` for each level the victim is above (or below) the player.
+#[derive(Deserialize, Debug, Default)]
+pub struct KillXp {
+    pub per_victim_level: i32,
+    pub level_difference_scale: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+}
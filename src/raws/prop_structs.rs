@@ -1,5 +1,5 @@
 use serde::{Deserialize};
-use super::{Renderable};
+use super::{Renderable, Light};
 use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
@@ -10,10 +10,57 @@ pub struct Prop {
     pub blocks_tile: Option<bool>,
     pub blocks_visibility: Option<bool>,
     pub door_open: Option<bool>,
+    pub lock: Option<Lock>,
     pub entry_trigger: Option<EntryTrigger>,
+    pub disarm: Option<Disarm>,
+    pub container: Option<Container>,
+    pub lever: Option<bool>,
+    pub fountain: Option<Fountain>,
+    pub altar: Option<Altar>,
+    pub light: Option<Light>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct EntryTrigger {
     pub effects: HashMap<String, String>
 }
+
+/// How hard a locked door is to pick, and to bash in.
+#[derive(Deserialize, Debug)]
+pub struct Lock {
+    pub pick: i32,
+    pub bash: i32,
+}
+
+/// How hard a trap is to disarm, and what (if anything) can be salvaged from it.
+#[derive(Deserialize, Debug)]
+pub struct Disarm {
+    pub difficulty: i32,
+    pub parts: Option<String>,
+}
+
+/// Something to search through for items, rolled from a loot table when it's first opened.
+#[derive(Deserialize, Debug)]
+pub struct Container {
+    pub loot_table: String,
+    pub rolls: Option<i32>,
+}
+
+/// How many drinks a fountain holds, and what each might do.
+#[derive(Deserialize, Debug)]
+pub struct Fountain {
+    pub uses: i32,
+    pub effects: Vec<FountainEffect>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FountainEffect {
+    pub effect: String,
+    pub weight: i32,
+}
+
+/// What praying at an altar does for the player's belongings.
+#[derive(Deserialize, Debug)]
+pub struct Altar {
+    pub uncurse: Option<bool>,
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize};
+
+#[derive(Deserialize, Debug)]
+pub struct Quest {
+    pub name: String,
+    pub description: String,
+    pub objectives: Vec<QuestObjective>,
+    pub reward: QuestReward,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct QuestObjective {
+    pub kill: Option<String>,
+    pub fetch: Option<String>,
+    pub escort: Option<String>,
+    pub count: Option<i32>,
+    pub reach_depth: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct QuestReward {
+    pub xp: Option<i32>,
+    pub gold: Option<i32>,
+    pub items: Option<Vec<String>>,
+}
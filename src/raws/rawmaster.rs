@@ -2,7 +2,10 @@ use std::collections::{HashMap, HashSet};
 use specs::prelude::*;
 use crate::components::*;
 use crate::{attr_bonus, npc_hp, mana_at_level, parse_dice_string};
-use super::{Raws};
+use super::{Raws, Progression, SkillDefinition, BehaviourProfile, BehaviourConditions, ScheduleProfile, DialogueTree,
+    Shop, Quest, MobAbility, BossLevel, SpawnRules, RoomTheme};
+use crate::dialogue::DialogueAction;
+use crate::{Map, tile_walkable};
 use specs::saveload::{MarkedBuilder, SimpleMarker};
 use crate::random_table::{RandomTable};
 
@@ -20,6 +23,15 @@ pub struct RawMaster {
     mob_index: HashMap<String, usize>,
     prop_index: HashMap<String, usize>,
     loot_index: HashMap<String, usize>,
+    affix_index: HashMap<String, usize>,
+    skill_index: HashMap<Skill, usize>,
+    faction_index: HashMap<String, HashMap<String, Reaction>>,
+    behaviour_index: HashMap<String, usize>,
+    group_index: HashMap<String, usize>,
+    schedule_index: HashMap<String, usize>,
+    dialogue_index: HashMap<String, usize>,
+    shop_index: HashMap<String, usize>,
+    quest_index: HashMap<String, usize>,
 }
 
 impl RawMaster {
@@ -31,12 +43,35 @@ impl RawMaster {
                 mobs: Vec::new(),
                 props: Vec::new(),
                 spawn_table: Vec::new(),
-                loot_tables: Vec::new()
+                spawn_rules: SpawnRules::default(),
+                room_themes: Vec::new(),
+                loot_tables: Vec::new(),
+                affixes: Vec::new(),
+                backgrounds: Vec::new(),
+                skills: Vec::new(),
+                faction_table: Vec::new(),
+                progression: Progression::default(),
+                behaviours: Vec::new(),
+                spawn_groups: Vec::new(),
+                schedules: Vec::new(),
+                dialogues: Vec::new(),
+                shops: Vec::new(),
+                quests: Vec::new(),
+                boss_levels: Vec::new(),
             },
             item_index: HashMap::new(),
             mob_index: HashMap::new(),
             prop_index: HashMap::new(),
             loot_index: HashMap::new(),
+            affix_index: HashMap::new(),
+            skill_index: HashMap::new(),
+            faction_index: HashMap::new(),
+            behaviour_index: HashMap::new(),
+            group_index: HashMap::new(),
+            schedule_index: HashMap::new(),
+            dialogue_index: HashMap::new(),
+            shop_index: HashMap::new(),
+            quest_index: HashMap::new(),
         }
     }
 
@@ -64,18 +99,205 @@ impl RawMaster {
             if used_names.contains(&prop.name) {
                 rltk::console::log(format!("WARNING - duplicate prop name in raws [{}]", prop.name));
             }
+            if let Some(container) = &prop.container {
+                if !self.raws.loot_tables.iter().any(|t| t.name == container.loot_table) {
+                    rltk::console::log(format!("WARNING - prop {} uses unknown loot table {}", prop.name, container.loot_table));
+                }
+            }
+            if let Some(parts) = prop.disarm.as_ref().and_then(|d| d.parts.as_ref()) {
+                if !self.item_index.contains_key(parts) {
+                    rltk::console::log(format!("WARNING - prop {} salvages unknown item {}", prop.name, parts));
+                }
+            }
             self.prop_index.insert(prop.name.clone(), i);
             used_names.insert(prop.name.clone());
         }
         for (i, loot) in self.raws.loot_tables.iter().enumerate() {
             self.loot_index.insert(loot.name.clone(), i);
         }
+        self.affix_index = HashMap::new();
+        for (i, affix) in self.raws.affixes.iter().enumerate() {
+            if self.affix_index.contains_key(&affix.name) {
+                rltk::console::log(format!("WARNING - duplicate affix name in raws [{}]", affix.name));
+            }
+            self.affix_index.insert(affix.name.clone(), i);
+        }
+        self.skill_index = HashMap::new();
+        for (i, skill) in self.raws.skills.iter().enumerate() {
+            match string_to_skill(&skill.name) {
+                Some(s) => { self.skill_index.insert(s, i); }
+                None => rltk::console::log(format!("WARNING - raws define unknown skill {}", skill.name))
+            }
+        }
+        self.faction_index = HashMap::new();
+        for faction in self.raws.faction_table.iter() {
+            let mut reactions: HashMap<String, Reaction> = HashMap::new();
+            for other in faction.responses.iter() {
+                reactions.insert(other.0.clone(), string_to_reaction(other.1));
+            }
+            self.faction_index.insert(faction.name.clone(), reactions);
+        }
+        self.behaviour_index = HashMap::new();
+        for (i, behaviour) in self.raws.behaviours.iter().enumerate() {
+            for option in behaviour.options.iter() {
+                if string_to_ai_action(&option.action).is_none() {
+                    rltk::console::log(format!("WARNING - behaviour {} uses unknown action {}", behaviour.name, option.action));
+                }
+                if let Some(order) = option.when.as_ref().and_then(|w| w.order.as_ref()) {
+                    if string_to_companion_order(order).is_none() {
+                        rltk::console::log(format!("WARNING - behaviour {} uses unknown order {}", behaviour.name, order));
+                    }
+                }
+            }
+            self.behaviour_index.insert(behaviour.name.clone(), i);
+        }
+        self.schedule_index = HashMap::new();
+        for (i, schedule) in self.raws.schedules.iter().enumerate() {
+            self.schedule_index.insert(schedule.name.clone(), i);
+        }
+        self.quest_index = HashMap::new();
+        for (i, quest) in self.raws.quests.iter().enumerate() {
+            for objective in quest.objectives.iter() {
+                let target = [&objective.kill, &objective.fetch, &objective.escort];
+                let known = match target {
+                    [Some(mob), None, None] | [None, None, Some(mob)] => self.mob_index.contains_key(mob),
+                    [None, Some(item), None] => self.item_index.contains_key(item),
+                    [None, None, None] => objective.reach_depth.is_some(),
+                    _ => false
+                };
+                if !known {
+                    rltk::console::log(format!("WARNING - quest {} has an objective that can't be met", quest.name));
+                }
+            }
+            for item in quest.reward.items.iter().flatten() {
+                if !self.item_index.contains_key(item) {
+                    rltk::console::log(format!("WARNING - quest {} rewards unknown item {}", quest.name, item));
+                }
+            }
+            self.quest_index.insert(quest.name.clone(), i);
+        }
+        self.shop_index = HashMap::new();
+        for (i, shop) in self.raws.shops.iter().enumerate() {
+            for item in shop.items.iter() {
+                if !self.item_index.contains_key(&item.name) {
+                    rltk::console::log(format!("WARNING - shop {} sells unknown item {}", shop.name, item.name));
+                }
+            }
+            self.shop_index.insert(shop.name.clone(), i);
+        }
+        self.dialogue_index = HashMap::new();
+        for (i, tree) in self.raws.dialogues.iter().enumerate() {
+            let node_ids: HashSet<&String> = tree.nodes.iter().map(|n| &n.id).collect();
+            if !node_ids.contains(&tree.start) {
+                rltk::console::log(format!("WARNING - dialogue {} starts at unknown node {}", tree.name, tree.start));
+            }
+            for node in tree.nodes.iter() {
+                for response in node.responses.iter() {
+                    if let Some(next) = &response.next {
+                        if !node_ids.contains(next) {
+                            rltk::console::log(format!("WARNING - dialogue {} leads to unknown node {}", tree.name, next));
+                        }
+                    }
+                    for action in response.actions.iter().flatten() {
+                        match string_to_dialogue_action(action.0, action.1) {
+                            None => rltk::console::log(format!("WARNING - dialogue {} uses unknown action {}", tree.name, action.0)),
+                            Some(DialogueAction::OpenShop(shop)) if !self.shop_index.contains_key(&shop) => {
+                                rltk::console::log(format!("WARNING - dialogue {} opens unknown shop {}", tree.name, shop));
+                            }
+                            Some(DialogueAction::StartQuest(quest)) if !self.quest_index.contains_key(&quest) => {
+                                rltk::console::log(format!("WARNING - dialogue {} starts unknown quest {}", tree.name, quest));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            self.dialogue_index.insert(tree.name.clone(), i);
+        }
+        for mob in self.raws.mobs.iter() {
+            if let Some(dialogue) = &mob.dialogue {
+                if !self.dialogue_index.contains_key(dialogue) {
+                    rltk::console::log(format!("WARNING - mob {} uses unknown dialogue {}", mob.name, dialogue));
+                }
+            }
+            if let Some(schedule) = &mob.schedule {
+                if !self.schedule_index.contains_key(schedule) {
+                    rltk::console::log(format!("WARNING - mob {} uses unknown schedule {}", mob.name, schedule));
+                }
+            }
+            if !self.faction_index.contains_key(&mob.faction) {
+                rltk::console::log(format!("WARNING - mob {} belongs to unknown faction {}", mob.name, mob.faction));
+            }
+            let behaviour = mob.behaviour.as_deref().unwrap_or(DEFAULT_BEHAVIOUR);
+            if !self.behaviour_index.contains_key(behaviour) {
+                rltk::console::log(format!("WARNING - mob {} uses unknown behaviour {}", mob.name, behaviour));
+            }
+            for phase in mob.phases.iter().flatten() {
+                if let Some(behaviour) = &phase.behaviour {
+                    if !self.behaviour_index.contains_key(behaviour) {
+                        rltk::console::log(format!("WARNING - mob {} has a phase with unknown behaviour {}", mob.name, behaviour));
+                    }
+                }
+            }
+            let phase_abilities = mob.phases.iter().flatten().flat_map(|p| p.abilities.iter().flatten());
+            for ability in mob.abilities.iter().flatten().chain(phase_abilities) {
+                if get_ability(ability).is_none() {
+                    rltk::console::log(format!("WARNING - mob {} has ability {} with an unknown effect or shape", mob.name, ability.name));
+                }
+                if let Some(summon) = &ability.summon {
+                    if !self.mob_index.contains_key(summon) {
+                        rltk::console::log(format!("WARNING - mob {} summons unknown mob {}", mob.name, summon));
+                    }
+                }
+            }
+        }
+
+        self.group_index = HashMap::new();
+        for (i, group) in self.raws.spawn_groups.iter().enumerate() {
+            if used_names.contains(&group.name) {
+                rltk::console::log(format!("WARNING - duplicate spawn group name in raws [{}]", group.name));
+            }
+            for member in std::iter::once(&group.leader).chain(group.followers.iter().map(|f| &f.name)) {
+                if !self.mob_index.contains_key(member) {
+                    rltk::console::log(format!("WARNING - spawn group {} contains unknown mob {}", group.name, member));
+                }
+            }
+            if string_to_formation(&group.formation).is_none() {
+                rltk::console::log(format!("WARNING - spawn group {} uses unknown formation {}", group.name, group.formation));
+            }
+            self.group_index.insert(group.name.clone(), i);
+            used_names.insert(group.name.clone());
+        }
 
         for spawn in self.raws.spawn_table.iter() {
             if !used_names.contains(&spawn.name) {
                 rltk::console::log(format!("WARNING - Spawn tables reference unspecified entity {}", spawn.name));
             }
         }
+        for theme in self.raws.room_themes.iter() {
+            for spawn in theme.spawns.iter() {
+                if !used_names.contains(&spawn.name) {
+                    rltk::console::log(format!("WARNING - room theme {} spawns unspecified entity {}", theme.name, spawn.name));
+                }
+            }
+        }
+        for level in self.raws.boss_levels.iter() {
+            if !self.mob_index.contains_key(&level.boss) && !self.group_index.contains_key(&level.boss) {
+                rltk::console::log(format!("WARNING - boss level {} has unknown boss {}", level.name, level.boss));
+            }
+            for item in level.supplies.iter().flatten() {
+                if !self.item_index.contains_key(item) {
+                    rltk::console::log(format!("WARNING - boss level {} supplies unknown item {}", level.name, item));
+                }
+            }
+        }
+        for table in self.raws.loot_tables.iter() {
+            for item in table.guaranteed.iter().flatten() {
+                if !self.item_index.contains_key(item) {
+                    rltk::console::log(format!("WARNING - loot table {} guarantees unknown item {}", table.name, item));
+                }
+            }
+        }
     }
 
 }
@@ -119,6 +341,198 @@ fn get_renderable_component(renderable: &super::item_structs::Renderable) -> cra
     }
 }
 
+fn get_light_component(light: &super::item_structs::Light) -> LightSource {
+    LightSource {
+        colour: rltk::RGB::from_hex(&light.colour).expect("Invalid RGB"),
+        range: light.range,
+    }
+}
+
+pub fn string_to_skill(skill: &str) -> Option<Skill> {
+    match skill {
+        "Melee" => Some(Skill::Melee),
+        "Defense" => Some(Skill::Defense),
+        "Magic" => Some(Skill::Magic),
+        "Stealth" => Some(Skill::Stealth),
+        "Perception" => Some(Skill::Perception),
+        "Lockpicking" => Some(Skill::Lockpicking),
+        "Ranged" => Some(Skill::Ranged),
+        "Alchemy" => Some(Skill::Alchemy),
+        _ => None
+    }
+}
+
+/// The attribute named in a skill's raws. Anything unrecognised is governed by intelligence.
+fn string_to_skill_attribute(attribute: &str) -> SkillAttribute {
+    match attribute {
+        "Might" => SkillAttribute::Might,
+        "Fitness" => SkillAttribute::Fitness,
+        "Quickness" => SkillAttribute::Quickness,
+        _ => SkillAttribute::Intelligence
+    }
+}
+
+/// How a member of one faction behaves towards a member of another.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Reaction { Ignore, Attack, Flee }
+
+fn string_to_reaction(reaction: &str) -> Reaction {
+    match reaction {
+        "attack" => Reaction::Attack,
+        "flee" => Reaction::Flee,
+        "ignore" => Reaction::Ignore,
+        _ => {
+            rltk::console::log(format!("WARNING - unknown faction reaction {}", reaction));
+            Reaction::Ignore
+        }
+    }
+}
+
+/// The behaviour profile used by mobs which don't name one.
+const DEFAULT_BEHAVIOUR: &str = "Default";
+
+fn string_to_ai_action(action: &str) -> Option<AiAction> {
+    match action {
+        "attack" => Some(AiAction::Attack),
+        "chase" => Some(AiAction::Chase),
+        "surround" => Some(AiAction::Surround),
+        "flee" => Some(AiAction::Flee),
+        "pick_up_item" => Some(AiAction::PickUpItem),
+        "call_for_help" => Some(AiAction::CallForHelp),
+        "guard" => Some(AiAction::Guard),
+        "hunt" => Some(AiAction::Hunt),
+        "follow" => Some(AiAction::Follow),
+        "wander" => Some(AiAction::Wander),
+        "idle" => Some(AiAction::Idle),
+        _ => None
+    }
+}
+
+pub fn string_to_companion_order(order: &str) -> Option<CompanionOrder> {
+    match order {
+        "follow" => Some(CompanionOrder::Follow),
+        "stay" => Some(CompanionOrder::Stay),
+        "attack" => Some(CompanionOrder::Attack),
+        _ => None
+    }
+}
+
+fn string_to_ai_scale(scale: &str) -> AiScale {
+    match scale {
+        "missing_health" => AiScale::MissingHealth,
+        "enemy_closeness" => AiScale::EnemyCloseness,
+        "constant" => AiScale::Constant,
+        _ => {
+            rltk::console::log(format!("WARNING - unknown behaviour scale {}", scale));
+            AiScale::Constant
+        }
+    }
+}
+
+fn get_ai_conditions(when: &Option<BehaviourConditions>) -> AiConditions {
+    match when {
+        None => AiConditions::default(),
+        Some(when) => AiConditions {
+            enemy_adjacent: when.enemy_adjacent,
+            enemy_visible: when.enemy_visible,
+            fear_visible: when.fear_visible,
+            ally_visible: when.ally_visible,
+            item_visible: when.item_visible,
+            remembers_target: when.remembers_target,
+            health_below: when.health_below,
+            away_from_home: when.away_from_home,
+            out_of_formation: when.out_of_formation,
+            routed: when.routed,
+            order: when.order.as_deref().and_then(string_to_companion_order),
+        }
+    }
+}
+
+/// Builds the `Behaviour` component for a raw-defined behaviour profile.
+fn get_behaviour_component(profile: &BehaviourProfile, home: Option<(i32, i32)>) -> Behaviour {
+    let mut options: Vec<AiOption> = Vec::new();
+    for option in profile.options.iter() {
+        if let Some(action) = string_to_ai_action(&option.action) {
+            options.push(AiOption{
+                action,
+                score: option.score,
+                scale: option.scale.as_deref().map_or(AiScale::Constant, string_to_ai_scale),
+                conditions: get_ai_conditions(&option.when),
+                cooldown: option.cooldown.unwrap_or(0),
+                cooldown_remaining: 0,
+            });
+        }
+    }
+    Behaviour{ options, home, alerted: None, decision: String::new() }
+}
+
+fn string_to_area_shape(shape: &str, radius: i32) -> Option<AreaShape> {
+    match shape {
+        "ball" => Some(AreaShape::Ball{ radius }),
+        "line" => Some(AreaShape::Line),
+        "cone" => Some(AreaShape::Cone),
+        _ => None
+    }
+}
+
+/// Turns a raw-defined mob ability into an `Ability`, or `None` if its effect or shape isn't
+/// recognised. Abilities used on an enemy default to a range which suits them.
+fn get_ability(ability: &MobAbility) -> Option<Ability> {
+    let damage = parse_dice_string(ability.damage.as_deref().unwrap_or("1d6"));
+    let amount = ability.amount.unwrap_or(5);
+    let turns = ability.turns.unwrap_or(5);
+    let (effect, range) = match ability.effect.as_str() {
+        "breath" => {
+            let shape = string_to_area_shape(ability.shape.as_deref().unwrap_or("cone"), ability.radius.unwrap_or(1))?;
+            (AbilityEffect::Breath{ damage, shape, ignites: ability.ignites.unwrap_or(false) }, 6.0)
+        }
+        "summon" => (AbilityEffect::Summon{ mob: ability.summon.clone()?, count: ability.count.unwrap_or(1) }, 0.0),
+        "heal" => (AbilityEffect::Heal{ amount }, 0.0),
+        "poison" => (AbilityEffect::Poison{ damage, per_turn: amount, turns }, 1.5),
+        "lunge" => (AbilityEffect::Lunge{ damage }, 3.0),
+        "regen" => (AbilityEffect::Regen{ amount, turns }, 0.0),
+        _ => return None
+    };
+    Some(Ability{
+        name: ability.name.clone(),
+        effect,
+        score: ability.score,
+        range: ability.range.unwrap_or(range),
+        conditions: get_ai_conditions(&ability.when),
+        cooldown: ability.cooldown,
+        cooldown_remaining: 0,
+        known: false,
+    })
+}
+
+fn get_schedule_component(profile: &ScheduleProfile, home: Option<usize>) -> Schedule {
+    let slots = profile.entries.iter()
+        .map(|e| ScheduleSlot{ from: e.from, to: e.to, place: e.place.clone() })
+        .collect();
+    Schedule{ slots, home, current: None }
+}
+
+/// Picks which house someone spawning at `pos` lives in: the one they're standing in, if any,
+/// otherwise any house in town.
+fn find_home(ecs: &World, pos: &SpawnType) -> Option<usize> {
+    let map = ecs.fetch::<Map>();
+    let houses: Vec<usize> = map.buildings.iter().enumerate()
+        .filter(|(_, b)| b.0 == "Hovel" || b.0 == "PlayerHouse")
+        .map(|(i, _)| i)
+        .collect();
+    if let SpawnType::AtPosition{x, y} = pos {
+        let inside = houses.iter().find(|i| {
+            let r = &map.buildings[**i].1;
+            *x >= r.x1 && *x < r.x2 && *y >= r.y1 && *y < r.y2
+        });
+        if let Some(house) = inside { return Some(*house); }
+    }
+    let hovels: Vec<usize> = houses.into_iter().filter(|i| map.buildings[*i].0 == "Hovel").collect();
+    if hovels.is_empty() { return None; }
+    let roll = ecs.write_resource::<rltk::RandomNumberGenerator>().roll_dice(1, hovels.len() as i32);
+    Some(hovels[roll as usize - 1])
+}
+
 pub fn string_to_slot(slot: &str) -> EquipmentSlot {
     match slot {
         "Shield" => EquipmentSlot::Shield,
@@ -127,6 +541,7 @@ pub fn string_to_slot(slot: &str) -> EquipmentSlot {
         "Hands" => EquipmentSlot::Hands,
         "Legs" => EquipmentSlot::Legs,
         "Feet" => EquipmentSlot::Feet,
+        "Light" => EquipmentSlot::Light,
         "Melee" => EquipmentSlot::Melee,
         _ => { rltk::console::log(format!("Warning: unknown equipment slot type [{}]", slot)); EquipmentSlot::Melee }
     }
@@ -159,6 +574,9 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spaw
         if let Some(renderable) = &item_template.renderable {
             eb = eb.with(get_renderable_component(renderable));
         }
+        if let Some(light) = &item_template.light {
+            eb = eb.with(get_light_component(light));
+        }
 
         // Give the entity a name
         eb = eb.with(Name{ name: item_template.name.clone() });
@@ -178,8 +596,10 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spaw
                     "damage" => { eb = eb.with(InflictsDamage{ damage: effect.1.parse::<i32>().unwrap() }) },
                     "area_of_effect" => { eb = eb.with(AreaOfEffect{ radius: effect.1.parse::<i32>().unwrap() }) },
                     "stunned" => { eb = eb.with(Stunned{ turns: effect.1.parse::<i32>().unwrap() }) },
+                    "ignite" => { eb = eb.with(Ignites{}) },
                     "magic_mapping" => { eb = eb.with(MagicMapper{})},
                     "food" => { eb = eb.with(ProvidesFood{})},
+                    "remove_curse" => { eb = eb.with(ProvidesRemoveCurse{})},
                     _ => {
                         rltk::console::log(format!("Warning: consumable effect {} not implemented.", effect_name));
                     }
@@ -209,6 +629,15 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spaw
             eb = eb.with(Equippable{ slot });
             eb = eb.with(Wearable{ slot, armour_class: wearable.armour_class });
         }
+        if let Some(fuel) = item_template.fuel {
+            eb = eb.with(Fuel{ turns: fuel, max: fuel });
+        }
+        if item_template.heavy == Some(true) {
+            eb = eb.with(Heavy{});
+        }
+        if item_template.key == Some(true) {
+            eb = eb.with(Key{});
+        }
 
         return Some(eb.build());
     }
@@ -216,6 +645,144 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spaw
     None
 }
 
+/// The magical properties rolled for a freshly generated item.
+/// * `enchantment` - Added to hit/damage for weapons and to AC for armour. Negative values are cursed.
+/// * `affix` - The name of a raw-defined affix, e.g. "of Fire"
+pub struct ItemMagic {
+    pub enchantment: i32,
+    pub affix: Option<String>,
+}
+
+/// Works out whether an item is a weapon or wearable, which decides which affixes can apply to it.
+fn item_kind(raws: &RawMaster, name: &str) -> Option<&'static str> {
+    if !raws.item_index.contains_key(name) { return None; }
+    let item = &raws.raws.items[raws.item_index[name]];
+    if item.weapon.is_some() {
+        Some("weapon")
+    } else if item.wearable.is_some() {
+        Some("wearable")
+    } else {
+        None
+    }
+}
+
+/// Gets the affixes which may be rolled onto a given kind of item at a given depth.
+pub fn get_affix_table_for_depth(raws: &RawMaster, kind: &str, depth: i32) -> RandomTable {
+    let mut rt = RandomTable::new();
+    for affix in raws.raws.affixes.iter()
+        .filter(|a| depth >= a.min_depth && depth <= a.max_depth && a.applies_to.iter().any(|k| k == kind))
+    {
+        rt = rt.add(affix.name.clone(), affix.weight);
+    }
+    rt
+}
+
+/// Rolls the enchantment level and affix for an item about to be generated at the given depth.
+/// Only weapons and wearables can be magical; everything else always comes out as its template.
+pub fn roll_item_magic(raws: &RawMaster, rng: &mut rltk::RandomNumberGenerator, name: &str, depth: i32) -> ItemMagic {
+    let mut magic = ItemMagic{ enchantment: 0, affix: None };
+    let kind = match item_kind(raws, name) {
+        None => return magic,
+        Some(kind) => kind
+    };
+
+    // Deeper levels produce more magic, and stronger magic
+    let max_level = i32::min(3, 1 + depth / 4);
+    let roll = rng.roll_dice(1, 100);
+    if roll <= 5 + depth {
+        magic.enchantment = -rng.roll_dice(1, max_level);
+    } else if roll > 90 - (depth * 2) {
+        magic.enchantment = rng.roll_dice(1, max_level);
+    }
+
+    if rng.roll_dice(1, 100) <= 10 + (depth * 2) {
+        let affix = get_affix_table_for_depth(raws, kind, depth).roll(rng);
+        if affix != "None" {
+            magic.affix = Some(affix);
+        }
+    }
+
+    magic
+}
+
+/// Applies rolled magic to an item which has already been spawned from its template.
+fn apply_item_magic(raws: &RawMaster, ecs: &mut World, item: Entity, magic: &ItemMagic) {
+    if magic.enchantment == 0 && magic.affix.is_none() { return; }
+
+    let mut weapons = ecs.write_storage::<MeleeWeapon>();
+    let mut wearables = ecs.write_storage::<Wearable>();
+    let mut names = ecs.write_storage::<Name>();
+    let mut cursed = ecs.write_storage::<Cursed>();
+    let mut attribute_bonuses = ecs.write_storage::<AttributeBonus>();
+
+    if let Some(weapon) = weapons.get_mut(item) {
+        weapon.hit_bonus += magic.enchantment;
+        weapon.damage_bonus += magic.enchantment;
+    }
+    if let Some(wearable) = wearables.get_mut(item) {
+        wearable.armour_class += magic.enchantment as f32;
+    }
+    if magic.enchantment < 0 {
+        cursed.insert(item, Cursed{}).expect("Unable to insert curse");
+    }
+
+    if let Some(affix_name) = &magic.affix {
+        let affix = &raws.raws.affixes[raws.affix_index[affix_name]];
+        let mut bonus = AttributeBonus::default();
+        let mut has_bonus = false;
+        for effect in affix.effects.iter() {
+            let amount = effect.1.parse::<i32>().unwrap();
+            match effect.0.as_str() {
+                "hit_bonus" => { if let Some(weapon) = weapons.get_mut(item) { weapon.hit_bonus += amount; } }
+                "damage_bonus" => { if let Some(weapon) = weapons.get_mut(item) { weapon.damage_bonus += amount; } }
+                "armour_class" => { if let Some(wearable) = wearables.get_mut(item) { wearable.armour_class += amount as f32; } }
+                "might" => { bonus.might = Some(amount); has_bonus = true; }
+                "fitness" => { bonus.fitness = Some(amount); has_bonus = true; }
+                "quickness" => { bonus.quickness = Some(amount); has_bonus = true; }
+                "intelligence" => { bonus.intelligence = Some(amount); has_bonus = true; }
+                _ => { rltk::console::log(format!("Warning: affix effect {} not implemented.", effect.0)); }
+            }
+        }
+        if has_bonus {
+            attribute_bonuses.insert(item, bonus).expect("Unable to insert attribute bonus");
+        }
+    }
+
+    if let Some(name) = names.get_mut(item) {
+        let mut new_name = name.name.clone();
+        if magic.enchantment != 0 {
+            new_name = format!("{:+} {}", magic.enchantment, new_name);
+        }
+        if let Some(affix_name) = &magic.affix {
+            new_name = format!("{} {}", new_name, affix_name);
+        }
+        name.name = new_name;
+    }
+}
+
+/// Spawns the named item, randomly rolling enchantments, curses and affixes appropriate to the depth.
+///
+/// Use this rather than `spawn_named_item` for anything found in the world (floor spawns, loot drops),
+/// and `spawn_named_item` when an exact template is wanted (starting kit, mob equipment).
+pub fn spawn_generated_item(raws: &RawMaster, ecs: &mut World, name: &str, pos: SpawnType, depth: i32) -> Option<Entity> {
+    let magic = {
+        let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+        roll_item_magic(raws, &mut rng, name, depth)
+    };
+    let item = spawn_named_item(raws, ecs, name, pos);
+    if let Some(item) = item {
+        apply_item_magic(raws, ecs, item, &magic);
+    }
+    item
+}
+
+/// Whether `name` is a unique monster who has already turned up this run, and so won't again.
+pub fn unique_already_met(raws: &RawMaster, ecs: &World, name: &str) -> bool {
+    let unique = raws.mob_index.get(name).is_some_and(|i| raws.raws.mobs[*i].unique == Some(true));
+    let player = *ecs.fetch::<Entity>();
+    unique && ecs.read_storage::<RunRecord>().get(player).is_some_and(|r| r.uniques_met.contains(name))
+}
+
 /// Spawns a named mob
 /// # Arguments
 /// 
@@ -231,29 +798,65 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spawn
     if raws.mob_index.contains_key(name) {
         let mob_template = &raws.raws.mobs[raws.mob_index[name]];
 
+        // Uniques only turn up once per run
+        if mob_template.unique == Some(true) {
+            let player = *ecs.fetch::<Entity>();
+            if let Some(record) = ecs.write_storage::<RunRecord>().get_mut(player) {
+                if !record.uniques_met.insert(name.to_string()) { return None; }
+            }
+        }
+
+        let asleep = mob_template.sleep_chance
+            .is_some_and(|chance| ecs.write_resource::<rltk::RandomNumberGenerator>().roll_dice(1, 100) <= chance);
+
+        let schedule = mob_template.schedule.as_ref()
+            .and_then(|s| raws.schedule_index.get(s))
+            .map(|i| get_schedule_component(&raws.raws.schedules[*i], find_home(ecs, &pos)));
+
         let mut eb = ecs.create_entity().marked::<SimpleMarker<SerializeMe>>();
 
         // Spawn in the specified location
+        // Creatures with a routine don't stay where they start out
+        let home = match pos {
+            SpawnType::AtPosition{x, y} if schedule.is_none() => Some((x, y)),
+            _ => None
+        };
         eb = spawn_position(pos, eb, name, raws);
 
         // Renderable
         if let Some(renderable) = &mob_template.renderable {
             eb = eb.with(get_renderable_component(renderable));
         }
+        if let Some(light) = &mob_template.light {
+            eb = eb.with(get_light_component(light));
+        }
 
         eb = eb.with(Name{ name: mob_template.name.clone() });
 
-        match mob_template.ai.as_ref() {
-            "melee" => eb = eb.with(Monster{}),
-            "bystander" => eb = eb.with(Bystander{}),
-            "vendor" => eb = eb.with(Vendor{}),
-            "carnivore" => eb = eb.with(Carnivore{}),
-            "herbivore" => eb = eb.with(Herbivore{}),
-            _ => {}
+        // How does it decide what to do?
+        let behaviour = mob_template.behaviour.as_deref().unwrap_or(DEFAULT_BEHAVIOUR);
+        if let Some(profile) = raws.behaviour_index.get(behaviour) {
+            eb = eb.with(get_behaviour_component(&raws.raws.behaviours[*profile], home));
+        }
+
+        if let Some(schedule) = schedule {
+            eb = eb.with(schedule);
+        }
+        if let Some(dialogue) = &mob_template.dialogue {
+            eb = eb.with(Dialogue{ tree: dialogue.clone() });
+        }
+
+        eb = eb.with(Faction{ name: mob_template.faction.clone() });
+        match mob_template.movement.as_ref() {
+            "random" => eb = eb.with(MoveMode{ mode: Movement::Random }),
+            _ => eb = eb.with(MoveMode{ mode: Movement::Static }),
         }
         if mob_template.blocks_tile {
             eb = eb.with(BlocksTile{});
         }
+        if mob_template.opens_doors.unwrap_or(true) {
+            eb = eb.with(OpensDoors{});
+        }
 
         // Set attributes
         let mut mob_fitness = 11;
@@ -289,22 +892,18 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spawn
             level: mob_level,
             xp: 0,
             hit_points: Pool { current: mob_hp, max: mob_hp },
-            mana: Pool{current: mob_mana, max: mob_mana}
+            mana: Pool{current: mob_mana, max: mob_mana},
+            gold: 0,
         };
         eb = eb.with(pools);
 
         // Set skills
-        let mut skills = Skills{ skills: HashMap::new() };
-        skills.skills.insert(Skill::Melee, 1);
-        skills.skills.insert(Skill::Defense, 1);
-        skills.skills.insert(Skill::Magic, 1);
+        let mut skills = base_skills(raws);
         if let Some(mobskills) = &mob_template.skills {
             for sk in mobskills.iter() {
-                match sk.0.as_str() {
-                    "Melee" => { skills.skills.insert(Skill::Melee, *sk.1); },
-                    "Defense" => { skills.skills.insert(Skill::Defense, *sk.1); },
-                    "Magic" => { skills.skills.insert(Skill::Magic, *sk.1); },
-                    _ => { rltk::console::log(format!("Unknown skill referenced: {}", sk.0)); }
+                match string_to_skill(sk.0) {
+                    Some(skill) => { skills.skills.insert(skill, *sk.1); },
+                    None => { rltk::console::log(format!("Unknown skill referenced: {}", sk.0)); }
                 }
             }
         }
@@ -314,7 +913,9 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spawn
         if let Some(memory) = &mob_template.memory {
             eb = eb.with(RemembersPlayer{
                 max_memory: memory.max_memory,
-                memory: 0
+                memory: 0,
+                last_seen: None,
+                heading: (0, 0),
             })
         }
         if let Some(quips) = &mob_template.quips {
@@ -346,11 +947,38 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spawn
             eb = eb.with(nature);
         }
 
+        // Special abilities
+        if let Some(abilities) = &mob_template.abilities {
+            eb = eb.with(Abilities{ abilities: abilities.iter().filter_map(get_ability).collect() });
+        }
+
+        // Bosses change as they're worn down
+        if let Some(phases) = &mob_template.phases {
+            let mut phases: Vec<BossPhase> = phases.iter().map(|phase| BossPhase{
+                health_below: phase.health_below,
+                message: phase.message.clone(),
+                behaviour: phase.behaviour.as_ref()
+                    .and_then(|b| raws.behaviour_index.get(b))
+                    .map(|i| get_behaviour_component(&raws.raws.behaviours[*i], home)),
+                abilities: phase.abilities.iter().flatten().filter_map(get_ability).collect(),
+            }).collect();
+            phases.sort_by(|a, b| b.health_below.total_cmp(&a.health_below));
+            eb = eb.with(BossPhases{ phases, next: 0 });
+        }
+        if mob_template.unique == Some(true) {
+            eb = eb.with(Unique{});
+        }
+
         // Do they have a loot table?
         if let Some(loot) = &mob_template.loot_table {
             eb = eb.with(LootTable{table: loot.clone()});
         }
 
+        // Some creatures might be found asleep
+        if asleep {
+            eb = eb.with(Asleep{});
+        }
+
         // We've finished creating the entity - it can now be committed
         let new_mob = eb.build();
 
@@ -379,6 +1007,9 @@ pub fn spawn_named_prop(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spaw
         if let Some(renderable) = &prop_template.renderable {
             eb = eb.with(get_renderable_component(renderable));
         }
+        if let Some(light) = &prop_template.light {
+            eb = eb.with(get_light_component(light));
+        }
 
         eb = eb.with(Name{name: prop_template.name.clone() });
 
@@ -396,16 +1027,43 @@ pub fn spawn_named_prop(raws: &RawMaster, ecs: &mut World, name: &str, pos: Spaw
         if let Some(door_open) = prop_template.door_open {
             eb = eb.with(Door{ open: door_open })
         }
+        if let Some(lock) = &prop_template.lock {
+            eb = eb.with(Locked{ lock_difficulty: lock.pick, bash_difficulty: lock.bash });
+        }
         if let Some(entry_trigger) = &prop_template.entry_trigger {
             eb = eb.with(EntryTrigger{});
             for effect in entry_trigger.effects.iter() {
                 match effect.0.as_str() {
                     "damage" => { eb = eb.with(InflictsDamage{ damage: effect.1.parse::<i32>().unwrap()})},
                     "single_activation" => { eb = eb.with(SingleActivation{}) },
+                    "teleport" => { eb = eb.with(TeleportTrap{}) },
+                    "alarm" => { eb = eb.with(Alarm{ volume: effect.1.parse::<i32>().unwrap() }) },
+                    "pit" => { eb = eb.with(Pit{}) },
+                    "gas" => {
+                        let effect_value = |key: &str| entry_trigger.effects.get(key).map_or(1, |v| v.parse::<i32>().unwrap());
+                        eb = eb.with(PoisonGas{ radius: effect.1.parse::<i32>().unwrap(), damage: effect_value("gas_damage"), turns: effect_value("gas_turns") });
+                    }
+                    "net" => { eb = eb.with(Ensnares{ turns: effect.1.parse::<i32>().unwrap() }) },
                     _ => {}
                 }
             }
         }
+        if let Some(disarm) = &prop_template.disarm {
+            eb = eb.with(Disarmable{ difficulty: disarm.difficulty, parts: disarm.parts.clone() });
+        }
+        if let Some(container) = &prop_template.container {
+            eb = eb.with(Container{ loot_table: container.loot_table.clone(), rolls: container.rolls.unwrap_or(1), opened: false });
+        }
+        if prop_template.lever == Some(true) {
+            eb = eb.with(Lever{ pulled: false });
+        }
+        if let Some(fountain) = &prop_template.fountain {
+            let effects = fountain.effects.iter().map(|e| (e.effect.clone(), e.weight)).collect();
+            eb = eb.with(Fountain{ uses: fountain.uses, effects });
+        }
+        if let Some(altar) = &prop_template.altar {
+            eb = eb.with(Altar{ uncurse: altar.uncurse.unwrap_or(false), used: false });
+        }
 
         return Some(eb.build());
     }
@@ -420,11 +1078,255 @@ pub fn spawn_named_entity(raws: &RawMaster, ecs: &mut World, name: &str, pos: Sp
         return spawn_named_mob(raws, ecs, name, pos);
     } else if raws.prop_index.contains_key(name) {
         return spawn_named_prop(raws, ecs, name, pos);
+    } else if raws.group_index.contains_key(name) {
+        // Groups can only be placed on the map
+        if let SpawnType::AtPosition{x, y} = pos {
+            return spawn_named_group(raws, ecs, name, x, y);
+        }
     }
 
     None
 }
 
+fn string_to_formation(formation: &str) -> Option<Formation> {
+    match formation {
+        "line" => Some(Formation::Line),
+        "pack" => Some(Formation::Pack),
+        _ => None
+    }
+}
+
+/// The most creatures a spawn group can contain, so that room can be made for all of them.
+pub fn spawn_group_size(raws: &RawMaster, name: &str) -> Option<usize> {
+    raws.group_index.get(name).map(|i| {
+        1 + raws.raws.spawn_groups[*i].followers.iter().map(|f| i32::max(0, f.max) as usize).sum::<usize>()
+    })
+}
+
+/// Lists the walkable, unoccupied tiles within `max_radius` of a point, nearest first.
+pub fn free_tiles_near(ecs: &World, x: i32, y: i32, max_radius: i32) -> Vec<(i32, i32)> {
+    let map = ecs.fetch::<Map>();
+    let positions = ecs.read_storage::<Position>();
+    let blockers = ecs.read_storage::<BlocksTile>();
+    let occupied: HashSet<(i32, i32)> = (&positions, &blockers).join().map(|(p, _)| (p.x, p.y)).collect();
+    let mut free: Vec<(i32, i32)> = Vec::new();
+    for radius in 1 ..= max_radius {
+        for ty in y - radius ..= y + radius {
+            for tx in x - radius ..= x + radius {
+                if i32::max((tx - x).abs(), (ty - y).abs()) != radius { continue; }
+                if tx < 1 || tx >= map.width - 1 || ty < 1 || ty >= map.height - 1 { continue; }
+                if tile_walkable(map.tiles[map.xy_idx(tx, ty)]) && !occupied.contains(&(tx, ty)) {
+                    free.push((tx, ty));
+                }
+            }
+        }
+    }
+    free
+}
+
+/// Spawns a group with its leader at (x, y), and its followers in the closest free spaces around
+/// them. The followers sleep when the leader does, and wake when it does.
+///
+/// # Returns
+/// The group's leader, or `None` if there's no such group.
+pub fn spawn_named_group(raws: &RawMaster, ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    let group = &raws.raws.spawn_groups[*raws.group_index.get(name)?];
+    let formation = string_to_formation(&group.formation).unwrap_or(Formation::Pack);
+    let leader = spawn_named_mob(raws, ecs, &group.leader, SpawnType::AtPosition{ x, y })?;
+    let leader_asleep = ecs.read_storage::<Asleep>().get(leader).is_some();
+
+    // Work out who's coming along
+    let mut follower_names: Vec<&str> = Vec::new();
+    {
+        let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+        for follower in group.followers.iter() {
+            let count = if follower.max > follower.min { rng.range(follower.min, follower.max + 1) } else { follower.min };
+            for _ in 0 .. count { follower_names.push(&follower.name); }
+        }
+    }
+
+    // Find somewhere to put them, starting with the spaces closest to the leader
+    let free = free_tiles_near(ecs, x, y, 3);
+
+    for (slot, (follower_name, (fx, fy))) in follower_names.iter().zip(free.iter()).enumerate() {
+        if let Some(follower) = spawn_named_mob(raws, ecs, follower_name, SpawnType::AtPosition{ x: *fx, y: *fy }) {
+            ecs.write_storage::<GroupMember>()
+                .insert(follower, GroupMember{ leader, formation, slot: slot as i32, morale: group.morale })
+                .expect("Unable to insert group member");
+            let mut asleep = ecs.write_storage::<Asleep>();
+            if leader_asleep {
+                asleep.insert(follower, Asleep{}).expect("Unable to insert asleep");
+            } else {
+                asleep.remove(follower);
+            }
+        }
+    }
+
+    Some(leader)
+}
+
+/// Turns a dialogue action from the raws, such as `"give_item": "Rations"`, into something the
+/// dialogue system can carry out.
+pub fn string_to_dialogue_action(action: &str, value: &str) -> Option<DialogueAction> {
+    match action {
+        "give_item" => Some(DialogueAction::GiveItem(value.to_string())),
+        "take_item" => Some(DialogueAction::TakeItem(value.to_string())),
+        "give_gold" => value.parse::<i32>().ok().map(DialogueAction::GiveGold),
+        "take_gold" => value.parse::<i32>().ok().map(DialogueAction::TakeGold),
+        "set_flag" => Some(DialogueAction::SetFlag(value.to_string())),
+        "change_faction" => Some(DialogueAction::ChangeFaction(value.to_string())),
+        "start_quest" => Some(DialogueAction::StartQuest(value.to_string())),
+        "open_shop" => Some(DialogueAction::OpenShop(value.to_string())),
+        "recruit" => string_to_companion_order(value).map(DialogueAction::Recruit),
+        _ => None
+    }
+}
+
+/// Gets a raw-defined dialogue tree by name.
+pub fn get_dialogue<'a>(raws: &'a RawMaster, name: &str) -> Option<&'a DialogueTree> {
+    raws.dialogue_index.get(name).map(|i| &raws.raws.dialogues[*i])
+}
+
+/// Gets a raw-defined quest by name.
+pub fn get_quest<'a>(raws: &'a RawMaster, name: &str) -> Option<&'a Quest> {
+    raws.quest_index.get(name).map(|i| &raws.raws.quests[*i])
+}
+
+/// Finds the index of a raw-defined shop by name.
+pub fn shop_index(raws: &RawMaster, name: &str) -> Option<usize> {
+    raws.shop_index.get(name).copied()
+}
+
+/// Gets a raw-defined shop by its index.
+pub fn get_shop(raws: &RawMaster, index: usize) -> &Shop {
+    &raws.raws.shops[index]
+}
+
+/// Lists the (name, description) of every background the player can choose from.
+pub fn background_list(raws: &RawMaster) -> Vec<(String, String)> {
+    raws.raws.backgrounds.iter().map(|b| (b.name.clone(), b.description.clone())).collect()
+}
+
+/// Applies a character background to a freshly spawned player, setting their starting skills
+/// and gold, and giving them the background's starting kit.
+pub fn apply_background(raws: &RawMaster, ecs: &mut World, player: Entity, index: usize) {
+    let background = match raws.raws.backgrounds.get(index) {
+        None => { rltk::console::log(format!("WARNING - unknown background index {}", index)); return; }
+        Some(background) => background
+    };
+
+    {
+        let mut skills = ecs.write_storage::<Skills>();
+        if let (Some(player_skills), Some(bg_skills)) = (skills.get_mut(player), &background.skills) {
+            for sk in bg_skills.iter() {
+                match string_to_skill(sk.0) {
+                    Some(skill) => { player_skills.skills.insert(skill, *sk.1); },
+                    None => { rltk::console::log(format!("Unknown skill referenced: {}", sk.0)); }
+                }
+            }
+        }
+        let mut pools = ecs.write_storage::<Pools>();
+        if let Some(player_pools) = pools.get_mut(player) {
+            player_pools.gold = background.gold.unwrap_or(0);
+        }
+    }
+
+    if let Some(equipped) = &background.equipped {
+        for tag in equipped.iter() {
+            spawn_named_entity(raws, ecs, tag, SpawnType::Equipped{ by: player });
+        }
+    }
+    if let Some(carried) = &background.carried {
+        for tag in carried.iter() {
+            spawn_named_entity(raws, ecs, tag, SpawnType::Carried{ by: player });
+        }
+    }
+    if let Some(companion) = &background.companion {
+        let pos = ecs.read_storage::<Position>().get(player).map_or((0, 0), |p| (p.x, p.y));
+        if let Some(pet) = spawn_named_mob(raws, ecs, companion, SpawnType::AtPosition{ x: pos.0, y: pos.1 }) {
+            make_companion(raws, ecs, pet, player, CompanionOrder::Follow);
+        }
+    }
+}
+
+/// The behaviour profile companions use, whatever they did before joining the player.
+const COMPANION_BEHAVIOUR: &str = "Companion";
+
+/// Puts a creature on the player's side. It joins the player's faction, falls in behind them,
+/// and swaps whatever it was doing for the companion behaviour.
+pub fn make_companion(raws: &RawMaster, ecs: &mut World, npc: Entity, player: Entity, order: CompanionOrder) {
+    let slot = crate::companion::followers(ecs, player).iter().filter(|e| **e != npc).count() as i32;
+    let faction = ecs.read_storage::<Faction>().get(player).map_or("Player".to_string(), |f| f.name.clone());
+    ecs.write_storage::<Faction>().insert(npc, Faction{ name: faction }).expect("Unable to insert faction");
+    ecs.write_storage::<Companion>().insert(npc, Companion{ order }).expect("Unable to insert companion");
+    ecs.write_storage::<GroupMember>()
+        .insert(npc, GroupMember{ leader: player, formation: Formation::Pack, slot, morale: 100 })
+        .expect("Unable to insert group member");
+    if let Some(profile) = raws.behaviour_index.get(COMPANION_BEHAVIOUR) {
+        let behaviour = get_behaviour_component(&raws.raws.behaviours[*profile], None);
+        ecs.write_storage::<Behaviour>().insert(npc, behaviour).expect("Unable to insert behaviour");
+    }
+    ecs.write_storage::<Dialogue>().remove(npc);
+    ecs.write_storage::<Schedule>().remove(npc);
+}
+
+/// Every raw-defined skill, in the order they are listed in the raws.
+pub fn skill_list(raws: &RawMaster) -> Vec<Skill> {
+    raws.raws.skills.iter().filter_map(|s| string_to_skill(&s.name)).collect()
+}
+
+/// The skills every creature starts with, before their own raws or background are applied.
+pub fn base_skills(raws: &RawMaster) -> Skills {
+    let mut skills = Skills{ skills: HashMap::new(), untrained: HashMap::new(), attributes: HashMap::new() };
+    for skill in raws.raws.skills.iter() {
+        let Some(s) = string_to_skill(&skill.name) else { continue; };
+        if let Some(base) = skill.base {
+            skills.skills.insert(s, base);
+        }
+        skills.untrained.insert(s, skill.untrained);
+        skills.attributes.insert(s, string_to_skill_attribute(&skill.attribute));
+    }
+    skills
+}
+
+/// The raw definition of a skill, if there is one.
+pub fn skill_definition(raws: &RawMaster, skill: Skill) -> Option<&SkillDefinition> {
+    raws.skill_index.get(&skill).map(|i| &raws.raws.skills[*i])
+}
+
+/// How members of `my_faction` react to members of `their_faction`. Factions which don't list
+/// a specific response use their "Default" response, and unknown factions ignore everyone.
+pub fn faction_reaction(my_faction: &str, their_faction: &str, raws: &RawMaster) -> Reaction {
+    if let Some(mine) = raws.faction_index.get(my_faction) {
+        if let Some(reaction) = mine.get(their_faction) {
+            return *reaction;
+        } else if let Some(default) = mine.get("Default") {
+            return *default;
+        }
+    }
+    Reaction::Ignore
+}
+
+/// The raw-defined rules for gaining experience and levelling up.
+pub fn progression(raws: &RawMaster) -> &Progression {
+    &raws.raws.progression
+}
+
+/// The total xp needed to reach the given level, following the raw-defined xp curve.
+pub fn xp_for_level(raws: &RawMaster, level: i32) -> i32 {
+    let curve = &raws.raws.progression.xp_curve;
+    (1..level).map(|l| (curve.base as f32 * (l as f32).powf(curve.exponent)) as i32).sum()
+}
+
+/// How much xp the player gets for killing a victim of the given level. Victims above the
+/// player's level are worth more, and victims below it are worth less.
+pub fn xp_for_kill(raws: &RawMaster, victim_level: i32, player_level: i32) -> i32 {
+    let kill_xp = &raws.raws.progression.kill_xp;
+    let scale = 1.0 + (victim_level - player_level) as f32 * kill_xp.level_difference_scale;
+    let scale = f32::min(kill_xp.max_scale, f32::max(kill_xp.min_scale, scale));
+    i32::max(1, (kill_xp.per_victim_level as f32 * victim_level as f32 * scale) as i32)
+}
+
 /// Gets an item drop from a loot table
 pub fn get_item_drop(raws: &RawMaster, rng: &mut rltk::RandomNumberGenerator, table: &str) -> Option<String> {
     if raws.loot_index.contains_key(table) {
@@ -438,13 +1340,38 @@ pub fn get_item_drop(raws: &RawMaster, rng: &mut rltk::RandomNumberGenerator, ta
     None
 }
 
-/// Gets a raw-defined spawn table for a given depth of the dungeon.
-pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> RandomTable {
+/// Gets the items a loot table always drops, on top of whatever `get_item_drop` rolls.
+pub fn get_guaranteed_drops(raws: &RawMaster, table: &str) -> Vec<String> {
+    raws.loot_index.get(table)
+        .and_then(|i| raws.raws.loot_tables[*i].guaranteed.clone())
+        .unwrap_or_default()
+}
+
+/// Gets the boss level at a given depth, if there is one.
+pub fn get_boss_level(raws: &RawMaster, depth: i32) -> Option<&BossLevel> {
+    raws.raws.boss_levels.iter().find(|l| l.depth == depth)
+}
+
+fn in_biome(biomes: &Option<Vec<String>>, biome: &str) -> bool {
+    biomes.as_ref().is_none_or(|b| b.iter().any(|b| b == biome))
+}
+
+/// Gets a raw-defined spawn table for a given depth of the dungeon, and the biome of the level.
+pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32, biome: &str) -> RandomTable {
+    spawn_table_where(raws, depth, biome, |_| true)
+}
+
+/// Like `get_spawn_table_for_depth`, but only the creatures and groups of creatures.
+pub fn get_creature_table_for_depth(raws: &RawMaster, depth: i32, biome: &str) -> RandomTable {
+    spawn_table_where(raws, depth, biome, |name| raws.mob_index.contains_key(name) || raws.group_index.contains_key(name))
+}
+
+fn spawn_table_where<F: Fn(&str) -> bool>(raws: &RawMaster, depth: i32, biome: &str, include: F) -> RandomTable {
     use super::SpawnTableEntry;
 
     let available_options: Vec<&SpawnTableEntry> = raws.raws.spawn_table
         .iter()
-        .filter(|a| depth >= a.min_depth && depth <= a.max_depth)
+        .filter(|a| depth >= a.min_depth && depth <= a.max_depth && in_biome(&a.biomes, biome) && include(&a.name))
         .collect();
 
     let mut rt = RandomTable::new();
@@ -458,3 +1385,60 @@ pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> RandomTable {
 
     rt
 }
+
+pub fn get_spawn_rules(raws: &RawMaster) -> &SpawnRules {
+    &raws.raws.spawn_rules
+}
+
+/// Maybe picks a theme for a room, at the given depth and biome.
+pub fn get_room_theme<'a>(raws: &'a RawMaster, rng: &mut rltk::RandomNumberGenerator, depth: i32, biome: &str) -> Option<&'a RoomTheme> {
+    if rng.roll_dice(1, 100) > raws.raws.spawn_rules.room_theme_chance { return None; }
+
+    let mut themes = RandomTable::new();
+    for theme in raws.raws.room_themes.iter().filter(|t| depth >= t.min_depth && depth <= t.max_depth && in_biome(&t.biomes, biome)) {
+        themes = themes.add(&theme.name, theme.weight);
+    }
+    let name = themes.roll(rng);
+    raws.raws.room_themes.iter().find(|t| t.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raws::SpawnTableEntry;
+
+    fn entry(name: &str, biomes: Option<Vec<&str>>) -> SpawnTableEntry {
+        SpawnTableEntry{
+            name: name.to_string(), weight: 10, min_depth: 1, max_depth: 10, add_map_depth_to_weight: None,
+            biomes: biomes.map(|b| b.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    fn rolls(table: &RandomTable) -> HashSet<String> {
+        let mut rng = rltk::RandomNumberGenerator::seeded(1);
+        (0..200).map(|_| table.roll(&mut rng)).collect()
+    }
+
+    #[test]
+    fn spawn_table_only_includes_the_levels_biome() {
+        let mut raws = RawMaster::empty();
+        raws.raws.spawn_table.push(entry("Rat", None));
+        raws.raws.spawn_table.push(entry("Wolf", Some(vec!["forest"])));
+        raws.raws.spawn_table.push(entry("Bat", Some(vec!["caves", "dungeon"])));
+
+        let forest = rolls(&get_spawn_table_for_depth(&raws, 2, "forest"));
+        assert!(forest.contains("Rat") && forest.contains("Wolf"));
+        assert!(!forest.contains("Bat"));
+
+        let caves = rolls(&get_spawn_table_for_depth(&raws, 2, "caves"));
+        assert!(caves.contains("Rat") && caves.contains("Bat"));
+        assert!(!caves.contains("Wolf"));
+    }
+
+    #[test]
+    fn spawn_table_is_empty_without_a_match() {
+        let mut raws = RawMaster::empty();
+        raws.raws.spawn_table.push(entry("Wolf", Some(vec!["forest"])));
+        assert_eq!(rolls(&get_spawn_table_for_depth(&raws, 2, "town")), HashSet::from(["None".to_string()]));
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize};
+
+#[derive(Deserialize, Debug)]
+pub struct ScheduleProfile {
+    pub name: String,
+    pub entries: Vec<ScheduleEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ScheduleEntry {
+    pub from: i32,
+    pub to: i32,
+    pub place: String,
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize};
+
+#[derive(Deserialize, Debug)]
+pub struct SkillDefinition {
+    pub name: String,
+    pub description: String,
+    pub attribute: String,
+    pub base: Option<i32>,
+    pub untrained: i32,
+    pub training_uses: i32,
+}
@@ -7,5 +7,81 @@ pub struct SpawnTableEntry {
     pub weight: i32,
     pub min_depth: i32,
     pub max_depth: i32,
-    pub add_map_depth_to_weight: Option<bool>
+    pub add_map_depth_to_weight: Option<bool>,
+    /// Only spawn on levels of these biomes. Anywhere goes if this is left out.
+    pub biomes: Option<Vec<String>>,
+}
+
+/// Settings for how levels are populated.
+#[derive(Deserialize, Debug, Default)]
+pub struct SpawnRules {
+    /// Percent chance that any one spawn is rolled as if the level were deeper
+    pub out_of_depth_chance: i32,
+    /// How many levels deeper an out-of-depth spawn can come from
+    pub out_of_depth_levels: i32,
+    /// Percent chance that a room is given a theme
+    pub room_theme_chance: i32,
+    pub wandering_monsters: WanderingMonsters,
+}
+
+/// How often wandering monsters turn up on a level. The first one arrives after around `interval`
+/// turns, less `interval_per_depth` for each level of depth, but never sooner than `min_interval`.
+/// Each one that turns up makes the next come `speedup` percent sooner, so it doesn't pay to
+/// hang around. Wanderers only appear on levels of the listed `biomes`, at least `min_distance`
+/// from the player.
+#[derive(Deserialize, Debug, Default)]
+pub struct WanderingMonsters {
+    pub interval: i32,
+    pub interval_per_depth: i32,
+    pub min_interval: i32,
+    pub speedup: i32,
+    pub min_distance: i32,
+    pub biomes: Vec<String>,
+}
+
+/// A theme, such as a barracks or library, which can be given to a room. Themed rooms add their
+/// own `spawns` to the level's spawn table, and get `extra_spawns` more things in them.
+#[derive(Deserialize, Debug)]
+pub struct RoomTheme {
+    pub name: String,
+    pub weight: i32,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub biomes: Option<Vec<String>>,
+    pub spawns: Vec<ThemeSpawn>,
+    pub extra_spawns: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ThemeSpawn {
+    pub name: String,
+    pub weight: i32,
+}
+
+/// A group of creatures which spawn and travel together, such as a wolf pack.
+#[derive(Deserialize, Debug)]
+pub struct SpawnGroup {
+    pub name: String,
+    pub leader: String,
+    pub followers: Vec<GroupFollower>,
+    pub formation: String,
+    pub morale: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GroupFollower {
+    pub name: String,
+    pub min: i32,
+    pub max: i32,
+}
+
+/// A level given over to a boss fight. At `depth`, the usual level generation is replaced by an
+/// arena with the `boss` (a mob or spawn group) waiting in the middle, and any `supplies` laid out
+/// in the antechamber.
+#[derive(Deserialize, Debug)]
+pub struct BossLevel {
+    pub depth: i32,
+    pub name: String,
+    pub boss: String,
+    pub supplies: Option<Vec<String>>,
 }
@@ -23,8 +23,10 @@ macro_rules! serialize_individually {
 pub fn save_game(ecs: &mut World) {
     // Create helper
     let mapcopy = ecs.get_mut::<super::map::Map>().unwrap().clone();
+    let clockcopy = ecs.get_mut::<super::clock_system::WorldClock>().unwrap().clone();
+    let directorcopy = ecs.get_mut::<super::spawn_director::SpawnDirector>().unwrap().clone();
     let _savehelper = ecs.create_entity()
-        .with(SerializationHelper{ map: mapcopy })
+        .with(SerializationHelper{ map: mapcopy, clock: clockcopy, director: directorcopy })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
@@ -35,7 +37,7 @@ pub fn save_game(ecs: &mut World) {
         let writer = File::create("./savegame.json").unwrap();
         let mut serializer = serde_json::Serializer::new(writer);
         serialize_individually!(ecs, serializer, data,
-            Position, Renderable, Player, Viewshed, Monster, Name, BlocksTile,
+            Position, Renderable, Player, Viewshed, Name, BlocksTile,
             SufferDamage, WantsToMelee, Item, Consumable, Ranged,
             InflictsDamage, AreaOfEffect, Stunned, ProvidesHealing, InBackpack,
             WantsToPickupItem, WantsToUseItem, WantsToDropItem,
@@ -43,8 +45,16 @@ pub fn save_game(ecs: &mut World) {
             Wearable, WantsToRemoveItem, ParticleLifetime, HungerClock,
             ProvidesFood, MagicMapper, Hidden, EntryTrigger, EntityMoved,
             SingleActivation, RemembersPlayer, BlocksVisibility, Door,
-            Bystander, Vendor, Quips, Attributes, Skills, Pools,
-            NaturalAttackDefense, LootTable, Carnivore, Herbivore
+            Faction, MoveMode, Quips, Attributes, Skills, Pools,
+            NaturalAttackDefense, LootTable, Cursed,
+            AttributeBonus, PendingLevelUp, SkillPractice, SkillUsed, ProvidesRemoveCurse,
+            MyTurn, WantsToApproach, WantsToFlee, WantsToMove, Behaviour, Sneaking, Asleep,
+            GroupMember, Routed, Schedule, Dialogue, StoryFlags, QuestLog, Escorted,
+            Companion, Abilities, WantsToUseAbility, WantsToSummon, Poisoned, Regenerating,
+            RunRecord, Unique, BossPhases, Locked, Key, OpensDoors,
+            TeleportTrap, Alarm, Pit, PoisonGas, Ensnares, Disarmable, Rooted,
+            Container, Lever, LinkedDoor, Fountain, Altar,
+            Burning, Swimming, Ignites, Heavy, LightSource, Fuel, NoticedPlayer
         );
     }
 }
@@ -87,7 +97,7 @@ pub fn load_game(ecs: &mut World) {
         let mut d = (&mut ecs.entities(), &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(), &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>());
 
         deserialize_individually!(ecs, de, d,
-            Position, Renderable, Player, Viewshed, Monster, Name, BlocksTile,
+            Position, Renderable, Player, Viewshed, Name, BlocksTile,
             SufferDamage, WantsToMelee, Item, Consumable, Ranged,
             InflictsDamage, AreaOfEffect, Stunned, ProvidesHealing, InBackpack,
             WantsToPickupItem, WantsToUseItem, WantsToDropItem,
@@ -95,8 +105,16 @@ pub fn load_game(ecs: &mut World) {
             Wearable, WantsToRemoveItem, ParticleLifetime, HungerClock,
             ProvidesFood, MagicMapper, Hidden, EntryTrigger, EntityMoved,
             SingleActivation, RemembersPlayer, BlocksVisibility, Door,
-            Bystander, Vendor, Quips, Attributes, Skills, Pools,
-            NaturalAttackDefense, LootTable, Carnivore, Herbivore
+            Faction, MoveMode, Quips, Attributes, Skills, Pools,
+            NaturalAttackDefense, LootTable, Cursed,
+            AttributeBonus, PendingLevelUp, SkillPractice, SkillUsed, ProvidesRemoveCurse,
+            MyTurn, WantsToApproach, WantsToFlee, WantsToMove, Behaviour, Sneaking, Asleep,
+            GroupMember, Routed, Schedule, Dialogue, StoryFlags, QuestLog, Escorted,
+            Companion, Abilities, WantsToUseAbility, WantsToSummon, Poisoned, Regenerating,
+            RunRecord, Unique, BossPhases, Locked, Key, OpensDoors,
+            TeleportTrap, Alarm, Pit, PoisonGas, Ensnares, Disarmable, Rooted,
+            Container, Lever, LinkedDoor, Fountain, Altar,
+            Burning, Swimming, Ignites, Heavy, LightSource, Fuel, NoticedPlayer
         );
     }
 
@@ -110,6 +128,8 @@ pub fn load_game(ecs: &mut World) {
             let mut worldmap = ecs.write_resource::<super::map::Map>();
             *worldmap = h.map.clone();
             worldmap.tile_content = vec![Vec::new(); (worldmap.width*worldmap.height) as usize];
+            *ecs.write_resource::<super::clock_system::WorldClock>() = h.clock.clone();
+            *ecs.write_resource::<super::spawn_director::SpawnDirector>() = h.director.clone();
             deleteme = Some(e);
         }
         for (e, _p, pos) in (&entities, &player, &position).join() {
@@ -0,0 +1,46 @@
+use specs::prelude::*;
+use super::{Skills, SkillPractice, SkillUsed, Player, gamelog::GameLog};
+use crate::raws::{RAWS, skill_definition, progression};
+
+/// Improves skills through use. Each rank takes more practice to reach than the one before.
+pub struct SkillTrainingSystem {}
+
+impl<'a> System<'a> for SkillTrainingSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, SkillUsed>,
+        WriteStorage<'a, SkillPractice>,
+        WriteStorage<'a, Skills>,
+        ReadStorage<'a, Player>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut skill_used, mut practice, mut skills, player, mut log) = data;
+        let raws = &RAWS.lock().unwrap();
+        let max_skill = progression(raws).max_skill;
+
+        for (entity, used, practice, skills) in (&entities, &skill_used, &mut practice, &mut skills).join() {
+            for skill in used.skills.iter() {
+                let training_uses = match skill_definition(raws, *skill) {
+                    None => continue,
+                    Some(definition) => definition.training_uses
+                };
+                let rank = skills.skills.get(skill).copied().unwrap_or(0);
+                if rank >= max_skill { continue; }
+
+                let uses = practice.uses.entry(*skill).or_insert(0);
+                *uses += 1;
+                if *uses >= training_uses * i32::max(1, rank + 1) {
+                    *uses = 0;
+                    skills.skills.insert(*skill, i32::max(1, rank + 1));
+                    if player.get(entity).is_some() {
+                        log.entries.push(format!("Your {:?} skill improves to {}!", skill, i32::max(1, rank + 1)));
+                    }
+                }
+            }
+        }
+
+        skill_used.clear();
+    }
+}
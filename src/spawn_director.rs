@@ -0,0 +1,114 @@
+use specs::prelude::*;
+use serde::{Serialize, Deserialize};
+use rltk::Point;
+use super::{Map, RunState, tile_walkable};
+use crate::raws::{RAWS, SpawnType, WanderingMonsters, get_spawn_rules, get_creature_table_for_depth, spawn_named_entity};
+
+/// Keeps track of how long the player has spent on the current level, so that wandering monsters
+/// can be sent their way if they linger. Saved along with the map.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SpawnDirector {
+    pub biome: String,
+    pub turns_on_level: i32,
+    /// The turn on which the next wanderer is due, once one has been scheduled
+    pub next_spawn: Option<i32>,
+    pub spawned: i32,
+}
+
+impl SpawnDirector {
+    pub fn new<S: ToString>(biome: S) -> SpawnDirector {
+        SpawnDirector{ biome: biome.to_string(), turns_on_level: 0, next_spawn: None, spawned: 0 }
+    }
+}
+
+/// How many turns until the next wanderer, before a bit of randomness is added.
+fn wandering_interval(rules: &WanderingMonsters, depth: i32, spawned: i32) -> i32 {
+    let interval = i32::max(rules.min_interval, rules.interval - depth * rules.interval_per_depth);
+    let speedup = (1.0 - rules.speedup as f32 / 100.0).powi(spawned);
+    i32::max(rules.min_interval, (interval as f32 * speedup) as i32)
+}
+
+/// Counts off the player's turns on the level, and when a wanderer is due, adds a creature from
+/// the level's spawn table somewhere out of the player's sight. Spawning needs the whole world,
+/// so this runs outside of the dispatcher.
+pub fn wandering_monsters(ecs: &mut World) {
+    if *ecs.fetch::<RunState>() != RunState::PlayerTurn { return; }
+
+    let raws = &RAWS.lock().unwrap();
+    let rules = &get_spawn_rules(raws).wandering_monsters;
+    let depth = ecs.fetch::<Map>().depth;
+
+    // Is it time?
+    let biome = {
+        let mut director = ecs.write_resource::<SpawnDirector>();
+        if !rules.biomes.contains(&director.biome) { return; }
+        director.turns_on_level += 1;
+        match director.next_spawn {
+            None => {
+                let interval = wandering_interval(rules, depth, director.spawned);
+                let delay = ecs.write_resource::<rltk::RandomNumberGenerator>().range(interval * 3 / 4, interval * 5 / 4 + 1);
+                director.next_spawn = Some(director.turns_on_level + delay);
+                return;
+            }
+            Some(turn) if director.turns_on_level < turn => return,
+            _ => {}
+        }
+        director.biome.clone()
+    };
+
+    // Find somewhere well away from the player, where they can't see it arrive
+    let spawn = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = *ecs.fetch::<Point>();
+        let candidates: Vec<(i32, i32)> = (0 .. map.tiles.len())
+            .filter(|idx| tile_walkable(map.tiles[*idx]) && !map.blocked[*idx] && !map.visible_tiles[*idx] && map.tile_content[*idx].is_empty())
+            .map(|idx| (idx as i32 % map.width, idx as i32 / map.width))
+            .filter(|(x, y)| rltk::DistanceAlg::Pythagoras.distance2d(player_pos, Point::new(*x, *y)) >= rules.min_distance as f32)
+            .collect();
+        let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+        let name = get_creature_table_for_depth(raws, depth, &biome).roll(&mut rng);
+        if candidates.is_empty() || name == "None" {
+            None
+        } else {
+            let (x, y) = candidates[rng.roll_dice(1, candidates.len() as i32) as usize - 1];
+            Some((name, x, y))
+        }
+    };
+
+    let spawned = spawn.is_some_and(|(name, x, y)| spawn_named_entity(raws, ecs, &name, SpawnType::AtPosition{ x, y }).is_some());
+    let mut director = ecs.write_resource::<SpawnDirector>();
+    if spawned {
+        director.spawned += 1;
+        director.next_spawn = None;
+    } else {
+        // Nothing turned up this time; try again shortly
+        director.next_spawn = Some(director.turns_on_level + 10);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> WanderingMonsters {
+        WanderingMonsters{ interval: 200, interval_per_depth: 10, min_interval: 50, speedup: 20, ..Default::default() }
+    }
+
+    #[test]
+    fn wanderers_come_sooner_deeper_down() {
+        assert_eq!(wandering_interval(&rules(), 0, 0), 200);
+        assert_eq!(wandering_interval(&rules(), 5, 0), 150);
+    }
+
+    #[test]
+    fn each_wanderer_hurries_the_next() {
+        assert_eq!(wandering_interval(&rules(), 0, 1), 160);
+        assert_eq!(wandering_interval(&rules(), 0, 2), 128);
+    }
+
+    #[test]
+    fn wanderers_never_come_sooner_than_the_minimum() {
+        assert_eq!(wandering_interval(&rules(), 30, 0), 50);
+        assert_eq!(wandering_interval(&rules(), 0, 20), 50);
+    }
+}
@@ -3,18 +3,16 @@ use specs::prelude::*;
 use super::{
     Player, Renderable, Name, Position, Viewshed, Rect,
     SerializeMe, random_table::RandomTable, HungerClock, HungerState, Map,
-    TileType, raws::*, Attributes, Attribute, Skills, Skill, Pools, Pool,
+    TileType, raws::*, Attributes, Attribute, SkillPractice, Pools, Pool, Faction,
+    StoryFlags, QuestLog, RunRecord,
 };
-use crate::{ attr_bonus, player_hp_at_level, mana_at_level};
+use crate::{ attr_bonus, player_hp_at_level, mana_at_level, character_creation::NewCharacter };
 use specs::saveload::{MarkedBuilder, SimpleMarker};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-/// Spawns the player and returns their entity object.
-pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
-    let mut skills = Skills{ skills: HashMap::new() };
-    skills.skills.insert(Skill::Melee, 1);
-    skills.skills.insert(Skill::Defense, 1);
-    skills.skills.insert(Skill::Magic, 1);
+/// Spawns the player from the choices made during character creation, and returns their entity object.
+pub fn player(ecs: &mut World, player_x: i32, player_y: i32, character: &NewCharacter) -> Entity {
+    let skills = base_skills(&RAWS.lock().unwrap());
 
     let player = ecs
         .create_entity()
@@ -26,52 +24,51 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
             render_order: 0,
         })
         .with(Player{})
+        .with(Faction{ name: "Player".to_string() })
         .with(Viewshed{ visible_tiles: Vec::new(), range: 8, dirty: true })
-        .with(Name{ name: "Player".to_string() })
+        .with(Name{ name: character.display_name() })
         .with(HungerClock{
             state: HungerState::WellFed,
-            duration: 500, // TODO: change back to 20
+            duration: 20,
         })
         .with(Attributes{
-            might: Attribute{ base: 11, modifiers: 0, bonus: attr_bonus(11)},
-            fitness: Attribute{ base: 11, modifiers: 0, bonus: attr_bonus(11)},
-            quickness: Attribute{ base: 11, modifiers: 0, bonus: attr_bonus(11)},
-            intelligence: Attribute{ base: 11, modifiers: 0, bonus: attr_bonus(11)}
+            might: Attribute{ base: character.might(), modifiers: 0, bonus: attr_bonus(character.might())},
+            fitness: Attribute{ base: character.fitness(), modifiers: 0, bonus: attr_bonus(character.fitness())},
+            quickness: Attribute{ base: character.quickness(), modifiers: 0, bonus: attr_bonus(character.quickness())},
+            intelligence: Attribute{ base: character.intelligence(), modifiers: 0, bonus: attr_bonus(character.intelligence())}
         })
         .with(skills)
+        .with(SkillPractice{ uses: HashMap::new() })
         .with(Pools{
             hit_points: Pool{
-                current: player_hp_at_level(51, 1), //TODO: change back to 11
-                max: player_hp_at_level(11, 1)
+                current: player_hp_at_level(character.fitness(), 1),
+                max: player_hp_at_level(character.fitness(), 1)
             },
             mana: Pool{
-                current: mana_at_level(11, 1),
-                max: mana_at_level(11, 1)
+                current: mana_at_level(character.intelligence(), 1),
+                max: mana_at_level(character.intelligence(), 1)
             },
             xp: 0,
             level: 1,
+            gold: 0,
         })
+        .with(StoryFlags{ flags: HashSet::new() })
+        .with(QuestLog{ quests: Vec::new() })
+        .with(RunRecord{ uniques_met: HashSet::new(), uniques_killed: Vec::new() })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
-    // Starting equipment
-    // TODO: remove Tower Shield/Longsword, return Rusty Longsword
-    spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Longsword", SpawnType::Equipped{ by: player });
-    spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Tower Shield", SpawnType::Equipped{ by: player });
-    // spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Rusty Longsword", SpawnType::Equipped{ by: player });
-    spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Dried Sausage", SpawnType::Carried{by: player});
-    spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Beer", SpawnType::Carried{by: player});
-    spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Stained Tunic", SpawnType::Equipped{ by: player });
-    spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Torn Trousers", SpawnType::Equipped{ by: player });
-    spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Old Boots", SpawnType::Equipped{ by: player });
+    // Starting skills, gold and equipment come from the chosen background
+    apply_background(&RAWS.lock().unwrap(), ecs, player, character.background);
 
     player
 }
 
 const MAX_SPAWNS: i32 = 4; /// Max monsters per room
 
-/// Fills a room with stuff!
-pub fn spawn_room(map: &Map, rng: &mut RandomNumberGenerator, room: &Rect, map_depth: i32, spawn_list: &mut Vec<(usize, String)>) {
+/// Fills a room with stuff! Some rooms are given a theme, such as a barracks or a library, which
+/// biases what turns up in them.
+pub fn spawn_room(map: &Map, rng: &mut RandomNumberGenerator, room: &Rect, map_depth: i32, biome: &str, spawn_list: &mut Vec<(usize, String)>) {
     let mut possible_targets: Vec<usize> = Vec::new();
     { // Borrow scope - to keep access to the map separated
         for y in room.y1 + 1 .. room.y2 {
@@ -84,26 +81,67 @@ pub fn spawn_room(map: &Map, rng: &mut RandomNumberGenerator, room: &Rect, map_d
         }
     }
 
-    spawn_region(map, rng, &possible_targets, map_depth, spawn_list);
+    let mut spawn_table = room_table(map_depth, biome);
+    let mut extra_spawns = 0;
+    if let Some(theme) = get_room_theme(&RAWS.lock().unwrap(), rng, map_depth, biome) {
+        for spawn in theme.spawns.iter() {
+            spawn_table = spawn_table.add(&spawn.name, spawn.weight);
+        }
+        extra_spawns = theme.extra_spawns.unwrap_or(0);
+    }
+
+    spawn_from_table(map, rng, &possible_targets, map_depth, biome, &spawn_table, extra_spawns, spawn_list);
 }
 
-pub fn spawn_region(_map: &Map, rng: &mut RandomNumberGenerator, area: &[usize], map_depth: i32, spawn_list: &mut Vec<(usize, String)>) {
-    let spawn_table = room_table(map_depth);
+pub fn spawn_region(map: &Map, rng: &mut RandomNumberGenerator, area: &[usize], map_depth: i32, biome: &str, spawn_list: &mut Vec<(usize, String)>) {
+    let spawn_table = room_table(map_depth, biome);
+    spawn_from_table(map, rng, area, map_depth, biome, &spawn_table, 0, spawn_list);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_from_table(map: &Map, rng: &mut RandomNumberGenerator, area: &[usize], map_depth: i32, biome: &str,
+    spawn_table: &RandomTable, extra_spawns: i32, spawn_list: &mut Vec<(usize, String)>)
+{
     let mut spawn_points: HashMap<usize, String> = HashMap::new();
     let mut areas: Vec<usize> = Vec::from(area);
 
+    // Every so often, something turns up from deeper down than it should
+    let (out_of_depth_chance, out_of_depth_levels) = {
+        let raws = RAWS.lock().unwrap();
+        let rules = get_spawn_rules(&raws);
+        (rules.out_of_depth_chance, rules.out_of_depth_levels)
+    };
+    let deep_table = room_table(map_depth + out_of_depth_levels, biome);
+
     // Scope to keep the borrow checker happy
     {
         // Num spawns is a number between 0 and MAX_SPAWNS. The additions
         // make spawn numbers increase with depth, and also mean that sometimes nothing will spawn.
-        let num_spawns = i32::min(areas.len() as i32, rng.roll_dice(1, MAX_SPAWNS + 3) + (map_depth - 1) - 3);
+        let num_spawns = i32::min(areas.len() as i32, rng.roll_dice(1, MAX_SPAWNS + 3) + (map_depth - 1) - 3 + extra_spawns);
         if num_spawns == 0 { return; }
 
-        for _i in 0 .. num_spawns {
+        let mut spawned = 0;
+        while spawned < num_spawns && !areas.is_empty() {
             let array_index = if areas.len() == 1 { 0usize } else { (rng.roll_dice(1, areas.len() as i32)-1) as usize };
             let map_idx = areas[array_index];
-            spawn_points.insert(map_idx, spawn_table.roll(rng));
+            let spawn_name = if rng.roll_dice(1, 100) <= out_of_depth_chance {
+                deep_table.roll(rng)
+            } else {
+                spawn_table.roll(rng)
+            };
             areas.remove(array_index);
+            spawned += 1;
+
+            // Groups count every member towards the number of spawns, and keep the spaces closest
+            // to their leader free for the rest of the group. If there isn't room, nothing spawns.
+            if let Some(size) = spawn_group_size(&RAWS.lock().unwrap(), &spawn_name) {
+                if size > areas.len() + 1 { continue; }
+                let (x, y) = (map_idx as i32 % map.width, map_idx as i32 / map.width);
+                areas.sort_by_key(|idx| i32::max((*idx as i32 % map.width - x).abs(), (*idx as i32 / map.width - y).abs()));
+                areas.drain(0 .. size - 1);
+                spawned += size as i32 - 1;
+            }
+            spawn_points.insert(map_idx, spawn_name);
         }
     }
 
@@ -118,8 +156,20 @@ pub fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) {
     let width = map.width as usize;
     let x = (*spawn.0 % width) as i32;
     let y = (*spawn.0 / width) as i32;
+    let depth = map.depth;
     std::mem::drop(map);
 
+    // Items found lying around might be magical (or cursed!)
+    let item_result = spawn_generated_item(&RAWS.lock().unwrap(), ecs, spawn.1, SpawnType::AtPosition{x, y}, depth);
+    if item_result.is_some() {
+        return;
+    }
+
+    // Uniques who have already turned up this run are left out
+    if unique_already_met(&RAWS.lock().unwrap(), ecs, spawn.1) {
+        return;
+    }
+
     // Attempt to spawn using the rawmaster. If successful, bail early
     let spawn_result = spawn_named_entity(&RAWS.lock().unwrap(), ecs, &spawn.1, SpawnType::AtPosition{x, y});
     if spawn_result.is_some() {
@@ -130,6 +180,6 @@ pub fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) {
         rltk::console::log(format!("WARNING: We don't know how to spawn '{}'!", spawn.1));
     }
 }
-fn room_table(map_depth: i32) -> RandomTable {
-    get_spawn_table_for_depth(&RAWS.lock().unwrap(), map_depth)
+fn room_table(map_depth: i32, biome: &str) -> RandomTable {
+    get_spawn_table_for_depth(&RAWS.lock().unwrap(), map_depth, biome)
 }
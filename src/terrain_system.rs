@@ -0,0 +1,302 @@
+use specs::prelude::*;
+use rltk::{Point, Algorithm2D};
+use std::collections::HashMap;
+use super::{
+    Map, TileType, tile_walkable, tile_flammable, tile_wet, Position, Name, Pools, Viewshed, Burning,
+    Swimming, Poisoned, Heavy, Cursed, Equipped, InBackpack, SufferDamage, RunState, gamelog::GameLog,
+    particle_system::ParticleBuilder,
+};
+
+/// How many turns a tile burns for before it's reduced to ash.
+const FIRE_TURNS: i32 = 5;
+/// How likely (one in this many) fire is to spread to each flammable neighbour, each turn.
+const FIRE_SPREAD_CHANCE: i32 = 4;
+/// How long a creature keeps burning after leaving the flames.
+const BURN_TURNS: i32 = 4;
+/// Burning damage per turn, as a die size.
+const BURN_DAMAGE: i32 = 4;
+/// How thick a gas cloud can get in any one tile.
+const MAX_GAS_DENSITY: i32 = 9;
+/// How long breathing in gas keeps a creature poisoned.
+const GAS_POISON_TURNS: i32 = 3;
+/// How many turns a creature can swim before it starts to drown.
+const BREATH_TURNS: i32 = 8;
+/// Drowning damage per turn, as a die size.
+const DROWNING_DAMAGE: i32 = 6;
+
+/// Collects changes to the terrain made during a turn (fires started, gas released), for the
+/// terrain system to apply.
+pub struct TerrainBuilder {
+    fires: Vec<Point>,
+    gas: Vec<(Point, i32)>,
+}
+
+impl TerrainBuilder {
+    pub fn new() -> TerrainBuilder {
+        TerrainBuilder{ fires: Vec::new(), gas: Vec::new() }
+    }
+
+    /// Sets fire to (x, y), if there's anything there to burn.
+    pub fn ignite(&mut self, x: i32, y: i32) {
+        self.fires.push(Point::new(x, y));
+    }
+
+    /// Releases `density` worth of poison gas at (x, y).
+    pub fn gas(&mut self, x: i32, y: i32, density: i32) {
+        if density > 0 {
+            self.gas.push((Point::new(x, y), density));
+        }
+    }
+}
+
+/// The tiles a fire at `idx` can spread to: its neighbours in the four cardinal directions,
+/// leaving out any which would fall off the edge of the map.
+fn fire_neighbours(map: &Map, idx: usize) -> Vec<usize> {
+    let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+    [(-1, 0), (1, 0), (0, -1), (0, 1)].iter()
+        .map(|(dx, dy)| Point::new(x + dx, y + dy))
+        .filter(|p| map.in_bounds(*p))
+        .map(|p| map.xy_idx(p.x, p.y))
+        .collect()
+}
+
+/// Updates terrain which changes during play. Fire spreads across grass and wooden floors, and
+/// burns them down to ash; anything standing in it catches fire, until it burns out or reaches
+/// water. Gas drifts and thins out, poisoning whoever breathes it. Creatures in deep water have
+/// to swim, which means letting go of anything heavy (it sinks for good, unless a curse keeps it
+/// on), and drown if they stay in too long.
+///
+/// Requests are applied straight away, but the terrain only moves on once per turn.
+pub struct TerrainSystem {}
+
+impl<'a> System<'a> for TerrainSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, Map>,
+        WriteExpect<'a, TerrainBuilder>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Pools>,
+        WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, Burning>,
+        WriteStorage<'a, Swimming>,
+        WriteStorage<'a, Poisoned>,
+        ReadStorage<'a, Heavy>,
+        ReadStorage<'a, Cursed>,
+        WriteStorage<'a, Equipped>,
+        WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, SufferDamage>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities, player_entity, runstate, mut map, mut terrain_builder, positions, names,
+            pools, mut viewsheds, mut burning, mut swimming, mut poisoned, heavy, cursed, mut equipped,
+            mut backpack, mut suffer_damage, mut gamelog, mut particle_builder, mut rng,
+        ) = data;
+
+        let in_bounds = |map: &Map, x: i32, y: i32| x > 0 && x < map.width-1 && y > 0 && y < map.height-1;
+        let mut changed = false;
+
+        for fire in terrain_builder.fires.drain(..) {
+            if !in_bounds(&map, fire.x, fire.y) { continue; }
+            let idx = map.xy_idx(fire.x, fire.y);
+            if tile_flammable(map.tiles[idx]) && !map.fire.contains_key(&idx) {
+                map.fire.insert(idx, FIRE_TURNS);
+                changed = true;
+            }
+        }
+        for (pos, density) in terrain_builder.gas.drain(..) {
+            if !in_bounds(&map, pos.x, pos.y) { continue; }
+            let idx = map.xy_idx(pos.x, pos.y);
+            if tile_walkable(map.tiles[idx]) {
+                let thickness = map.gas.entry(idx).or_insert(0);
+                *thickness = i32::min(MAX_GAS_DENSITY, *thickness + density);
+                changed = true;
+            }
+        }
+
+        if *runstate == RunState::PlayerTurn {
+            // Fires spread to their neighbours, and eventually burn out
+            let mut spread: Vec<usize> = Vec::new();
+            let mut burnt_out: Vec<usize> = Vec::new();
+            for (idx, turns) in map.fire.iter_mut() {
+                *turns -= 1;
+                if *turns < 1 { burnt_out.push(*idx); }
+            }
+            for idx in map.fire.keys() {
+                spread.extend(fire_neighbours(&map, *idx));
+            }
+            for idx in burnt_out {
+                map.fire.remove(&idx);
+                map.tiles[idx] = TileType::Ash;
+            }
+            for idx in spread {
+                if tile_flammable(map.tiles[idx]) && !map.fire.contains_key(&idx) && rng.roll_dice(1, FIRE_SPREAD_CHANCE) == 1 {
+                    map.fire.insert(idx, FIRE_TURNS);
+                }
+            }
+
+            // Gas drifts into a neighbouring tile, thinning out as it goes
+            let mut drifted: HashMap<usize, i32> = HashMap::new();
+            for (idx, density) in map.gas.iter() {
+                let remaining = density - 1;
+                if remaining < 1 { continue; }
+                let (x, y) = (*idx as i32 % map.width, *idx as i32 / map.width);
+                let (dx, dy) = [(-1, 0), (1, 0), (0, -1), (0, 1)][rng.roll_dice(1, 4) as usize - 1];
+                let target = map.xy_idx(x + dx, y + dy);
+                let moved = if in_bounds(&map, x + dx, y + dy) && tile_walkable(map.tiles[target]) { remaining / 2 } else { 0 };
+                *drifted.entry(*idx).or_insert(0) += remaining - moved;
+                if moved > 0 {
+                    *drifted.entry(target).or_insert(0) += moved;
+                }
+            }
+            drifted.retain(|_, density| *density > 0);
+            for density in drifted.values_mut() {
+                *density = i32::min(MAX_GAS_DENSITY, *density);
+            }
+            changed = changed || !map.gas.is_empty() || !map.fire.is_empty();
+            map.gas = drifted;
+
+            // Then see what it does to anyone caught up in it
+            let mut sunk: Vec<Entity> = Vec::new();
+            let mut left_water: Vec<Entity> = Vec::new();
+            for (entity, pos, _pools) in (&entities, &positions, &pools).join() {
+                let idx = map.xy_idx(pos.x, pos.y);
+                let is_player = entity == *player_entity;
+                let name = names.get(entity).map_or("Something".to_string(), |n| n.name.clone());
+                let seen = map.visible_tiles[idx];
+
+                if map.fire.contains_key(&idx) && !burning.contains(entity) {
+                    burning.insert(entity, Burning{ turns: BURN_TURNS }).expect("Unable to insert status");
+                    if is_player {
+                        gamelog.entries.push("You catch fire!".to_string());
+                    } else if seen {
+                        gamelog.entries.push(format!("{} catches fire!", name));
+                    }
+                }
+
+                if map.gas.contains_key(&idx) {
+                    let turns = poisoned.get(entity).map_or(GAS_POISON_TURNS, |p| i32::max(p.turns, GAS_POISON_TURNS));
+                    poisoned.insert(entity, Poisoned{ damage: 1, turns }).expect("Unable to insert status");
+                    if is_player {
+                        gamelog.entries.push("You choke on the gas!".to_string());
+                    }
+                }
+
+                if let Some(burn) = burning.get_mut(entity) {
+                    if tile_wet(map.tiles[idx]) {
+                        burning.remove(entity);
+                        if is_player {
+                            gamelog.entries.push("You douse the flames.".to_string());
+                        } else if seen {
+                            gamelog.entries.push(format!("{} douses the flames.", name));
+                        }
+                    } else {
+                        let amount = rng.roll_dice(1, BURN_DAMAGE);
+                        SufferDamage::new_damage(&mut suffer_damage, entity, amount, false);
+                        particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('▲'), 200.0);
+                        if is_player {
+                            gamelog.entries.push(format!("You burn, for {} damage.", amount));
+                        }
+                        // Whoever's burning sets light to whatever they're standing on
+                        if tile_flammable(map.tiles[idx]) && !map.fire.contains_key(&idx) {
+                            map.fire.insert(idx, FIRE_TURNS);
+                        }
+                        burn.turns -= 1;
+                        if burn.turns < 1 && !map.fire.contains_key(&idx) {
+                            burning.remove(entity);
+                            if is_player {
+                                gamelog.entries.push("The flames go out.".to_string());
+                            }
+                        }
+                    }
+                }
+
+                if map.tiles[idx] == TileType::DeepWater {
+                    if let Some(swim) = swimming.get_mut(entity) {
+                        swim.turns += 1;
+                        if swim.turns > BREATH_TURNS {
+                            let amount = rng.roll_dice(1, DROWNING_DAMAGE);
+                            SufferDamage::new_damage(&mut suffer_damage, entity, amount, false);
+                            if is_player {
+                                gamelog.entries.push(format!("You are drowning, and take {} damage!", amount));
+                            }
+                        } else if is_player && swim.turns == BREATH_TURNS - 2 {
+                            gamelog.entries.push("You're tiring, and struggling to stay afloat.".to_string());
+                        }
+                    } else {
+                        swimming.insert(entity, Swimming{ turns: 0 }).expect("Unable to insert status");
+                        if is_player {
+                            gamelog.entries.push("You are swimming.".to_string());
+                        }
+                        // Nobody swims in plate; anything heavy has to go, unless it's cursed to stay on
+                        for (item, _heavy, item_name) in (&entities, &heavy, &names).join() {
+                            let worn = equipped.get(item).is_some_and(|e| e.owner == entity);
+                            let carried = backpack.get(item).is_some_and(|b| b.owner == entity);
+                            if worn && cursed.contains(item) {
+                                if is_player {
+                                    gamelog.entries.push(format!("Your cursed {} clings to you, dragging you down.", item_name.name));
+                                }
+                            } else if worn || carried {
+                                sunk.push(item);
+                                if is_player {
+                                    gamelog.entries.push(format!("You let go of your {}, and it sinks out of sight.", item_name.name));
+                                }
+                            }
+                        }
+                    }
+                } else if swimming.contains(entity) {
+                    left_water.push(entity);
+                }
+            }
+            for item in sunk {
+                equipped.remove(item);
+                backpack.remove(item);
+                entities.delete(item).expect("Unable to delete");
+            }
+            for entity in left_water {
+                swimming.remove(entity);
+            }
+        }
+
+        // Fire blocks the way and gas the view, so anything looking around needs to look again
+        if changed {
+            map.populate_blocked();
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fire_spreads_to_the_four_sides() {
+        let map = Map::new(1, 10, 10, "Test");
+        let mut neighbours = fire_neighbours(&map, map.xy_idx(5, 5));
+        neighbours.sort();
+        assert_eq!(neighbours, vec![map.xy_idx(5, 4), map.xy_idx(4, 5), map.xy_idx(6, 5), map.xy_idx(5, 6)]);
+    }
+
+    #[test]
+    fn fire_doesnt_wrap_around_the_edges() {
+        let map = Map::new(1, 10, 10, "Test");
+        let mut neighbours = fire_neighbours(&map, map.xy_idx(0, 0));
+        neighbours.sort();
+        assert_eq!(neighbours, vec![map.xy_idx(1, 0), map.xy_idx(0, 1)]);
+
+        let mut neighbours = fire_neighbours(&map, map.xy_idx(9, 9));
+        neighbours.sort();
+        assert_eq!(neighbours, vec![map.xy_idx(9, 8), map.xy_idx(8, 9)]);
+    }
+}
@@ -0,0 +1,126 @@
+use specs::prelude::*;
+use rltk::Point;
+use super::{
+    Hidden, EntryTrigger, Disarmable, Position, EntityMoved, Viewshed, Map, Name, Attributes, Skills, BlocksTile,
+    Skill, SkillUsed, skill_check, gamelog::GameLog,
+};
+use crate::raws::{RAWS, SpawnType, spawn_named_item};
+
+/// How far from the player a search reaches.
+const SEARCH_RADIUS: f32 = 3.0;
+
+/// Searching on purpose is a good deal easier than spotting something in passing.
+const SEARCH_DIFFICULTY: i32 = 12;
+
+/// Searches the tiles the player can see close by for anything hidden, with a Perception check
+/// for each. Always takes a turn.
+pub fn search(ecs: &mut World) {
+    let player = *ecs.fetch::<Entity>();
+    let ppos = *ecs.fetch::<Point>();
+    let found: Vec<String> = {
+        let entities = ecs.entities();
+        let mut hidden = ecs.write_storage::<Hidden>();
+        let positions = ecs.read_storage::<Position>();
+        let names = ecs.read_storage::<Name>();
+        let attributes = ecs.read_storage::<Attributes>();
+        let skills = ecs.read_storage::<Skills>();
+        let map = ecs.fetch::<Map>();
+        let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+
+        let nearby: Vec<Entity> = (&entities, &hidden, &positions).join()
+            .filter(|(_, _, pos)| map.visible_tiles[map.xy_idx(pos.x, pos.y)]
+                && rltk::DistanceAlg::Pythagoras.distance2d(ppos, Point::new(pos.x, pos.y)) <= SEARCH_RADIUS)
+            .map(|(e, _, _)| e)
+            .collect();
+        let mut found = Vec::new();
+        for e in nearby {
+            let spotted = match (attributes.get(player), skills.get(player)) {
+                (Some(attr), Some(sk)) => skill_check(&mut rng, Skill::Perception, attr, sk, SEARCH_DIFFICULTY),
+                _ => false
+            };
+            if spotted {
+                hidden.remove(e);
+                found.push(names.get(e).map_or("something".to_string(), |n| n.name.clone()));
+            }
+        }
+        found
+    };
+
+    let mut log = ecs.fetch_mut::<GameLog>();
+    if found.is_empty() {
+        log.entries.push("You search the area, but find nothing.".to_string());
+        return;
+    }
+    SkillUsed::new_use(&mut ecs.write_storage::<SkillUsed>(), player, Skill::Perception);
+    for name in found {
+        log.entries.push(format!("You find a {}!", name));
+    }
+}
+
+/// Finds a trap next to the player which they know about, and could disarm.
+pub fn known_trap_near(ecs: &World) -> Option<Entity> {
+    let ppos = *ecs.fetch::<Point>();
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let triggers = ecs.read_storage::<EntryTrigger>();
+    let disarmable = ecs.read_storage::<Disarmable>();
+
+    (&entities, &positions, &triggers, &disarmable, !&hidden).join()
+        .filter(|(_, pos, _, _, _)| (pos.x - ppos.x).abs() <= 1 && (pos.y - ppos.y).abs() <= 1)
+        .map(|(e, _, _, _, _)| e)
+        .next()
+}
+
+/// Tries to disarm a trap next to the player with a Lockpicking check. A disarmed trap is gone
+/// for good, leaving behind any parts worth salvaging; botching it badly sets the trap off, if
+/// there's room to stumble onto it.
+pub fn disarm_trap(ecs: &mut World, trap: Entity) {
+    let player = *ecs.fetch::<Entity>();
+    let Some(disarm) = ecs.read_storage::<Disarmable>().get(trap).cloned() else { return; };
+    let Some(trap_pos) = ecs.read_storage::<Position>().get(trap).cloned() else { return; };
+    let name = ecs.read_storage::<Name>().get(trap).map_or("trap".to_string(), |n| n.name.clone());
+
+    let (disarmed, botched) = {
+        let attributes = ecs.read_storage::<Attributes>();
+        let skills = ecs.read_storage::<Skills>();
+        let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+        SkillUsed::new_use(&mut ecs.write_storage::<SkillUsed>(), player, Skill::Lockpicking);
+        let disarmed = match (attributes.get(player), skills.get(player)) {
+            (Some(attributes), Some(skills)) => skill_check(&mut rng, Skill::Lockpicking, attributes, skills, disarm.difficulty),
+            _ => false
+        };
+        (disarmed, !disarmed && rng.roll_dice(1, 4) == 1)
+    };
+
+    if disarmed {
+        ecs.fetch_mut::<GameLog>().entries.push(format!("You disarm the {}.", name));
+        ecs.delete_entity(trap).expect("Unable to delete trap");
+        if let Some(parts) = disarm.parts {
+            spawn_named_item(&RAWS.lock().unwrap(), ecs, &parts, SpawnType::AtPosition{ x: trap_pos.x, y: trap_pos.y });
+            ecs.fetch_mut::<GameLog>().entries.push(format!("You salvage a {}.", parts));
+        }
+    } else if botched && can_stumble_onto(ecs, &trap_pos) {
+        // Fumbling with it, the player stumbles onto the trap; the trigger system does the rest
+        ecs.fetch_mut::<GameLog>().entries.push(format!("You fumble, and set off the {}!", name));
+        if let Some(pos) = ecs.write_storage::<Position>().get_mut(player) {
+            pos.x = trap_pos.x;
+            pos.y = trap_pos.y;
+        }
+        *ecs.write_resource::<Point>() = Point::new(trap_pos.x, trap_pos.y);
+        if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(player) {
+            viewshed.dirty = true;
+        }
+        ecs.write_storage::<EntityMoved>().insert(player, EntityMoved{}).expect("Unable to insert marker");
+    } else {
+        ecs.fetch_mut::<GameLog>().entries.push(format!("You fail to disarm the {}.", name));
+    }
+}
+
+/// Whether there's room for the player to stumble onto a trap, with nothing else in the way.
+fn can_stumble_onto(ecs: &World, trap_pos: &Position) -> bool {
+    let map = ecs.fetch::<Map>();
+    let idx = map.xy_idx(trap_pos.x, trap_pos.y);
+    let blocks = ecs.read_storage::<BlocksTile>();
+    !map.blocked[idx] && !map.tile_content[idx].iter().any(|e| blocks.contains(*e))
+}
@@ -1,17 +1,21 @@
 use specs::prelude::*;
+use rltk::Point;
 use super::{
     EntityMoved, Position, EntryTrigger, Hidden, Map, Name, gamelog::GameLog,
     InflictsDamage, particle_system::ParticleBuilder, SufferDamage,
-    SingleActivation, Renderable,
+    SingleActivation, Renderable, TeleportTrap, Alarm, Pit, PoisonGas, Ensnares,
+    Rooted, Poisoned, Pools, Viewshed, RunState, tile_walkable, noise_system::NoiseBuilder,
+    terrain_system::TerrainBuilder,
 };
 
 pub struct TriggerSystem{}
 
 impl<'a> System<'a> for TriggerSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         ReadExpect<'a, Map>,
         WriteStorage<'a, EntityMoved>,
-        ReadStorage<'a, Position>,
+        WriteStorage<'a, Position>,
         WriteStorage<'a, EntryTrigger>,
         WriteStorage<'a, Hidden>,
         ReadStorage<'a, Name>,
@@ -22,50 +26,128 @@ impl<'a> System<'a> for TriggerSystem {
         WriteStorage<'a, SufferDamage>,
         WriteStorage<'a, SingleActivation>,
         WriteStorage<'a, Renderable>,
+        (ReadStorage<'a, TeleportTrap>, ReadStorage<'a, Alarm>, ReadStorage<'a, Pit>, ReadStorage<'a, PoisonGas>, ReadStorage<'a, Ensnares>),
+        WriteStorage<'a, Rooted>,
+        WriteStorage<'a, Poisoned>,
+        ReadStorage<'a, Pools>,
+        WriteStorage<'a, Viewshed>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, Point>,
+        WriteExpect<'a, RunState>,
+        WriteExpect<'a, NoiseBuilder>,
+        WriteExpect<'a, TerrainBuilder>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
-            map, mut entity_moved, positions, mut entry_triggers, mut hidden,
+            map, mut entity_moved, mut positions, mut entry_triggers, mut hidden,
             names, entities, mut gamelog, inflicts_damage, mut particle_builder,
             mut inflict_damage, mut single_activation, mut renderable,
+            (teleport_traps, alarms, pits, poison_gas, ensnares),
+            mut rooted, mut poisoned, pools, mut viewsheds, player_entity, mut player_pos,
+            mut runstate, mut noise_builder, mut terrain_builder, mut rng,
         ) = data;
 
-        let mut deactivate_entities: Vec<Entity> = Vec::new();
-        // For each entity which moved, look at its final position
-        for (entity, mut _entity_moved, pos) in (&entities, &mut entity_moved, &positions).join() {
+        // For each entity which moved, look at its final position for anything it set off.
+        let mut triggered: Vec<(Entity, Entity, Point)> = Vec::new();
+        for (entity, _entity_moved, pos) in (&entities, &entity_moved, &positions).join() {
             let idx = map.xy_idx(pos.x, pos.y);
-            // Iterate through all other entities on that tile to look for triggered entities.
-            for entity_id in map.tile_content[idx].iter() {
-                if entity != *entity_id { // Don't bother to check whether you are a trap.
-                    let maybe_trigger = entry_triggers.get(*entity_id);
-                    match maybe_trigger {
-                        None => {},
-                        Some(_trigger) => {
-                            // We triggered it!
-                            let name = names.get(*entity_id);
-                            if let Some(name) = name {
-                                gamelog.entries.push(format!("{} triggers!", &name.name));
-                            }
+            for trap in map.tile_content[idx].iter().filter(|e| **e != entity && entry_triggers.contains(**e)) {
+                // Only the player is heavy enough to open a trapdoor; anything else just walks over it
+                if pits.contains(*trap) && entity != *player_entity { continue; }
+                triggered.push((entity, *trap, Point::new(pos.x, pos.y)));
+            }
+        }
+
+        let mut deactivate_entities: Vec<Entity> = Vec::new();
+        for (victim, trap, pos) in triggered {
+            // We triggered it!
+            if let Some(name) = names.get(trap) {
+                gamelog.entries.push(format!("{} triggers!", &name.name));
+            }
+            hidden.remove(trap); // The trap is no longer hidden.
+            let victim_name = names.get(victim).map_or("Something".to_string(), |n| n.name.clone());
+            let is_player = victim == *player_entity;
 
-                            hidden.remove(*entity_id); // The trap is no longer hidden.
+            // If the trap is damaging, inflict damage
+            if let Some(damages) = inflicts_damage.get(trap) {
+                particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 200.0);
+                SufferDamage::new_damage(&mut inflict_damage, victim, damages.damage, false);
+            }
 
-                            // If the trap is damaging, inflict damage
-                            let damages = inflicts_damage.get(*entity_id);
-                            if let Some(damages) = damages {
-                                particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 200.0);
-                                SufferDamage::new_damage(&mut inflict_damage, entity, damages.damage, false);
-                            }
+            // Alarms wake up everything in earshot
+            if let Some(alarm) = alarms.get(trap) {
+                gamelog.entries.push("A bell clangs loudly!".to_string());
+                particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::YELLOW), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('!'), 400.0);
+                noise_builder.request(pos.x, pos.y, alarm.volume, victim);
+            }
 
-                            // If it is a single activation, mark it for deactivation
-                            let sa = single_activation.get(*entity_id);
-                            if let Some(_sa) = sa {
-                                deactivate_entities.push(*entity_id);
-                            }
+            // Gas poisons everyone nearby, not just the victim, and lingers for a while
+            if let Some(gas) = poison_gas.get(trap) {
+                gamelog.entries.push("A cloud of poison gas billows out!".to_string());
+                let in_cloud = |x: i32, y: i32| rltk::DistanceAlg::Pythagoras.distance2d(pos, Point::new(x, y)) <= gas.radius as f32;
+                for y in pos.y - gas.radius ..= pos.y + gas.radius {
+                    for x in pos.x - gas.radius ..= pos.x + gas.radius {
+                        if x > 0 && x < map.width-1 && y > 0 && y < map.height-1 && in_cloud(x, y) && tile_walkable(map.tiles[map.xy_idx(x, y)]) {
+                            particle_builder.request(x, y, rltk::RGB::named(rltk::GREEN), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('░'), 300.0);
+                            terrain_builder.gas(x, y, gas.radius + 1);
+                        }
+                    }
+                }
+                for (entity, _pools, p) in (&entities, &pools, &positions).join().filter(|(_, _, p)| in_cloud(p.x, p.y)) {
+                    let turns = poisoned.get(entity).map_or(gas.turns, |p| i32::max(p.turns, gas.turns));
+                    poisoned.insert(entity, Poisoned{ damage: gas.damage, turns }).expect("Unable to insert status");
+                    if map.visible_tiles[map.xy_idx(p.x, p.y)] {
+                        if let Some(name) = names.get(entity) {
+                            gamelog.entries.push(format!("{} is poisoned!", name.name));
                         }
-                    }    
+                    }
                 }
             }
+
+            // Nets hold the victim in place
+            if let Some(net) = ensnares.get(trap) {
+                let turns = rooted.get(victim).map_or(net.turns, |r| i32::max(r.turns, net.turns));
+                rooted.insert(victim, Rooted{ turns }).expect("Unable to insert status");
+                gamelog.entries.push(format!("{} is caught in a net!", victim_name));
+            }
+
+            // Teleporters send the victim to a random free spot on the level
+            if teleport_traps.contains(trap) {
+                let free: Vec<usize> = (0 .. map.tiles.len())
+                    .filter(|idx| tile_walkable(map.tiles[*idx]) && !map.blocked[*idx] && map.tile_content[*idx].is_empty())
+                    .collect();
+                if !free.is_empty() {
+                    let idx = free[rng.roll_dice(1, free.len() as i32) as usize - 1];
+                    if let Some(victim_pos) = positions.get_mut(victim) {
+                        victim_pos.x = idx as i32 % map.width;
+                        victim_pos.y = idx as i32 / map.width;
+                    }
+                    if let Some(viewshed) = viewsheds.get_mut(victim) {
+                        viewshed.dirty = true;
+                    }
+                    if is_player {
+                        player_pos.x = idx as i32 % map.width;
+                        player_pos.y = idx as i32 / map.width;
+                        gamelog.entries.push("The world lurches around you!".to_string());
+                    } else {
+                        gamelog.entries.push(format!("{} vanishes in a flash of light!", victim_name));
+                    }
+                    particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::MAGENTA), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('☼'), 200.0);
+                }
+            }
+
+            // Pits drop the player to the next level
+            if pits.contains(trap) && is_player {
+                gamelog.entries.push("The floor gives way beneath you!".to_string());
+                *runstate = RunState::NextLevel;
+            }
+
+            // If it is a single activation, mark it for deactivation
+            if single_activation.contains(trap) {
+                deactivate_entities.push(trap);
+            }
         }
 
         // Deactivate any single use traps
@@ -81,4 +163,4 @@ impl<'a> System<'a> for TriggerSystem {
         // Remove all entity movement markers.
         entity_moved.clear();
     }
-}
\ No newline at end of file
+}
@@ -1,10 +1,14 @@
 use specs::prelude::*;
 use super::{
     Viewshed, Position, Map, Player, Hidden, gamelog::GameLog, Name,
-    BlocksVisibility,
+    BlocksVisibility, Attributes, Skills, Skill, SkillUsed, skill_check, Sneaking, NoticedPlayer,
+    clock_system::WorldClock,
 };
 use rltk::{field_of_view, Point};
 
+/// How hard it is to spot something hidden in plain view.
+const SPOT_DIFFICULTY: i32 = 22;
+
 pub struct VisibilitySystem{}
 
 impl<'a> System<'a> for VisibilitySystem {
@@ -19,12 +23,19 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteExpect<'a, GameLog>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, BlocksVisibility>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
+        WriteStorage<'a, SkillUsed>,
+        ReadStorage<'a, Sneaking>,
+        WriteStorage<'a, NoticedPlayer>,
+        ReadExpect<'a, WorldClock>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
             mut map, entities, mut viewshed, pos, player, mut hidden, mut rng,
-            mut log, names, blocks_visibility
+            mut log, names, blocks_visibility, attributes, skills, mut skill_used, sneaking,
+            mut noticed, clock
         ) = data;
 
         map.view_blocked.clear();
@@ -34,40 +45,63 @@ impl<'a> System<'a> for VisibilitySystem {
         }
 
         for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
+            let refreshed = viewshed.dirty;
             if viewshed.dirty {
                 viewshed.dirty = false;
                 viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
                 viewshed.visible_tiles.retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height );
+            }
 
-                // If this is the player, reveal what they can see
-                let _p: Option<&Player> = player.get(ent);
-                if let Some(_p) = _p {
-                    for t in map.visible_tiles.iter_mut() { *t = false }; // Clear the list of visible tiles.
-                    for vis in viewshed.visible_tiles.iter() {
-                        let idx = map.xy_idx(vis.x, vis.y);
-                        map.revealed_tiles[idx] = true;
-                        map.visible_tiles[idx] = true;
+            // If this is the player, reveal what they can see. Creatures see in the dark, but the
+            // player needs light to see by, other than right next to them; and since lights move
+            // around, that has to be checked every time.
+            let _p: Option<&Player> = player.get(ent);
+            if let Some(_p) = _p {
+                let me = Point::new(pos.x, pos.y);
+                for t in map.visible_tiles.iter_mut() { *t = false }; // Clear the list of visible tiles.
+                for vis in viewshed.visible_tiles.iter() {
+                    let idx = map.xy_idx(vis.x, vis.y);
+                    let within_reach = rltk::DistanceAlg::Pythagoras.distance2d(me, *vis) < 1.5;
+                    if !map.is_lit(idx) && !within_reach { continue; }
+                    map.revealed_tiles[idx] = true;
+                    map.visible_tiles[idx] = true;
+                    if !refreshed { continue; }
 
-                        // Chance to reveal hidden things
-                        for e in map.tile_content[idx].iter() {
-                            // Iterate through each entity on each tile
-                            let maybe_hidden = hidden.get(*e);
-                            if let Some(_maybe_hidden) = maybe_hidden {
-                                // If the entity is hidden, roll to see if it gets revealed.
-                                if rng.roll_dice(1, 24) == 1 {
-                                    let name = names.get(*e);
-                                    if let Some(name) = name {
-                                        log.entries.push(format!("You spotted a {}.", &name.name));
-                                    }
-                                    hidden.remove(*e);
+                    // Chance to reveal hidden things
+                    for e in map.tile_content[idx].iter() {
+                        // Iterate through each entity on each tile
+                        let maybe_hidden = hidden.get(*e);
+                        if let Some(_maybe_hidden) = maybe_hidden {
+                            // If the entity is hidden, make a perception check to see if it gets revealed.
+                            let spotted = match (attributes.get(ent), skills.get(ent)) {
+                                (Some(attr), Some(sk)) => skill_check(&mut rng, Skill::Perception, attr, sk, SPOT_DIFFICULTY),
+                                _ => rng.roll_dice(1, 24) == 1
+                            };
+                            if spotted {
+                                SkillUsed::new_use(&mut skill_used, ent, Skill::Perception);
+                                let name = names.get(*e);
+                                if let Some(name) = name {
+                                    log.entries.push(format!("You spotted a {}.", &name.name));
                                 }
+                                hidden.remove(*e);
                             }
                         }
                     }
                 }
-
             }
+        }
 
+        // Anyone who can see the player notices them straight away, unless they're sneaking. A
+        // sneaking player has to be spotted, which creatures try on their own turn.
+        let visible_player = (&entities, &player, &pos).join()
+            .find(|(e, _, _)| !sneaking.contains(*e))
+            .map(|(e, _, p)| (e, Point::new(p.x, p.y)));
+        if let Some((player_entity, player_pos)) = visible_player {
+            for (ent, viewshed) in (&entities, &viewshed).join() {
+                if ent != player_entity && viewshed.visible_tiles.contains(&player_pos) {
+                    noticed.insert(ent, NoticedPlayer{ turn: clock.turn }).expect("Unable to insert noticed");
+                }
+            }
         }
     }
-}
\ No newline at end of file
+}